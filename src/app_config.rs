@@ -13,6 +13,10 @@ pub struct AppConfig {
 
     #[serde(default)]
     pub accounts: BTreeMap<String, AccountConfig>,
+
+    /// Saved queries (`@name` in query sugar), shared by all accounts.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub queries: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -35,6 +39,59 @@ pub struct AccountConfig {
 
     #[serde(rename = "trustRedirectHosts", default)]
     pub trust_redirect_hosts: Vec<String>,
+
+    /// IANA timezone for bare dates in queries, e.g. Asia/Tokyo (default: UTC).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Saved queries for this account; override global ones with the same name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub queries: BTreeMap<String, String>,
+
+    /// What sending does to the sent email (default: move it from Drafts to Sent).
+    #[serde(rename = "afterSend", default, skip_serializing_if = "Option::is_none")]
+    pub after_send: Option<AfterSend>,
+}
+
+/// Applied by the server once a submission succeeds, in the same `EmailSubmission/set` call
+/// (`onSuccessUpdateEmail` / `onSuccessDestroyEmail`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AfterSend {
+    /// Drafts -> Sent, drop `$draft`, set `$seen`.
+    #[default]
+    Move,
+    /// Add to Sent but keep the Drafts copy; drop `$draft`, set `$seen`.
+    Copy,
+    /// No mailbox change (e.g. the server files sent mail itself); drop `$draft`, set `$seen`.
+    Keep,
+    /// Destroy the email (the server keeps its own copy of sent mail).
+    Destroy,
+    /// Leave the email untouched.
+    None,
+}
+
+impl AfterSend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AfterSend::Move => "move",
+            AfterSend::Copy => "copy",
+            AfterSend::Keep => "keep",
+            AfterSend::Destroy => "destroy",
+            AfterSend::None => "none",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "move" => Some(AfterSend::Move),
+            "copy" => Some(AfterSend::Copy),
+            "keep" => Some(AfterSend::Keep),
+            "destroy" => Some(AfterSend::Destroy),
+            "none" => Some(AfterSend::None),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -203,6 +260,9 @@ pub fn ensure_minimal_fastmail_config() -> Result<(AppConfig, PathBuf), XinError
                 "jmap.fastmail.com".to_string(),
                 "fastmail.com".to_string(),
             ],
+            timezone: None,
+            queries: BTreeMap::new(),
+            after_send: None,
         },
     );
 
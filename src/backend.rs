@@ -22,13 +22,120 @@ pub struct ThreadResult {
 
 pub struct Backend {
     j: XinJmap,
+    tz: Result<chrono_tz::Tz, XinErrorOut>,
+    saved_queries: std::collections::BTreeMap<String, String>,
+    after_send: crate::app_config::AfterSend,
+}
+
+/// What the server does to the email once its submission succeeds.
+#[derive(Debug, Clone)]
+pub enum OnSuccess {
+    Nothing,
+    /// `onSuccessUpdateEmail` patch.
+    Update(Value),
+    /// `onSuccessDestroyEmail`.
+    Destroy,
+}
+
+/// A created EmailSubmission.
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub id: String,
+    pub send_at: Option<String>,
+    pub undo_status: Option<String>,
+    /// SetError when the implicit `Email/set` (on-success update/destroy) failed.
+    pub email_error: Option<Value>,
 }
 
 #[derive(Debug, Clone)]
 pub struct UploadedBlob {
     pub blob_id: String,
     pub content_type: String,
-    pub size: usize,
+    /// `None` for a bare `--attach-blob <blobId>` reference, whose size is not known.
+    pub size: Option<usize>,
+    pub name: Option<String>,
+    /// Content-ID of an inline image referenced from the HTML body (`cid:`); `None` for
+    /// regular attachments.
+    pub cid: Option<String>,
+}
+
+/// One page of `Backend::query_email_page`.
+#[derive(Debug, Clone)]
+pub struct EmailPage {
+    /// Number of ids the query returned; emails destroyed before `Email/get` are missing
+    /// from `emails`.
+    pub ids: usize,
+    pub total: Option<u64>,
+    pub emails: Vec<Value>,
+}
+
+/// Walks every page of `Backend::query_email_page` for one filter and sort.
+pub struct EmailPager {
+    filter: Value,
+    sort: Vec<Value>,
+    limit: usize,
+    properties: &'static [&'static str],
+    position: i32,
+    done: bool,
+}
+
+impl EmailPager {
+    pub fn new(
+        filter: Value,
+        sort: Vec<Value>,
+        limit: usize,
+        properties: &'static [&'static str],
+    ) -> Self {
+        Self {
+            filter,
+            sort,
+            limit,
+            properties,
+            position: 0,
+            done: false,
+        }
+    }
+
+    /// The next page, or `None` once every matching email has been returned.
+    pub async fn next_page(&mut self, backend: &Backend) -> Result<Option<EmailPage>, XinErrorOut> {
+        if self.done {
+            return Ok(None);
+        }
+        let page = backend
+            .query_email_page(
+                self.filter.clone(),
+                &self.sort,
+                self.position,
+                self.limit,
+                self.properties,
+            )
+            .await?;
+
+        self.position += page.ids as i32;
+        self.done = page.ids == 0
+            || match page.total {
+                Some(total) => self.position as u64 >= total,
+                None => page.ids < self.limit,
+            };
+        Ok(Some(page))
+    }
+}
+
+/// Server upload limits (see `Backend::upload_limits`): sizes are checked before
+/// uploading, `max_concurrent_upload` bounds parallel uploads.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UploadLimits {
+    pub max_size_upload: Option<u64>,
+    pub max_message_size: Option<u64>,
+    pub max_concurrent_upload: Option<usize>,
+}
+
+/// A local file for `Backend::upload_files`.
+#[derive(Debug, Clone)]
+pub struct UploadFile<'a> {
+    pub path: &'a std::path::Path,
+    pub size: u64,
+    pub content_type: &'a str,
     pub name: Option<String>,
 }
 
@@ -45,20 +152,117 @@ impl Backend {
     pub async fn connect(account: Option<&str>) -> Result<Self, XinErrorOut> {
         let resolved = crate::config::resolve_runtime_config(account)?;
         let j = XinJmap::connect(&resolved.config).await?;
-        Ok(Self { j })
+        Ok(Self {
+            j,
+            tz: resolved.config.timezone,
+            saved_queries: resolved.config.saved_queries,
+            after_send: resolved.config.after_send,
+        })
     }
 
-    pub async fn download_blob(&self, blob_id: &str) -> Result<Vec<u8>, XinErrorOut> {
+    /// Account `afterSend` policy (`XIN_AFTER_SEND` / config).
+    pub fn after_send(&self) -> crate::app_config::AfterSend {
+        self.after_send
+    }
+
+    /// Timezone for bare dates in query sugar (`--tz` / `XIN_TZ` / account `timezone`); an
+    /// error when the setting is invalid.
+    pub fn timezone(&self) -> Result<chrono_tz::Tz, XinErrorOut> {
+        self.tz.clone()
+    }
+
+    /// Saved queries available to `@name` in query sugar.
+    pub fn saved_queries(&self) -> &std::collections::BTreeMap<String, String> {
+        &self.saved_queries
+    }
+
+    /// WebSocket push endpoint (RFC 8887), if the session advertises one with push support.
+    pub fn websocket_push_url(&self) -> Option<String> {
         self.j
             .client()
-            .download(blob_id)
-            .await
-            .map_err(|e| XinErrorOut {
-                kind: "httpError".to_string(),
-                message: format!("download failed: {e}"),
-                http: None,
-                jmap: None,
-            })
+            .session()
+            .websocket_capabilities()
+            .filter(|ws| ws.supports_push())
+            .map(|ws| ws.url().to_string())
+    }
+
+    /// Open a WebSocket and subscribe to StateChange push for the given data types.
+    ///
+    /// Returns `Ok(None)` when the server does not advertise WebSocket push; callers should
+    /// fall back to polling.
+    pub async fn connect_websocket_push(
+        &self,
+        data_types: &[&str],
+        ping_secs: u64,
+    ) -> Result<Option<crate::push::WebSocketPush>, XinErrorOut> {
+        let Some(url) = self.websocket_push_url() else {
+            return Ok(None);
+        };
+
+        let client = self.j.client();
+        crate::push::WebSocketPush::connect(
+            &url,
+            client.headers(),
+            client.default_account_id(),
+            data_types,
+            ping_secs,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// EventSource push endpoint (RFC 8620 §7.3), if the session advertises one.
+    pub fn event_source_url(&self) -> Option<String> {
+        let url = self.j.client().session().event_source_url();
+        (!url.trim().is_empty()).then(|| url.to_string())
+    }
+
+    /// Open the EventSource stream for the given data types.
+    ///
+    /// Returns `Ok(None)` when the session has no `eventSourceUrl`.
+    pub async fn connect_event_source_push(
+        &self,
+        data_types: &[&str],
+        ping_secs: u64,
+    ) -> Result<Option<crate::push::EventSourcePush>, XinErrorOut> {
+        let Some(url) = self.event_source_url() else {
+            return Ok(None);
+        };
+
+        let client = self.j.client();
+        crate::push::EventSourcePush::connect(
+            &url,
+            client.headers(),
+            client.default_account_id(),
+            data_types,
+            ping_secs,
+        )
+        .await
+        .map(Some)
+    }
+
+    /// Stream a blob to `out`, resuming an interrupted download of the same blob to the same
+    /// path, with progress reported to `progress`. `name` fills the `downloadUrl` template.
+    pub async fn download_blob_to(
+        &self,
+        blob_id: &str,
+        name: &str,
+        out: &std::path::Path,
+        progress: &mut crate::transfer::Progress,
+    ) -> Result<Download, XinErrorOut> {
+        let client = self.j.client();
+        let url = crate::transfer::expand_url_template(
+            client.session().download_url(),
+            &[
+                ("accountId", client.default_account_id()),
+                ("blobId", blob_id),
+                ("name", name),
+                ("type", "application/octet-stream"),
+            ],
+        );
+        let http = transfer_http(client.headers(), client.timeout())?;
+        let part = crate::transfer::part_path(out, blob_id);
+        download_to(&http, &url, out, &part, progress).await
     }
 
     pub async fn get_email(
@@ -266,30 +470,36 @@ impl Backend {
         }))
     }
 
-    /// Search using a raw JMAP filter JSON value (pass-through).
+    /// `Email/query` arguments used by [`Backend::search_raw_filter_json`].
     ///
-    /// This avoids xin enforcing a whitelist of filter fields at the CLI layer.
-    pub async fn search_raw_filter_json(
+    /// With an `anchor`, the window starts right after that email id (`anchorOffset: 1`)
+    /// and the server ignores `position`, so we omit it.
+    pub fn email_query_args(
         &self,
         filter_json: Value,
         position: i32,
+        anchor: Option<&str>,
         limit: usize,
         collapse_threads: bool,
-        is_ascending: bool,
-    ) -> Result<SearchResult, XinErrorOut> {
-        let client = self.j.client();
-        let session = client.session();
-        let api_url = session.api_url().to_string();
-        let account_id = client.default_account_id().to_string();
+        sort: &[Value],
+    ) -> Value {
+        let account_id = self.j.client().default_account_id().to_string();
 
         let mut query_args = json!({
             "accountId": account_id,
-            "sort": [{"property": "receivedAt", "isAscending": is_ascending}],
+            "sort": sort,
             "collapseThreads": collapse_threads,
             "position": position,
             "limit": limit
         });
 
+        if let Some(anchor) = anchor {
+            let obj = query_args.as_object_mut().expect("query args object");
+            obj.remove("position");
+            obj.insert("anchor".to_string(), json!(anchor));
+            obj.insert("anchorOffset".to_string(), json!(1));
+        }
+
         // JMAP requires `filter` to be an object; we still pass through as-is and
         // let the server validate. However, omit empty object to match previous
         // behavior (no filter).
@@ -300,6 +510,102 @@ impl Backend {
                 .insert("filter".to_string(), filter_json);
         }
 
+        query_args
+    }
+
+    /// One page of a flat (`collapseThreads: false`) `Email/query`, with `Email/get` of the
+    /// raw `properties` for its ids. For commands that walk every matching email.
+    pub async fn query_email_page(
+        &self,
+        filter_json: Value,
+        sort: &[Value],
+        position: i32,
+        limit: usize,
+        properties: &[&str],
+    ) -> Result<EmailPage, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+        let query_args = self.email_query_args(filter_json, position, None, limit, false, sort);
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("Email"),
+                json!([
+                    ["Email/query", query_args, "q0"],
+                    ["Email/get", {
+                        "accountId": account_id,
+                        "#ids": {"resultOf": "q0", "name": "Email/query", "path": "/ids"},
+                        "properties": properties
+                    }, "g0"]
+                ]),
+            )
+            .await?;
+
+        let query = mrs
+            .iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("Email/query"))
+            .and_then(|mr| mr.get(1))
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: "missing Email/query response".to_string(),
+                http: None,
+                jmap: None,
+            })?;
+        let ids = query
+            .get("ids")
+            .and_then(|v| v.as_array())
+            .map_or(0, |a| a.len());
+        let total = query.get("total").and_then(|v| v.as_u64());
+
+        Ok(EmailPage {
+            ids,
+            total,
+            emails: take_list(mrs, "Email/get"),
+        })
+    }
+
+    /// `Email/get` for `ids` with the given properties, as raw JSON (missing ids are
+    /// skipped).
+    pub async fn get_emails_raw(
+        &self,
+        ids: &[String],
+        properties: &[&str],
+    ) -> Result<Vec<Value>, XinErrorOut> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let account_id = self.j.client().default_account_id().to_string();
+        let mrs = self
+            .raw_call(
+                &Self::using_for("Email"),
+                json!([["Email/get", {
+                    "accountId": account_id,
+                    "ids": ids,
+                    "properties": properties
+                }, "g0"]]),
+            )
+            .await?;
+        Ok(take_list(mrs, "Email/get"))
+    }
+
+    /// Search using a raw JMAP filter JSON value (pass-through).
+    ///
+    /// This avoids xin enforcing a whitelist of filter fields at the CLI layer.
+    pub async fn search_raw_filter_json(
+        &self,
+        filter_json: Value,
+        position: i32,
+        anchor: Option<&str>,
+        limit: usize,
+        collapse_threads: bool,
+        sort: &[Value],
+    ) -> Result<SearchResult, XinErrorOut> {
+        let client = self.j.client();
+        let session = client.session();
+        let api_url = session.api_url().to_string();
+
+        let query_args =
+            self.email_query_args(filter_json, position, anchor, limit, collapse_threads, sort);
+
         let get_args = json!({
             "accountId": client.default_account_id(),
             "#ids": {"resultOf": "q0", "name": "Email/query", "path": "/ids"},
@@ -416,6 +722,45 @@ impl Backend {
                 .get("description")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
+
+            if ty == "unsupportedSort" {
+                let props: Vec<&str> = sort
+                    .iter()
+                    .filter_map(|c| c.get("property").and_then(|p| p.as_str()))
+                    .collect();
+                return Err(XinErrorOut {
+                    kind: "jmapRequestError".to_string(),
+                    message: format!(
+                        "unsupportedSort: the server cannot sort by {}; choose other --sort keys",
+                        props.join(", ")
+                    ),
+                    http: None,
+                    jmap: Some(json!({
+                        "type": ty,
+                        "description": (!desc.is_empty()).then_some(desc),
+                        "method": "Email/query",
+                        "sort": sort,
+                        "details": err
+                    })),
+                });
+            }
+
+            // The page token's anchor email left the result set (deleted, moved, or
+            // no longer matching). Callers can fall back to `position`.
+            if ty == "anchorNotFound" {
+                return Err(XinErrorOut {
+                    kind: "jmapRequestError".to_string(),
+                    message: "anchorNotFound: the last email of the previous page is no longer in the results".to_string(),
+                    http: None,
+                    jmap: Some(json!({
+                        "type": ty,
+                        "method": "Email/query",
+                        "anchor": anchor,
+                        "details": err
+                    })),
+                });
+            }
+
             let msg = if desc.is_empty() {
                 format!("JMAP error: {ty}")
             } else {
@@ -641,30 +986,50 @@ impl Backend {
             })
     }
 
-    pub async fn upload_blob(
+    /// Upload files streamed from disk, up to the session's `maxConcurrentUpload` at a time.
+    /// Results are in the order of `files`; the first failure fails the whole batch.
+    pub async fn upload_files(
         &self,
-        bytes: Vec<u8>,
-        content_type: Option<&str>,
-        name: Option<String>,
-    ) -> Result<UploadedBlob, XinErrorOut> {
-        let r = self
-            .j
-            .client()
-            .upload(None, bytes, content_type)
-            .await
-            .map_err(|e| XinErrorOut {
-                kind: "httpError".to_string(),
-                message: format!("upload failed: {e}"),
-                http: None,
-                jmap: None,
-            })?;
+        files: &[UploadFile<'_>],
+    ) -> Result<Vec<UploadedBlob>, XinErrorOut> {
+        use futures_util::StreamExt;
 
-        Ok(UploadedBlob {
-            blob_id: r.blob_id().to_string(),
-            content_type: r.content_type().to_string(),
-            size: r.size(),
-            name,
-        })
+        let client = self.j.client();
+        let url = crate::transfer::expand_url_template(
+            client.session().upload_url(),
+            &[("accountId", client.default_account_id())],
+        );
+        let http = transfer_http(client.headers(), client.timeout())?;
+        let concurrency = self.upload_limits().max_concurrent_upload.unwrap_or(1);
+
+        let results: Vec<Result<UploadedBlob, XinErrorOut>> = futures_util::stream::iter(files)
+            .map(|f| {
+                let (http, url) = (&http, url.as_str());
+                async move {
+                    let v = upload_from(http, url, f.path, f.size, f.content_type).await?;
+                    let blob_id = v.get("blobId").and_then(|x| x.as_str()).ok_or_else(|| {
+                        transfer_error("upload failed: response has no blobId".to_string(), None)
+                    })?;
+                    Ok(UploadedBlob {
+                        blob_id: blob_id.to_string(),
+                        content_type: v
+                            .get("type")
+                            .and_then(|x| x.as_str())
+                            .unwrap_or(f.content_type)
+                            .to_string(),
+                        size: Some(
+                            v.get("size").and_then(|x| x.as_u64()).unwrap_or(f.size) as usize
+                        ),
+                        name: f.name.clone(),
+                        cid: None,
+                    })
+                }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+
+        results.into_iter().collect()
     }
 
     pub async fn create_draft_email(
@@ -768,6 +1133,49 @@ impl Backend {
             })
     }
 
+    /// `Email/import` an uploaded RFC 5322 blob into `mailbox_id` (RFC 8621 §4.8), as is.
+    /// Returns the created object (`id`, `blobId`, `threadId`, `size`).
+    pub async fn import_email(
+        &self,
+        blob_id: &str,
+        mailbox_id: &str,
+    ) -> Result<Value, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+
+        // Like create_draft_email: Drafts membership only, no `$draft` keyword.
+        let mrs = self
+            .raw_call(
+                &Self::using_for("Email"),
+                json!([["Email/import", {
+                    "accountId": account_id,
+                    "emails": {"c0": {"blobId": blob_id, "mailboxIds": {mailbox_id: true}}}
+                }, "i0"]]),
+            )
+            .await?;
+
+        let import = mrs
+            .into_iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("Email/import"))
+            .and_then(|mut mr| mr.get_mut(1).map(Value::take))
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: "missing Email/import response".to_string(),
+                http: None,
+                jmap: None,
+            })?;
+        set_item_error(&import, "notCreated", "c0", "import email")?;
+
+        import
+            .pointer("/created/c0")
+            .cloned()
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: "Email/import did not return the created email".to_string(),
+                http: None,
+                jmap: None,
+            })
+    }
+
     pub async fn modify_emails(
         &self,
         email_ids: &[String],
@@ -1089,6 +1497,306 @@ impl Backend {
         Ok((changes, created_get.take_list(), updated_get.take_list()))
     }
 
+    /// Send raw JMAP method calls and return `methodResponses`.
+    ///
+    /// A method-level `error` response is turned into `jmapRequestError` (with the JMAP error
+    /// object attached), like the other raw requests in this file.
+    pub async fn raw_call(
+        &self,
+        using: &[&str],
+        method_calls: Value,
+    ) -> Result<Vec<Value>, XinErrorOut> {
+        let client = self.j.client();
+        let api_url = client.session().api_url().to_string();
+
+        let request_body = json!({
+            "using": using,
+            "methodCalls": method_calls
+        });
+
+        let http = reqwest::Client::builder()
+            .timeout(client.timeout())
+            .default_headers(client.headers().clone())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("failed to build http client: {e}"),
+                http: None,
+                jmap: None,
+            })?;
+
+        let resp = http
+            .post(api_url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("request failed: {e}"),
+                http: None,
+                jmap: None,
+            })?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("server returned {status}: {text}"),
+                http: Some(json!({"status": status.as_u16()})),
+                jmap: None,
+            });
+        }
+
+        let mut v: Value = resp.json().await.map_err(|e| XinErrorOut {
+            kind: "httpError".to_string(),
+            message: format!("invalid json response: {e}"),
+            http: None,
+            jmap: None,
+        })?;
+
+        let mrs = match v.get_mut("methodResponses").map(Value::take) {
+            Some(Value::Array(a)) => a,
+            _ => {
+                return Err(XinErrorOut {
+                    kind: "jmapRequestError".to_string(),
+                    message: "missing methodResponses".to_string(),
+                    http: None,
+                    jmap: None,
+                });
+            }
+        };
+
+        for mr in &mrs {
+            if mr.get(0).and_then(|n| n.as_str()) != Some("error") {
+                continue;
+            }
+            let err = mr.get(1).cloned().unwrap_or(Value::Null);
+            let ty = err
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let desc = err
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let msg = if desc.is_empty() {
+                format!("JMAP error: {ty}")
+            } else {
+                format!("JMAP error: {ty}: {desc}")
+            };
+            return Err(XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: msg,
+                http: None,
+                jmap: Some(err),
+            });
+        }
+
+        Ok(mrs)
+    }
+
+    /// Capability URNs needed to call methods on `data_type`.
+    fn using_for(data_type: &str) -> Vec<&'static str> {
+        match data_type {
+            "EmailSubmission" | "Identity" => vec![
+                "urn:ietf:params:jmap:core",
+                "urn:ietf:params:jmap:mail",
+                "urn:ietf:params:jmap:submission",
+            ],
+            _ => vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+        }
+    }
+
+    /// Current state of a collection (`<Type>/get` with an empty id list).
+    pub async fn object_state(&self, data_type: &str) -> Result<String, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+        let method = format!("{data_type}/get");
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for(data_type),
+                json!([[method, {"accountId": account_id, "ids": []}, "s0"]]),
+            )
+            .await?;
+
+        mrs.iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some(method.as_str()))
+            .and_then(|mr| mr.pointer("/1/state"))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: format!("missing {method} state"),
+                http: None,
+                jmap: None,
+            })
+    }
+
+    /// `<Type>/changes` as raw JSON arguments (`oldState`, `newState`, `created`, ...).
+    pub async fn object_changes(
+        &self,
+        data_type: &str,
+        since_state: &str,
+        max_changes: Option<usize>,
+    ) -> Result<Value, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+        let method = format!("{data_type}/changes");
+
+        let mut args = json!({
+            "accountId": account_id,
+            "sinceState": since_state
+        });
+        if let Some(m) = max_changes {
+            args.as_object_mut()
+                .expect("changes args")
+                .insert("maxChanges".to_string(), json!(m));
+        }
+
+        let mrs = self
+            .raw_call(&Self::using_for(data_type), json!([[method, args, "c0"]]))
+            .await?;
+
+        mrs.into_iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some(method.as_str()))
+            .and_then(|mut mr| mr.get_mut(1).map(Value::take))
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: format!("missing {method} response"),
+                http: None,
+                jmap: None,
+            })
+    }
+
+    /// Which of `ids` match `filter`.
+    ///
+    /// JMAP has no id filter, so each distinct `receivedAt` of the given emails gets its own
+    /// `Email/query` narrowed to that second (batched up to `maxCallsInRequest` calls per
+    /// request), and the results are intersected with `ids`. Other mail is only listed when
+    /// it arrived in the same second.
+    pub async fn email_ids_matching(
+        &self,
+        filter: &Value,
+        ids: &[String],
+    ) -> Result<std::collections::BTreeSet<String>, XinErrorOut> {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        const PAGE: usize = 256;
+        let using = Self::using_for("Email");
+        let account_id = self.j.client().default_account_id().to_string();
+
+        if ids.is_empty() {
+            return Ok(BTreeSet::new());
+        }
+
+        let mrs = self
+            .raw_call(
+                &using,
+                json!([["Email/get", {
+                    "accountId": account_id,
+                    "ids": ids,
+                    "properties": ["receivedAt"]
+                }, "g0"]]),
+            )
+            .await?;
+        // Emails destroyed since the change was recorded are simply missing.
+        let mut wanted: BTreeMap<chrono::DateTime<chrono::Utc>, BTreeSet<String>> = BTreeMap::new();
+        for e in mrs
+            .iter()
+            .filter_map(|mr| mr.pointer("/1/list").and_then(|l| l.as_array()))
+            .flatten()
+        {
+            let id = e.get("id").and_then(|v| v.as_str());
+            let received = e
+                .get("receivedAt")
+                .and_then(|d| d.as_str())
+                .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok());
+            if let (Some(id), Some(received)) = (id, received) {
+                wanted
+                    .entry(received.with_timezone(&chrono::Utc))
+                    .or_default()
+                    .insert(id.to_string());
+            }
+        }
+
+        let max_calls = self.max_calls_in_request();
+        let mut matched = BTreeSet::new();
+        // (receivedAt, position) of the queries still to run.
+        let mut pending: Vec<(chrono::DateTime<chrono::Utc>, usize)> =
+            wanted.keys().map(|at| (*at, 0)).collect();
+        while !pending.is_empty() {
+            let batch: Vec<_> = pending.drain(..pending.len().min(max_calls)).collect();
+            let calls: Vec<Value> = batch
+                .iter()
+                .enumerate()
+                .map(|(n, (at, position))| {
+                    let mut conditions = vec![
+                        json!({"after": at.to_rfc3339()}),
+                        json!({"before": (*at + chrono::Duration::seconds(1)).to_rfc3339()}),
+                    ];
+                    if !filter.as_object().is_some_and(|o| o.is_empty()) {
+                        conditions.insert(0, filter.clone());
+                    }
+                    json!(["Email/query", {
+                        "accountId": account_id,
+                        "filter": {"operator": "AND", "conditions": conditions},
+                        "sort": [{"property": "receivedAt", "isAscending": false}],
+                        "collapseThreads": false,
+                        "position": position,
+                        "limit": PAGE,
+                        "calculateTotal": true
+                    }, format!("q{n}")])
+                })
+                .collect();
+            let mrs = self.raw_call(&using, Value::Array(calls)).await?;
+
+            for (n, (at, position)) in batch.into_iter().enumerate() {
+                let call_id = format!("q{n}");
+                let resp = mrs
+                    .iter()
+                    .find(|mr| mr.get(2).and_then(|c| c.as_str()) == Some(call_id.as_str()))
+                    .and_then(|mr| mr.get(1))
+                    .ok_or_else(|| XinErrorOut {
+                        kind: "jmapRequestError".to_string(),
+                        message: format!("missing Email/query response ({call_id})"),
+                        http: None,
+                        jmap: None,
+                    })?;
+                let page: Vec<&str> = resp
+                    .get("ids")
+                    .and_then(|v| v.as_array())
+                    .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+                    .unwrap_or_default();
+
+                let ids_at = &wanted[&at];
+                matched.extend(
+                    page.iter()
+                        .filter(|id| ids_at.contains(**id))
+                        .map(|id| id.to_string()),
+                );
+
+                let next = position + page.len();
+                let total = resp.get("total").and_then(|t| t.as_u64()).unwrap_or(0) as usize;
+                if !page.is_empty() && next < total && !ids_at.is_subset(&matched) {
+                    pending.push((at, next));
+                }
+            }
+        }
+
+        Ok(matched)
+    }
+
+    /// The session's `maxCallsInRequest` (RFC 8620 §2), or 16 when it is not advertised.
+    fn max_calls_in_request(&self) -> usize {
+        let session = serde_json::to_value(&*self.j.client().session()).unwrap_or(Value::Null);
+        session
+            .pointer("/capabilities/urn:ietf:params:jmap:core/maxCallsInRequest")
+            .and_then(|v| v.as_u64())
+            .filter(|&n| n > 0)
+            .map_or(16, |n| n as usize)
+    }
+
     pub async fn destroy_emails(&self, email_ids: &[String]) -> Result<(), XinErrorOut> {
         let mut request = self.j.client().build();
         request
@@ -1107,24 +1815,434 @@ impl Backend {
             })
     }
 
+    /// Submit `email_id` and, in the same `EmailSubmission/set` call, apply `on_success`
+    /// to the email.
+    ///
+    /// `envelope` is only needed for SMTP parameters (e.g. FUTURERELEASE); without it the
+    /// server derives the envelope from the email.
     pub async fn submit_email(
         &self,
         email_id: &str,
         identity_id: &str,
-    ) -> Result<jmap_client::email_submission::EmailSubmission, XinErrorOut> {
-        self.j
-            .client()
-            .email_submission_create(email_id.to_string(), identity_id.to_string())
-            .await
-            .map_err(|e| XinErrorOut {
+        envelope: Option<&Value>,
+        on_success: &OnSuccess,
+    ) -> Result<Submission, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+
+        let mut create = json!({"emailId": email_id, "identityId": identity_id});
+        if let Some(envelope) = envelope {
+            create
+                .as_object_mut()
+                .expect("submission create")
+                .insert("envelope".to_string(), envelope.clone());
+        }
+
+        let mut args = json!({
+            "accountId": account_id,
+            "create": {"c0": create}
+        });
+        insert_on_success(&mut args, "#c0", on_success);
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("EmailSubmission"),
+                json!([["EmailSubmission/set", args, "s0"]]),
+            )
+            .await?;
+
+        let set = mrs
+            .iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("EmailSubmission/set"))
+            .and_then(|mr| mr.get(1))
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: "missing EmailSubmission/set response".to_string(),
+                http: None,
+                jmap: None,
+            })?;
+
+        if let Some(err) = set.pointer("/notCreated/c0") {
+            let ty = err
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let desc = err
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            return Err(XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: if desc.is_empty() {
+                    format!("EmailSubmission/set failed: {ty}")
+                } else {
+                    format!("EmailSubmission/set failed: {ty}: {desc}")
+                },
+                http: None,
+                jmap: Some(err.clone()),
+            });
+        }
+
+        let created = set.pointer("/created/c0").ok_or_else(|| XinErrorOut {
+            kind: "jmapRequestError".to_string(),
+            message: "EmailSubmission/set did not return the created submission".to_string(),
+            http: None,
+            jmap: None,
+        })?;
+        let field = |k: &str| created.get(k).and_then(|v| v.as_str()).map(str::to_string);
+
+        let id = field("id").ok_or_else(|| XinErrorOut {
+            kind: "jmapRequestError".to_string(),
+            message: "EmailSubmission/set did not return a submission id".to_string(),
+            http: None,
+            jmap: None,
+        })?;
+
+        Ok(Submission {
+            id,
+            send_at: field("sendAt"),
+            undo_status: field("undoStatus"),
+            email_error: implicit_email_error(&mrs, email_id),
+        })
+    }
+
+    /// The session's `urn:ietf:params:jmap:submission` capability for the mail account
+    /// (`maxDelayedSend`, `submissionExtensions`), falling back to the session-level object.
+    pub fn submission_capability(&self) -> Option<Value> {
+        let client = self.j.client();
+        let session = serde_json::to_value(&*client.session()).ok()?;
+        let urn = "urn:ietf:params:jmap:submission";
+
+        session
+            .get("accounts")
+            .and_then(|a| a.get(client.default_account_id()))
+            .and_then(|a| a.get("accountCapabilities"))
+            .and_then(|c| c.get(urn))
+            .filter(|c| c.get("submissionExtensions").is_some())
+            .or_else(|| session.get("capabilities").and_then(|c| c.get(urn)))
+            .cloned()
+    }
+
+    /// Upload limits: the session's `maxSizeUpload` (per blob) and `maxConcurrentUpload`, and
+    /// the submission `SIZE` extension (whole message), when advertised.
+    pub fn upload_limits(&self) -> UploadLimits {
+        let session = serde_json::to_value(&*self.j.client().session()).unwrap_or(Value::Null);
+        let max_size_upload = session
+            .pointer("/capabilities/urn:ietf:params:jmap:core/maxSizeUpload")
+            .and_then(|v| v.as_u64())
+            .filter(|&n| n > 0);
+        // `"SIZE": ["10240000"]`; `0` or no argument means no fixed limit (RFC 1870).
+        let max_message_size = self
+            .submission_capability()
+            .as_ref()
+            .and_then(|c| c.pointer("/submissionExtensions/SIZE/0"))
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|&n| n > 0);
+        let max_concurrent_upload = session
+            .pointer("/capabilities/urn:ietf:params:jmap:core/maxConcurrentUpload")
+            .and_then(|v| v.as_u64())
+            .filter(|&n| n > 0)
+            .map(|n| n as usize);
+        UploadLimits {
+            max_size_upload,
+            max_message_size,
+            max_concurrent_upload,
+        }
+    }
+
+    /// `EmailSubmission/query` + `EmailSubmission/get`, newest `sendAt` first.
+    pub async fn submissions_list(
+        &self,
+        filter: Option<Value>,
+        limit: usize,
+    ) -> Result<Vec<Value>, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+
+        let mut query_args = json!({
+            "accountId": account_id,
+            "sort": [{"property": "sentAt", "isAscending": false}],
+            "limit": limit
+        });
+        if let Some(filter) = filter {
+            query_args
+                .as_object_mut()
+                .expect("query args")
+                .insert("filter".to_string(), filter);
+        }
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("EmailSubmission"),
+                json!([
+                    ["EmailSubmission/query", query_args, "q0"],
+                    ["EmailSubmission/get", {
+                        "accountId": account_id,
+                        "#ids": {"resultOf": "q0", "name": "EmailSubmission/query", "path": "/ids"},
+                        "properties": SUBMISSION_PROPERTIES
+                    }, "g0"]
+                ]),
+            )
+            .await?;
+
+        Ok(take_list(mrs, "EmailSubmission/get"))
+    }
+
+    /// `EmailSubmission/get` for `ids`; returns `(list, notFound)`.
+    pub async fn submissions_get(
+        &self,
+        ids: &[String],
+    ) -> Result<(Vec<Value>, Vec<String>), XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("EmailSubmission"),
+                json!([["EmailSubmission/get", {
+                    "accountId": account_id,
+                    "ids": ids,
+                    "properties": SUBMISSION_PROPERTIES
+                }, "g0"]]),
+            )
+            .await?;
+
+        let not_found = mrs
+            .iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("EmailSubmission/get"))
+            .and_then(|mr| mr.pointer("/1/notFound"))
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok((take_list(mrs, "EmailSubmission/get"), not_found))
+    }
+
+    /// Cancel a pending submission (`undoStatus: canceled`), applying `on_success` to its
+    /// email in the same call. Returns the implicit `Email/set` SetError, if any.
+    pub async fn cancel_submission(
+        &self,
+        submission_id: &str,
+        email_id: &str,
+        on_success: &OnSuccess,
+    ) -> Result<Option<Value>, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+
+        let mut args = json!({
+            "accountId": account_id,
+            "update": {submission_id: {"undoStatus": "canceled"}}
+        });
+        insert_on_success(&mut args, submission_id, on_success);
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("EmailSubmission"),
+                json!([["EmailSubmission/set", args, "s0"]]),
+            )
+            .await?;
+
+        let set = mrs
+            .iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("EmailSubmission/set"))
+            .and_then(|mr| mr.get(1))
+            .ok_or_else(|| XinErrorOut {
                 kind: "jmapRequestError".to_string(),
-                message: format!("EmailSubmission/set failed: {e}"),
+                message: "missing EmailSubmission/set response".to_string(),
+                http: None,
+                jmap: None,
+            })?;
+
+        if let Some(err) = set.get("notUpdated").and_then(|n| n.get(submission_id)) {
+            let ty = err
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let hint = if ty == "cannotUnsend" {
+                " (the message has already been sent)"
+            } else {
+                ""
+            };
+            return Err(XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: format!("cannot cancel submission {submission_id}: {ty}{hint}"),
+                http: None,
+                jmap: Some(err.clone()),
+            });
+        }
+
+        Ok(implicit_email_error(&mrs, email_id))
+    }
+
+    /// `Identity/get` with every property xin reports (signatures included), as raw JSON.
+    /// `ids: None` returns all identities.
+    pub async fn identities_get_raw(
+        &self,
+        ids: Option<&[String]>,
+    ) -> Result<Vec<Value>, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("Identity"),
+                json!([["Identity/get", {
+                    "accountId": account_id,
+                    "ids": ids,
+                    "properties": IDENTITY_PROPERTIES
+                }, "g0"]]),
+            )
+            .await?;
+
+        Ok(take_list(mrs, "Identity/get"))
+    }
+
+    /// `Identity/set` create; returns the identity with the server-set properties merged in.
+    pub async fn create_identity(&self, identity: Value) -> Result<Value, XinErrorOut> {
+        let set = self
+            .identity_set(json!({"create": {"c0": identity.clone()}}))
+            .await?;
+        set_item_error(&set, "notCreated", "c0", "create identity")?;
+
+        let mut out = identity;
+        if let (Some(obj), Some(created)) = (
+            out.as_object_mut(),
+            set.pointer("/created/c0").and_then(|c| c.as_object()),
+        ) {
+            for (k, v) in created {
+                obj.insert(k.clone(), v.clone());
+            }
+        }
+        Ok(out)
+    }
+
+    /// `Identity/set` update with a patch object.
+    pub async fn update_identity(&self, id: &str, patch: Value) -> Result<(), XinErrorOut> {
+        let set = self.identity_set(json!({"update": {id: patch}})).await?;
+        set_item_error(&set, "notUpdated", id, "update identity")
+    }
+
+    /// `Identity/set` destroy.
+    pub async fn destroy_identity(&self, id: &str) -> Result<(), XinErrorOut> {
+        let set = self.identity_set(json!({"destroy": [id]})).await?;
+        set_item_error(&set, "notDestroyed", id, "delete identity")
+    }
+
+    async fn identity_set(&self, mut args: Value) -> Result<Value, XinErrorOut> {
+        let account_id = self.j.client().default_account_id().to_string();
+        args.as_object_mut()
+            .expect("identity set args")
+            .insert("accountId".to_string(), json!(account_id));
+
+        let mrs = self
+            .raw_call(
+                &Self::using_for("Identity"),
+                json!([["Identity/set", args, "s0"]]),
+            )
+            .await?;
+
+        mrs.into_iter()
+            .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("Identity/set"))
+            .and_then(|mut mr| mr.get_mut(1).map(Value::take))
+            .ok_or_else(|| XinErrorOut {
+                kind: "jmapRequestError".to_string(),
+                message: "missing Identity/set response".to_string(),
                 http: None,
                 jmap: None,
             })
     }
 }
 
+/// Identity properties xin reports.
+const IDENTITY_PROPERTIES: &[&str] = &[
+    "id",
+    "name",
+    "email",
+    "replyTo",
+    "bcc",
+    "textSignature",
+    "htmlSignature",
+    "mayDelete",
+];
+
+/// Map a `/set` SetError for `key` (in `notCreated` / `notUpdated` / `notDestroyed`) to
+/// `jmapRequestError`.
+fn set_item_error(set: &Value, field: &str, key: &str, action: &str) -> Result<(), XinErrorOut> {
+    let Some(err) = set.get(field).and_then(|n| n.get(key)) else {
+        return Ok(());
+    };
+    let ty = err
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown");
+    let description = err
+        .get("description")
+        .and_then(|v| v.as_str())
+        .map(|d| format!(" ({d})"))
+        .unwrap_or_default();
+    Err(XinErrorOut {
+        kind: "jmapRequestError".to_string(),
+        message: format!("cannot {action}: {ty}{description}"),
+        http: None,
+        jmap: Some(err.clone()),
+    })
+}
+
+/// EmailSubmission properties xin reports.
+const SUBMISSION_PROPERTIES: &[&str] = &[
+    "id",
+    "identityId",
+    "emailId",
+    "threadId",
+    "envelope",
+    "sendAt",
+    "undoStatus",
+    "deliveryStatus",
+    "dsnBlobIds",
+    "mdnBlobIds",
+];
+
+/// Add `onSuccessUpdateEmail` / `onSuccessDestroyEmail` for submission `key`
+/// (`#<creationId>` or a submission id) to `EmailSubmission/set` arguments.
+fn insert_on_success(args: &mut Value, key: &str, on_success: &OnSuccess) {
+    let obj = args.as_object_mut().expect("submission args");
+    match on_success {
+        OnSuccess::Nothing => {}
+        OnSuccess::Update(patch) => {
+            obj.insert("onSuccessUpdateEmail".to_string(), json!({key: patch}));
+        }
+        OnSuccess::Destroy => {
+            obj.insert("onSuccessDestroyEmail".to_string(), json!([key]));
+        }
+    }
+}
+
+/// SetError for `email_id` in the implicit `Email/set` response that follows an
+/// `EmailSubmission/set` with `onSuccess*Email`.
+fn implicit_email_error(mrs: &[Value], email_id: &str) -> Option<Value> {
+    mrs.iter()
+        .filter(|mr| mr.get(0).and_then(|n| n.as_str()) == Some("Email/set"))
+        .find_map(|mr| {
+            let args = mr.get(1)?;
+            args.get("notUpdated")
+                .and_then(|n| n.get(email_id))
+                .or_else(|| args.get("notDestroyed").and_then(|n| n.get(email_id)))
+                .cloned()
+        })
+}
+
+/// `list` of the first `method` response.
+fn take_list(mrs: Vec<Value>, method: &str) -> Vec<Value> {
+    mrs.into_iter()
+        .find(|mr| mr.get(0).and_then(|n| n.as_str()) == Some(method))
+        .and_then(|mut mr| mr.pointer_mut("/1/list").map(Value::take))
+        .and_then(|l| match l {
+            Value::Array(a) => Some(a),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
 fn build_email_body(
     text: Option<&str>,
     html: Option<&str>,
@@ -1137,6 +2255,27 @@ fn build_email_body(
 
     let mut body_values: Vec<(String, String)> = Vec::new();
 
+    // Inline images go next to the HTML part (multipart/related); the rest are attachments.
+    let (inline, attachments): (Vec<&UploadedBlob>, Vec<&UploadedBlob>) = attachments
+        .iter()
+        .partition(|a| a.cid.is_some() && html.is_some());
+
+    let html_part = || -> PartSet {
+        let part = jmap_client::email::EmailBodyPart::new()
+            .part_id("html")
+            .content_type("text/html");
+        if inline.is_empty() {
+            return part;
+        }
+        let mut related = jmap_client::email::EmailBodyPart::new()
+            .content_type("multipart/related")
+            .sub_part(part.into());
+        for a in &inline {
+            related = related.sub_part(blob_part(a, "inline").into());
+        }
+        related
+    };
+
     let body_part: Option<PartSet> = match (text, html) {
         (Some(t), Some(h)) => {
             let alt = jmap_client::email::EmailBodyPart::new()
@@ -1147,12 +2286,7 @@ fn build_email_body(
                         .content_type("text/plain")
                         .into(),
                 )
-                .sub_part(
-                    jmap_client::email::EmailBodyPart::new()
-                        .part_id("html")
-                        .content_type("text/html")
-                        .into(),
-                );
+                .sub_part(html_part().into());
 
             body_values.push(("text".to_string(), t.to_string()));
             body_values.push(("html".to_string(), h.to_string()));
@@ -1169,11 +2303,7 @@ fn build_email_body(
         }
         (None, Some(h)) => {
             body_values.push(("html".to_string(), h.to_string()));
-            Some(
-                jmap_client::email::EmailBodyPart::new()
-                    .part_id("html")
-                    .content_type("text/html"),
-            )
+            Some(html_part())
         }
         (None, None) => None,
     };
@@ -1197,23 +2327,7 @@ fn build_email_body(
         );
 
         for a in attachments {
-            let mut p = jmap_client::email::EmailBodyPart::new()
-                .blob_id(a.blob_id.clone())
-                .content_type(a.content_type.clone());
-            if let Some(name) = &a.name {
-                p = p.name(name.clone());
-            }
-
-            // jmap-client does not currently expose a builder for EmailBodyPart.disposition.
-            // Use a JSON round-trip to set it explicitly so attachments are marked as attachments.
-            let mut v = serde_json::to_value(&p).expect("part json");
-            v.as_object_mut().expect("part object").insert(
-                "disposition".to_string(),
-                Value::String("attachment".to_string()),
-            );
-            let p: PartSet = serde_json::from_value(v).expect("part from json");
-
-            mixed = mixed.sub_part(p.into());
+            mixed = mixed.sub_part(blob_part(a, "attachment").into());
         }
         mixed
     } else {
@@ -1226,3 +2340,196 @@ fn build_email_body(
 
     (root, body_values)
 }
+
+/// A body part referencing an uploaded blob, with `disposition` (and `cid` for inline images).
+fn blob_part(
+    a: &UploadedBlob,
+    disposition: &str,
+) -> jmap_client::email::EmailBodyPart<jmap_client::Set> {
+    let mut p = jmap_client::email::EmailBodyPart::new()
+        .blob_id(a.blob_id.clone())
+        .content_type(a.content_type.clone());
+    if let Some(name) = &a.name {
+        p = p.name(name.clone());
+    }
+
+    // jmap-client does not currently expose a builder for EmailBodyPart.disposition / cid.
+    // Use a JSON round-trip to set them explicitly.
+    let mut v = serde_json::to_value(&p).expect("part json");
+    let obj = v.as_object_mut().expect("part object");
+    obj.insert(
+        "disposition".to_string(),
+        Value::String(disposition.to_string()),
+    );
+    if let Some(cid) = &a.cid {
+        obj.insert("cid".to_string(), Value::String(cid.clone()));
+    }
+    serde_json::from_value(v).expect("part from json")
+}
+
+/// A blob streamed to disk by `Backend::download_blob_to`.
+#[derive(Debug, Clone, Copy)]
+pub struct Download {
+    /// Size of the complete file.
+    pub bytes: u64,
+    /// Bytes kept from an earlier interrupted download (`0` when not resumed).
+    pub resumed_from: u64,
+}
+
+fn transfer_error(message: String, status: Option<reqwest::StatusCode>) -> XinErrorOut {
+    XinErrorOut {
+        kind: "httpError".to_string(),
+        message,
+        http: status.map(|s| json!({"status": s.as_u16()})),
+        jmap: None,
+    }
+}
+
+/// HTTP client for blob transfers: no overall timeout (large blobs take a while), but
+/// connecting and every read are bounded by `timeout`.
+fn transfer_http(
+    headers: &reqwest::header::HeaderMap,
+    timeout: std::time::Duration,
+) -> Result<reqwest::Client, XinErrorOut> {
+    reqwest::Client::builder()
+        .connect_timeout(timeout)
+        .read_timeout(timeout)
+        .default_headers(headers.clone())
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| transfer_error(format!("failed to build http client: {e}"), None))
+}
+
+/// GET `url` into `out`, through `part`. A leftover `part` is resumed with a `Range`
+/// request; a server that ignores it (`200`), rejects it (`416`) or answers from another
+/// position (`Content-Range`) gets a full download.
+async fn download_to(
+    http: &reqwest::Client,
+    url: &str,
+    out: &std::path::Path,
+    part: &std::path::Path,
+    progress: &mut crate::transfer::Progress,
+) -> Result<Download, XinErrorOut> {
+    use reqwest::StatusCode;
+    use reqwest::header::{CONTENT_RANGE, RANGE};
+    use std::io::Write;
+
+    let request_failed = |e: reqwest::Error| transfer_error(format!("download failed: {e}"), None);
+    let write_failed =
+        |e: std::io::Error| XinErrorOut::usage(format!("failed to write output file: {e}"));
+
+    let offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = http.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+    let mut resp = request.send().await.map_err(request_failed)?;
+    let range_start = resp
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::transfer::content_range_start);
+    let restart = match resp.status() {
+        StatusCode::RANGE_NOT_SATISFIABLE => true,
+        StatusCode::PARTIAL_CONTENT => range_start != Some(offset),
+        _ => false,
+    };
+    if offset > 0 && restart {
+        resp = http.get(url).send().await.map_err(request_failed)?;
+    }
+
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(transfer_error(
+            format!("server returned {status}: {text}"),
+            Some(status),
+        ));
+    }
+
+    let resumed_from = if offset > 0 && !restart && status == StatusCode::PARTIAL_CONTENT {
+        offset
+    } else {
+        0
+    };
+    let total = if resumed_from > 0 {
+        resp.headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(crate::transfer::content_range_total)
+    } else {
+        resp.content_length()
+    };
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed_from > 0)
+        .truncate(resumed_from == 0)
+        .open(part)
+        .map_err(write_failed)?;
+
+    let mut written = resumed_from;
+    progress.update(written, total);
+    while let Some(chunk) = resp.chunk().await.map_err(request_failed)? {
+        file.write_all(&chunk).map_err(write_failed)?;
+        written += chunk.len() as u64;
+        progress.update(written, total);
+    }
+    file.flush().map_err(write_failed)?;
+    drop(file);
+
+    if total.is_some_and(|t| t != written) {
+        return Err(transfer_error(
+            format!(
+                "download incomplete: got {written} of {} bytes; run again to resume",
+                total.unwrap_or_default()
+            ),
+            Some(status),
+        ));
+    }
+
+    std::fs::rename(part, out).map_err(write_failed)?;
+    progress.finish(written, total);
+    Ok(Download {
+        bytes: written,
+        resumed_from,
+    })
+}
+
+/// POST the file at `path` to `url`, streamed from disk.
+async fn upload_from(
+    http: &reqwest::Client,
+    url: &str,
+    path: &std::path::Path,
+    size: u64,
+    content_type: &str,
+) -> Result<Value, XinErrorOut> {
+    use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| XinErrorOut::usage(format!("failed to read {}: {e}", path.display())))?;
+
+    let resp = http
+        .post(url)
+        .header(CONTENT_TYPE, content_type)
+        .header(CONTENT_LENGTH, size)
+        .body(reqwest::Body::from(file))
+        .send()
+        .await
+        .map_err(|e| transfer_error(format!("upload failed: {e}"), None))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(transfer_error(
+            format!("upload failed: server returned {status}: {text}"),
+            Some(status),
+        ));
+    }
+    resp.json()
+        .await
+        .map_err(|e| transfer_error(format!("upload failed: invalid json response: {e}"), None))
+}
@@ -32,6 +32,10 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub account: Option<String>,
 
+    /// Timezone for bare dates in queries (IANA name, e.g. Asia/Tokyo). Overrides XIN_TZ.
+    #[arg(long, global = true, value_name = "ZONE")]
+    pub tz: Option<String>,
+
     /// Verbose logging.
     #[arg(long, global = true)]
     pub verbose: bool,
@@ -63,6 +67,15 @@ pub enum Command {
     /// Download an attachment.
     Attachment(AttachmentArgs),
 
+    /// Bulk attachment operations.
+    Attachments {
+        #[command(subcommand)]
+        command: AttachmentsCommand,
+    },
+
+    /// Export raw messages to an mbox file, a Maildir or .eml files.
+    Export(ExportArgs),
+
     /// Print webmail URL(s) (Fastmail-only).
     Url(UrlArgs),
 
@@ -114,16 +127,25 @@ pub enum Command {
     /// Reply to an email by emailId (JMAP Email id).
     Reply(ReplyArgs),
 
+    /// Forward an email by emailId, inline or as a message/rfc822 attachment.
+    Forward(ForwardArgs),
+
     /// Drafts operations.
     Drafts {
         #[command(subcommand)]
         command: DraftsCommand,
     },
 
+    /// Email submissions (scheduled sends, undo-send).
+    Submissions {
+        #[command(subcommand)]
+        command: SubmissionsCommand,
+    },
+
     /// History / changes.
     History(HistoryArgs),
 
-    /// Watch for email changes (polling Email/changes; NDJSON stream).
+    /// Watch for changes (Email/changes and friends; NDJSON stream).
     Watch(WatchArgs),
 
     /// Config file operations.
@@ -132,6 +154,12 @@ pub enum Command {
         command: ConfigCommand,
     },
 
+    /// Saved queries (`@name` in query sugar).
+    Queries {
+        #[command(subcommand)]
+        command: QueriesCommand,
+    },
+
     /// Credential helpers.
     Auth {
         #[command(subcommand)]
@@ -143,7 +171,7 @@ pub enum Command {
 
 #[derive(Args, Debug)]
 #[command(
-    after_help = "Examples:\n  xin search \"in:inbox\" --max 20\n  xin search \"in:inbox seen:false\" --max 20\n  xin search \"from:github subject:release\" --max 10\n  xin search \"has:attachment after:2026-01-01\" --max 20\n  xin search \"-in:trash\" --max 20\n  xin search \"or:(from:github | from:atlassian) seen:false\" --max 20\n  xin search --filter-json @filter.json --max 50\n\nQuery sugar (not Gmail-compatible):\n  from:<text> to:<text> cc:<text> bcc:<text>\n  subject:<text> text:<text>\n  in:<mailbox> (role/name/id; e.g. inbox, trash, junk, archive)\n  seen:true|false flagged:true|false\n  has:attachment after:<YYYY-MM-DD> before:<YYYY-MM-DD>\n  -term (NOT), or:(a | b)\n\nTips:\n  - Quote multi-term queries.\n  - Use --filter-json for precise server-owned filters (accepts @/path.json)."
+    after_help = "Examples:\n  xin search \"in:inbox\" --max 20\n  xin search \"in:inbox seen:false\" --max 20\n  xin search \"from:github subject:release\" --max 10\n  xin search \"has:attachment after:2026-01-01\" --max 20\n  xin search \"newer_than:7d larger:5M\" --max 20\n  xin search 'header:List-Id:github thread-has:$flagged' --max 20\n  xin search \"-in:trash\" --max 20\n  xin search \"or:(from:github | from:atlassian) seen:false\" --max 20\n  xin search \"(from:github OR from:gitlab) -(subject:digest seen:true)\" --max 20\n  xin search --filter-json @filter.json --max 50\n  xin search \"in:Receipts after:2026-01-01\" --explain\n  xin search \"@invoices newer_than:30d\" --max 20\n  xin search \"in:inbox\" --sort from,receivedAt:desc\n  xin search \"in:inbox\" --sort 'hasKeyword:$flagged,receivedAt'\n\nQuery sugar (not Gmail-compatible):\n  from:<text> to:<text> cc:<text> bcc:<text>\n  subject:<text> text:<text>\n  in:<mailbox> (role/name/id; e.g. inbox, trash, junk, archive)\n  seen:true|false flagged:true|false\n  has:attachment after:<YYYY-MM-DD|today|yesterday> before:<...>\n  newer_than:7d older_than:2w (h/d/w/m/y)\n  larger:5M smaller:100K\n  is:answered|draft|flagged|seen|unread keyword:<kw>\n  thread-has:<kw> thread-all:<kw> header:<Name>[:<text>]\n  a b (AND), a OR b / a | b (OR), -term / NOT term (NOT)\n  ( ... ) grouping, -( ... ) group negation, or:(a | b)\n  @name (saved query from config; see `xin queries`)\n  Precedence: NOT > AND > OR\n\nTips:\n  - Quote multi-term queries.\n  - Use --filter-json for precise server-owned filters (accepts @/path.json).\n  - Use --explain to see the compiled filter and mailbox resolution without searching.\n  - --page continues after the last email of the previous page; if the results changed\n    in between, meta.warnings says so (or use --fail-on-drift to error instead)."
 )]
 pub struct SearchArgs {
     #[arg(value_name = "QUERY", allow_hyphen_values = true)]
@@ -164,13 +192,97 @@ pub struct SearchArgs {
     #[arg(long = "collapse-threads")]
     pub collapse_threads: Option<bool>,
 
-    #[arg(long, value_enum, default_value_t = SortKey::ReceivedAt)]
-    pub sort: SortKey,
+    /// Sort keys, comma-separated: `<key>[:asc|:desc]`, `hasKeyword:<kw>[:asc|:desc]`.
+    /// Keys: receivedAt (default), sentAt, size, from, to, subject, hasKeyword.
+    #[arg(long, value_name = "KEYS", value_parser = parse_sort_spec, value_delimiter = ',')]
+    pub sort: Vec<SortSpec>,
+    /// With --page: fail instead of warning when the result set changed since the previous page.
+    #[arg(long = "fail-on-drift")]
+    pub fail_on_drift: bool,
+    /// Show the compiled filter and Email/query arguments without running the query.
+    #[arg(long)]
+    pub explain: bool,
 }
 
+/// RFC 8621 `Email/query` sort properties.
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortKey {
+    #[value(name = "receivedAt", alias = "received-at")]
     ReceivedAt,
+    #[value(name = "sentAt", alias = "sent-at")]
+    SentAt,
+    Size,
+    From,
+    To,
+    Subject,
+    #[value(name = "hasKeyword", alias = "has-keyword")]
+    HasKeyword,
+}
+
+impl SortKey {
+    pub fn property(self) -> &'static str {
+        match self {
+            SortKey::ReceivedAt => "receivedAt",
+            SortKey::SentAt => "sentAt",
+            SortKey::Size => "size",
+            SortKey::From => "from",
+            SortKey::To => "to",
+            SortKey::Subject => "subject",
+            SortKey::HasKeyword => "hasKeyword",
+        }
+    }
+}
+
+/// One `--sort` key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortSpec {
+    pub key: SortKey,
+    /// Only for `hasKeyword`.
+    pub keyword: Option<String>,
+    /// Explicit `:asc` / `:desc`; otherwise see `is_ascending`.
+    pub ascending: Option<bool>,
+}
+
+impl SortSpec {
+    /// Explicit direction, else `--oldest`, else newest/largest/flagged first for
+    /// dates, size and keywords and A→Z for addresses and subject.
+    pub fn is_ascending(&self, oldest: bool) -> bool {
+        self.ascending
+            .unwrap_or(oldest || matches!(self.key, SortKey::From | SortKey::To | SortKey::Subject))
+    }
+}
+
+fn parse_sort_spec(s: &str) -> Result<SortSpec, String> {
+    let mut parts: Vec<&str> = s.trim().split(':').collect();
+    let ascending = match parts.last().map(|p| p.to_ascii_lowercase()) {
+        Some(d) if parts.len() > 1 && (d == "asc" || d == "desc") => {
+            parts.pop();
+            Some(d == "asc")
+        }
+        _ => None,
+    };
+
+    let key = SortKey::from_str(parts[0], true).map_err(|_| {
+        format!(
+            "unknown sort key '{}' (expected receivedAt, sentAt, size, from, to, subject or hasKeyword:<kw>)",
+            parts[0]
+        )
+    })?;
+
+    let keyword = match (key, &parts[1..]) {
+        (SortKey::HasKeyword, [kw]) if !kw.is_empty() => Some(kw.to_lowercase()),
+        (SortKey::HasKeyword, _) => {
+            return Err("hasKeyword needs a keyword, e.g. hasKeyword:$flagged".to_string());
+        }
+        (_, []) => None,
+        (_, _) => return Err(format!("unexpected sort option in '{s}'")),
+    };
+
+    Ok(SortSpec {
+        key,
+        keyword,
+        ascending,
+    })
 }
 
 #[derive(Subcommand, Debug)]
@@ -180,7 +292,7 @@ pub enum MessagesCommand {
 
 #[derive(Args, Debug)]
 #[command(
-    after_help = "Examples:\n  xin messages search \"in:inbox\" --max 50\n  xin messages search \"from:github\" --max 20\n  xin messages search --filter-json @filter.json --max 50\n\nNotes:\n  - Default output is stable JSON."
+    after_help = "Examples:\n  xin messages search \"in:inbox\" --max 50\n  xin messages search \"from:github\" --max 20\n  xin messages search \"larger:5M\" --sort size\n  xin messages search --filter-json @filter.json --max 50\n\nNotes:\n  - Default output is stable JSON."
 )]
 pub struct MessagesSearchArgs {
     #[arg(value_name = "QUERY", allow_hyphen_values = true)]
@@ -192,8 +304,19 @@ pub struct MessagesSearchArgs {
     #[arg(long)]
     pub page: Option<String>,
 
+    /// Sort keys (same as `xin search --sort`).
+    #[arg(long, value_name = "KEYS", value_parser = parse_sort_spec, value_delimiter = ',')]
+    pub sort: Vec<SortSpec>,
+
+    /// With --page: fail instead of warning when the result set changed since the previous page.
+    #[arg(long = "fail-on-drift")]
+    pub fail_on_drift: bool,
+
     #[arg(long = "filter-json")]
     pub filter_json: Option<String>,
+    /// Show the compiled filter and Email/query arguments without running the query.
+    #[arg(long)]
+    pub explain: bool,
 }
 
 #[derive(Args, Debug)]
@@ -245,9 +368,23 @@ pub struct ThreadGetArgs {
 }
 
 #[derive(Args, Debug)]
-#[command(after_help = "Examples:\n  xin thread attachments <threadId>")]
+#[command(
+    after_help = "Examples:\n  xin thread attachments <threadId>\n  xin thread attachments <threadId> --download --dir ./out"
+)]
 pub struct ThreadAttachmentsArgs {
     pub thread_id: String,
+
+    /// Download every attachment into --dir (deduplicated, with a manifest.json).
+    #[arg(long)]
+    pub download: bool,
+
+    /// Directory for --download.
+    #[arg(long, value_name = "DIR", default_value = ".", requires = "download")]
+    pub dir: String,
+
+    /// Do not report download progress on stderr.
+    #[arg(long, requires = "download")]
+    pub no_progress: bool,
 }
 
 #[derive(Args, Debug)]
@@ -311,6 +448,70 @@ pub struct AttachmentArgs {
 
     #[arg(long)]
     pub name: Option<String>,
+
+    /// Do not report download progress on stderr.
+    #[arg(long)]
+    pub no_progress: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AttachmentsCommand {
+    /// Download every attachment of the emails matching a query.
+    Export(AttachmentsExportArgs),
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin attachments export \"from:billing@example.com has:attachment newer_than:7d\" --dir ./invoices\n  xin attachments export --filter-json @filter.json --dir ./out\n\nNotes:\n  - Files are deduplicated by SHA-256 and listed in <dir>/manifest.json.\n  - Re-running into the same --dir skips attachments already exported."
+)]
+pub struct AttachmentsExportArgs {
+    #[arg(value_name = "QUERY", allow_hyphen_values = true)]
+    pub query: Option<String>,
+
+    #[arg(long = "filter-json", conflicts_with = "query")]
+    pub filter_json: Option<String>,
+
+    /// Output directory (created if missing).
+    #[arg(long, value_name = "DIR")]
+    pub dir: String,
+
+    /// Do not report download progress on stderr.
+    #[arg(long)]
+    pub no_progress: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin export \"from:acme.example after:2025-01-01\" --format mbox --out ./acme.mbox\n  xin export \"in:Projects/Apollo\" --format maildir --out ./apollo\n  xin export \"in:Projects/Apollo\" --format maildir --out ./apollo --incremental\n  xin export --filter-json @filter.json --format eml --out ./out --since <state>\n\nNotes:\n  - Every run saves the Email state it started from (mbox: <out>.xin-export.json,\n    maildir/eml: <out>/.xin-export.json); --incremental fetches only mail created since.\n  - Maildir files carry keywords as flags: $draft D, $flagged F, $forwarded P,\n    $answered R, $seen S.\n  - A full mbox export replaces the file; incremental runs append to it."
+)]
+pub struct ExportArgs {
+    #[arg(value_name = "QUERY", allow_hyphen_values = true)]
+    pub query: Option<String>,
+
+    #[arg(long = "filter-json", conflicts_with = "query")]
+    pub filter_json: Option<String>,
+
+    #[arg(long, value_enum)]
+    pub format: ExportFormat,
+
+    /// Output: the mbox file, or the Maildir / .eml directory (created if missing).
+    #[arg(long, value_name = "PATH")]
+    pub out: String,
+
+    /// Only export mail created since the previous export into --out.
+    #[arg(long)]
+    pub incremental: bool,
+
+    /// Only export mail created since this Email state (e.g. from `xin history`).
+    #[arg(long, value_name = "STATE", conflicts_with = "incremental")]
+    pub since: Option<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Mbox,
+    Maildir,
+    Eml,
 }
 
 #[derive(Args, Debug)]
@@ -388,6 +589,9 @@ pub struct InboxNextArgs {
     /// Page token (from meta.nextPage).
     #[arg(long)]
     pub page: Option<String>,
+    /// Show the compiled filter and Email/query arguments without running the query.
+    #[arg(long)]
+    pub explain: bool,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
@@ -511,6 +715,12 @@ pub struct LabelsModifyArgs {
 pub enum IdentitiesCommand {
     List,
     Get(IdentitiesGetArgs),
+    /// Create an identity (Identity/set).
+    Create(IdentitiesCreateArgs),
+    /// Update an identity's name, Reply-To, Bcc or signatures.
+    Update(IdentitiesUpdateArgs),
+    /// Delete an identity. Requires global --force.
+    Delete(IdentitiesDeleteArgs),
 }
 
 #[derive(Args, Debug)]
@@ -520,15 +730,84 @@ pub struct IdentitiesGetArgs {
 
 #[derive(Args, Debug)]
 #[command(
-    after_help = "Examples:\n  xin send --to bob@example.com --subject \"Hello\" --text \"hi\"\n  xin send --to bob@example.com --subject \"Hello\" --text @body.txt --attach ./a.pdf\n  xin send --to bob@example.com --subject \"Hello\" --body-html @body.html\n  xin send --to bob@example.com --subject \"Hello\" --text \"hi\" --identity alice@example.com"
+    after_help = "Examples:\n  xin identities create --email sales@example.com --name \"Sales\"\n  xin identities create --email me@example.com --text-signature @sig.txt --html-signature @sig.html\n\nNotes:\n  - Addresses accept `email` or `Name <email>`."
+)]
+pub struct IdentitiesCreateArgs {
+    /// From address (must be one the server allows you to send as).
+    #[arg(long)]
+    pub email: String,
+
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Reply-To address(es).
+    #[arg(long = "reply-to")]
+    pub reply_to: Vec<String>,
+
+    /// Bcc address(es) added to every message sent with this identity.
+    #[arg(long)]
+    pub bcc: Vec<String>,
+
+    /// Plain text signature. Supports @/path/to/file.txt
+    #[arg(long = "text-signature")]
+    pub text_signature: Option<String>,
+
+    /// HTML signature. Supports @/path/to/file.html
+    #[arg(long = "html-signature")]
+    pub html_signature: Option<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin identities update sales@example.com --name \"Sales Team\"\n  xin identities update <identityId> --text-signature @sig.txt\n  xin identities update <identityId> --clear-reply-to"
+)]
+pub struct IdentitiesUpdateArgs {
+    /// Identity id or email.
+    pub id: String,
+
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Replace the Reply-To address(es).
+    #[arg(long = "reply-to", conflicts_with = "clear_reply_to")]
+    pub reply_to: Vec<String>,
+
+    #[arg(long = "clear-reply-to")]
+    pub clear_reply_to: bool,
+
+    /// Replace the Bcc address(es).
+    #[arg(long, conflicts_with = "clear_bcc")]
+    pub bcc: Vec<String>,
+
+    #[arg(long = "clear-bcc")]
+    pub clear_bcc: bool,
+
+    /// Plain text signature (empty string clears it). Supports @/path/to/file.txt
+    #[arg(long = "text-signature")]
+    pub text_signature: Option<String>,
+
+    /// HTML signature (empty string clears it). Supports @/path/to/file.html
+    #[arg(long = "html-signature")]
+    pub html_signature: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct IdentitiesDeleteArgs {
+    /// Identity id or email.
+    pub id: String,
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin send --to bob@example.com --subject \"Hello\" --text \"hi\"\n  xin send --to bob@example.com --subject \"Hello\" --text @body.txt --attach ./a.pdf\n  xin send --to bob@example.com --subject \"Hello\" --body-html @body.html\n  xin send --to bob@example.com --subject \"Hello\" --markdown @notes.md\n  xin send --to bob@example.com --subject \"Hello\" --text \"hi\" --identity alice@example.com\n  xin send --to bob@example.com --subject \"Hello\" --text \"hi\" --send-at +10m\n  xin send --to bob@example.com --subject \"Hello\" --text \"hi\" --send-at 2026-03-01T09:00:00+09:00\n  xin --dry-run send --template @notice.tmpl --data @rows.csv\n  xin send --template @notice.tmpl --data @rows.csv --delay-ms 2000\n  xin send --template @notice.tmpl --data @rows.csv --rows 4,9\n\nNotes:\n  - A scheduled send can be cancelled until it goes out: xin submissions cancel <submissionId>\n  - Mail merge renders every row before sending anything; results are per row (data.results)."
 )]
 pub struct SendArgs {
     /// Recipient(s). Can be specified multiple times.
-    #[arg(long, required = true, num_args = 1..)]
+    #[arg(long, required_unless_present_any = ["template", "eml"], num_args = 1..)]
     pub to: Vec<String>,
 
-    #[arg(long)]
-    pub subject: String,
+    #[arg(long, required_unless_present_any = ["template", "eml"])]
+    pub subject: Option<String>,
 
     /// Plain text body. Supports @/path/to/file.txt
     #[arg(long)]
@@ -538,6 +817,10 @@ pub struct SendArgs {
     #[arg(long = "body-html")]
     pub body_html: Option<String>,
 
+    /// Markdown body, rendered to HTML plus a plain-text alternative. Supports @/path/to/file.md
+    #[arg(long, conflicts_with_all = ["text", "body_html"])]
+    pub markdown: Option<String>,
+
     #[arg(long)]
     pub cc: Vec<String>,
 
@@ -548,14 +831,98 @@ pub struct SendArgs {
     #[arg(long = "attach")]
     pub attach: Vec<String>,
 
+    /// Attach an existing blob by reference, without re-uploading: `<emailId>:<blobId>`
+    /// (name and type from that email's attachment) or a bare `<blobId>`.
+    #[arg(long = "attach-blob", value_name = "[EMAILID:]BLOBID")]
+    pub attach_blob: Vec<String>,
+
     /// Identity to send as (id or email).
     #[arg(long)]
     pub identity: Option<String>,
+
+    /// Append the sending identity's signature (textSignature / htmlSignature).
+    #[arg(long)]
+    pub signature: bool,
+
+    /// Schedule the send: RFC3339 time or delay like `+10m` (s/m/h/d). Needs FUTURERELEASE.
+    #[arg(long = "send-at", value_name = "WHEN")]
+    pub send_at: Option<String>,
+
+    /// After submitting, poll the delivery status until it is final (or `--wait-timeout`).
+    #[arg(long = "wait-delivery", conflicts_with = "send_at")]
+    pub wait_delivery: bool,
+
+    /// Seconds to wait for a final delivery status with `--wait-delivery`.
+    #[arg(
+        long = "wait-timeout",
+        value_name = "SECS",
+        default_value_t = 120,
+        requires = "wait_delivery"
+    )]
+    pub wait_timeout: u64,
+
+    /// Mail merge template: To/Cc/Bcc/Subject/Format header lines, a blank line, then the
+    /// body; `{{column}}` is replaced per data row. Supports @/path/to/template
+    #[arg(
+        long,
+        requires = "data",
+        conflicts_with_all = ["to", "subject", "text", "body_html", "markdown", "wait_delivery"]
+    )]
+    pub template: Option<String>,
+
+    /// Mail merge rows: @rows.csv (with a header row) or @rows.jsonl (one object per line).
+    #[arg(long, value_name = "@FILE", requires = "template")]
+    pub data: Option<String>,
+
+    /// Mail merge: only these 1-based data rows, e.g. `2,5-7` (to retry failed rows).
+    #[arg(long, value_name = "LIST", requires = "template")]
+    pub rows: Option<String>,
+
+    /// Mail merge: create one draft per row without submitting.
+    #[arg(
+        long = "drafts-only",
+        requires = "template",
+        conflicts_with = "send_at"
+    )]
+    pub drafts_only: bool,
+
+    /// Mail merge: pause between submissions, in milliseconds.
+    #[arg(
+        long = "delay-ms",
+        value_name = "MS",
+        default_value_t = 1000,
+        requires = "template"
+    )]
+    pub delay_ms: u64,
+
+    /// Mail merge: print each row's result as a JSON line instead of the final envelope.
+    #[arg(long, requires = "template")]
+    pub ndjson: bool,
+
+    /// Send a complete RFC 5322 message as is (`Email/import` into Drafts, then submit).
+    /// The envelope comes from its From/To/Cc/Bcc headers.
+    #[arg(
+        long,
+        value_name = "@FILE",
+        conflicts_with_all = [
+            "to", "subject", "text", "body_html", "markdown", "cc", "bcc", "attach",
+            "attach_blob", "signature", "template"
+        ]
+    )]
+    pub eml: Option<String>,
+
+    /// `--eml`: envelope MAIL FROM address (default: the message's Sender, then From).
+    #[arg(long = "mail-from", value_name = "ADDR", requires = "eml")]
+    pub mail_from: Option<String>,
+
+    /// `--eml`: envelope RCPT TO address(es), replacing the message's To/Cc/Bcc.
+    #[arg(long = "rcpt-to", value_name = "ADDR", requires = "eml", num_args = 1..)]
+    pub rcpt_to: Vec<String>,
 }
 
 #[derive(Args, Debug)]
 #[command(
-    after_help = "Examples:\n  xin reply <emailId> --text \"Reply text\"\n  xin reply <emailId> --reply-all --text \"Reply all\"\n  xin reply <emailId> --to other@example.com --text \"Custom recipients\"\n\nNotes:\n  - <emailId> is the JMAP Email id (from `xin search`, `xin messages search`, or `xin inbox next`)."
+    after_help = "Examples:\n  xin reply <emailId> --text \"Reply text\"\n  xin reply <emailId> --reply-all --text \"Reply all\"\n  xin reply <emailId> --to other@example.com --text \"Custom recipients\"\n  xin reply <emailId> --quote --text \"Answers inline below\"\n\nNotes:\n  - <emailId> is the JMAP Email id (from `xin search`, `xin messages search`, or `xin inbox next`)."
 )]
 pub struct ReplyArgs {
     /// Original email id (JMAP Email id).
@@ -565,6 +932,10 @@ pub struct ReplyArgs {
     #[arg(long = "reply-all")]
     pub reply_all: bool,
 
+    /// Quote the original below the reply (`>` lines in text, a blockquote in HTML).
+    #[arg(long)]
+    pub quote: bool,
+
     /// Override To recipients (otherwise inferred from original From).
     #[arg(long, num_args = 1..)]
     pub to: Vec<String>,
@@ -593,9 +964,95 @@ pub struct ReplyArgs {
     #[arg(long = "attach")]
     pub attach: Vec<String>,
 
+    /// Attach an existing blob by reference, without re-uploading: `<emailId>:<blobId>`
+    /// (name and type from that email's attachment) or a bare `<blobId>`.
+    #[arg(long = "attach-blob", value_name = "[EMAILID:]BLOBID")]
+    pub attach_blob: Vec<String>,
+
     /// Identity to send as (id or email).
     #[arg(long)]
     pub identity: Option<String>,
+
+    /// Append the sending identity's signature (textSignature / htmlSignature).
+    #[arg(long)]
+    pub signature: bool,
+
+    /// Schedule the send: RFC3339 time or delay like `+10m` (s/m/h/d). Needs FUTURERELEASE.
+    #[arg(long = "send-at", value_name = "WHEN")]
+    pub send_at: Option<String>,
+
+    /// After submitting, poll the delivery status until it is final (or `--wait-timeout`).
+    #[arg(long = "wait-delivery", conflicts_with = "send_at")]
+    pub wait_delivery: bool,
+
+    /// Seconds to wait for a final delivery status with `--wait-delivery`.
+    #[arg(
+        long = "wait-timeout",
+        value_name = "SECS",
+        default_value_t = 120,
+        requires = "wait_delivery"
+    )]
+    pub wait_timeout: u64,
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin forward <emailId> --to bob@example.com\n  xin forward <emailId> --to bob@example.com --text \"FYI, see below\"\n  xin forward <emailId> --to bob@example.com --as-attachment\n\nNotes:\n  - Inline forwards keep the original attachments (same blobIds, nothing is downloaded).\n  - --as-attachment attaches the original message as message/rfc822."
+)]
+pub struct ForwardArgs {
+    /// Email to forward (JMAP Email id).
+    pub email_id: String,
+
+    /// Recipient(s). Can be specified multiple times.
+    #[arg(long, required = true, num_args = 1..)]
+    pub to: Vec<String>,
+
+    #[arg(long)]
+    pub cc: Vec<String>,
+
+    #[arg(long)]
+    pub bcc: Vec<String>,
+
+    /// Subject override. Default: `Fwd: <original subject>`.
+    #[arg(long)]
+    pub subject: Option<String>,
+
+    /// Note above the forwarded message. Supports @/path/to/file.txt
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// HTML note above the forwarded message. Supports @/path/to/file.html
+    #[arg(long = "body-html")]
+    pub body_html: Option<String>,
+
+    /// Attach the original as a message/rfc822 part instead of forwarding inline.
+    #[arg(long = "as-attachment")]
+    pub as_attachment: bool,
+
+    /// Add attachment(s) by local file path.
+    #[arg(long = "attach")]
+    pub attach: Vec<String>,
+
+    /// Identity to send as (id or email).
+    #[arg(long)]
+    pub identity: Option<String>,
+
+    /// Schedule the send: RFC3339 time or delay like `+10m` (s/m/h/d). Needs FUTURERELEASE.
+    #[arg(long = "send-at", value_name = "WHEN")]
+    pub send_at: Option<String>,
+
+    /// After submitting, poll the delivery status until it is final (or `--wait-timeout`).
+    #[arg(long = "wait-delivery", conflicts_with = "send_at")]
+    pub wait_delivery: bool,
+
+    /// Seconds to wait for a final delivery status with `--wait-delivery`.
+    #[arg(
+        long = "wait-timeout",
+        value_name = "SECS",
+        default_value_t = 120,
+        requires = "wait_delivery"
+    )]
+    pub wait_timeout: u64,
 }
 
 #[derive(Subcommand, Debug)]
@@ -652,6 +1109,10 @@ pub struct DraftsCreateArgs {
     #[arg(long = "body-html")]
     pub body_html: Option<String>,
 
+    /// Markdown body, rendered to HTML plus a plain-text alternative. Supports @/path/to/file.md
+    #[arg(long, conflicts_with_all = ["body", "body_file", "body_html"])]
+    pub markdown: Option<String>,
+
     #[arg(long)]
     pub cc: Vec<String>,
 
@@ -661,8 +1122,17 @@ pub struct DraftsCreateArgs {
     #[arg(long = "attach")]
     pub attach: Vec<String>,
 
+    /// Attach an existing blob by reference, without re-uploading: `<emailId>:<blobId>`
+    /// (name and type from that email's attachment) or a bare `<blobId>`.
+    #[arg(long = "attach-blob", value_name = "[EMAILID:]BLOBID")]
+    pub attach_blob: Vec<String>,
+
     #[arg(long)]
     pub identity: Option<String>,
+
+    /// Append the sending identity's signature (textSignature / htmlSignature).
+    #[arg(long)]
+    pub signature: bool,
 }
 
 #[derive(Args, Debug)]
@@ -723,6 +1193,11 @@ pub struct DraftsRewriteArgs {
     #[arg(long = "attach")]
     pub attach: Vec<String>,
 
+    /// Attach an existing blob by reference, without re-uploading: `<emailId>:<blobId>`
+    /// (name and type from that email's attachment) or a bare `<blobId>`.
+    #[arg(long = "attach-blob", value_name = "[EMAILID:]BLOBID")]
+    pub attach_blob: Vec<String>,
+
     /// Replace existing attachments (default: append).
     #[arg(long)]
     pub replace_attachments: bool,
@@ -752,6 +1227,73 @@ pub struct DraftsSendArgs {
 
     #[arg(long)]
     pub identity: Option<String>,
+
+    /// Schedule the send: RFC3339 time or delay like `+10m` (s/m/h/d). Needs FUTURERELEASE.
+    #[arg(long = "send-at", value_name = "WHEN")]
+    pub send_at: Option<String>,
+
+    /// After submitting, poll the delivery status until it is final (or `--wait-timeout`).
+    #[arg(long = "wait-delivery", conflicts_with = "send_at")]
+    pub wait_delivery: bool,
+
+    /// Seconds to wait for a final delivery status with `--wait-delivery`.
+    #[arg(
+        long = "wait-timeout",
+        value_name = "SECS",
+        default_value_t = 120,
+        requires = "wait_delivery"
+    )]
+    pub wait_timeout: u64,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SubmissionsCommand {
+    /// List submissions (newest first).
+    List(SubmissionsListArgs),
+    /// Get submission(s) by id.
+    Get(SubmissionsGetArgs),
+    /// Cancel a pending submission (undo-send); the email goes back to Drafts.
+    Cancel(SubmissionsCancelArgs),
+    /// Submission changes since a state (`EmailSubmission/changes`), hydrated.
+    Changes(SubmissionsChangesArgs),
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin submissions list --pending\n  xin submissions list --max 20"
+)]
+pub struct SubmissionsListArgs {
+    #[arg(long = "max")]
+    pub max: Option<usize>,
+
+    /// Only submissions that can still be cancelled (`undoStatus: pending`).
+    #[arg(long)]
+    pub pending: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SubmissionsGetArgs {
+    #[arg(required = true)]
+    pub submission_ids: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(after_help = "Examples:\n  xin submissions cancel <submissionId>")]
+pub struct SubmissionsCancelArgs {
+    pub submission_id: String,
+}
+
+#[derive(Args, Debug)]
+#[command(
+    after_help = "Examples:\n  xin submissions changes\n  xin submissions changes --since <state>"
+)]
+pub struct SubmissionsChangesArgs {
+    /// State from a previous call; without it, only the current state is returned.
+    #[arg(long)]
+    pub since: Option<String>,
+
+    #[arg(long = "max")]
+    pub max: Option<usize>,
 }
 
 // --- Config / auth
@@ -783,6 +1325,36 @@ pub struct ConfigShowArgs {
     pub effective: bool,
 }
 
+#[derive(Subcommand, Debug)]
+#[command(
+    after_help = "Examples:\n  xin queries set invoices \"from:billing OR subject:invoice\"\n  xin --account work queries set triage \"in:inbox seen:false -from:noreply\"\n  xin queries list\n  xin queries delete invoices\n  xin search \"@invoices newer_than:30d\"\n\nNotes:\n  - Without --account, queries are global; with --account they belong to that account\n    and override global queries of the same name."
+)]
+pub enum QueriesCommand {
+    /// List saved queries (global and per account).
+    List,
+
+    /// Create or replace a saved query.
+    Set(QueriesSetArgs),
+
+    /// Delete a saved query.
+    Delete(QueriesDeleteArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct QueriesSetArgs {
+    /// Name used as `@name` (letters, digits, `_` and `-`).
+    pub name: String,
+
+    /// Sugar query; may reference other saved queries.
+    #[arg(allow_hyphen_values = true)]
+    pub query: String,
+}
+
+#[derive(Args, Debug)]
+pub struct QueriesDeleteArgs {
+    pub name: String,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum AuthCommand {
     /// Store a bearer token for an account (writes tokenFile and updates config).
@@ -799,7 +1371,7 @@ pub struct AuthSetTokenArgs {
 
 #[derive(Args, Debug)]
 #[command(
-    after_help = "Examples:\n  xin history\n  xin history --since <state>\n  xin history --since <state> --hydrate\n\nPaging:\n  - If meta.nextPage is set, continue with: xin history --page <TOKEN>"
+    after_help = "Examples:\n  xin history\n  xin history --since <state>\n  xin history --since <state> --hydrate\n  xin history --types email,mailbox\n\nPaging:\n  - If meta.nextPage is set, continue with: xin history --page <TOKEN>"
 )]
 pub struct HistoryArgs {
     #[arg(long)]
@@ -814,11 +1386,15 @@ pub struct HistoryArgs {
     /// When set, also fetch a summary for changed emails (created/updated) via Email/get.
     #[arg(long)]
     pub hydrate: bool,
+
+    /// Data types to track (comma-separated). Default: email.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub types: Vec<ChangeType>,
 }
 
 #[derive(Args, Debug)]
 #[command(
-    after_help = "Examples:\n  xin watch --checkpoint /tmp/xin.watch.token\n  xin watch --since <state> --once\n\nNotes:\n  - Default output is NDJSON stream for agents.\n  - Use --pretty for human-friendly pretty JSON.\n  - Use --no-envelope for stream-only output (no final envelope line)."
+    after_help = "Examples:\n  xin watch --checkpoint /tmp/xin.watch.token\n  xin watch --since <state> --once\n  xin watch --transport websocket --checkpoint /tmp/xin.watch.token\n  xin watch --transport eventsource --checkpoint /tmp/xin.watch.token\n  xin watch --types email,mailbox,submission --checkpoint /tmp/xin.watch.token\n  xin watch --query \"@invoices\" --hydrate\n\nNotes:\n  - Default output is NDJSON stream for agents.\n  - Use --pretty for human-friendly pretty JSON.\n  - Use --no-envelope for stream-only output (no final envelope line)."
 )]
 pub struct WatchArgs {
     /// Start watching from this state (like history --since).
@@ -845,6 +1421,20 @@ pub struct WatchArgs {
     #[arg(long, default_value_t = 600)]
     pub jitter_ms: u64,
 
+    /// How to learn about new changes. `websocket` (RFC 8887) and `eventsource` (RFC 8620 §7.3)
+    /// use JMAP push and fall back to polling when the server does not advertise them.
+    #[arg(long, value_enum, default_value_t = WatchTransport::Poll)]
+    pub transport: WatchTransport,
+
+    /// Data types to track (comma-separated). Default: email.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub types: Vec<ChangeType>,
+
+    /// Only report created/updated emails matching this sugar query (e.g. `@invoices`).
+    /// Destroyed ids are always reported.
+    #[arg(long, value_name = "QUERY", allow_hyphen_values = true)]
+    pub query: Option<String>,
+
     /// Persist the latest page token to this file (and resume from it if present).
     #[arg(long)]
     pub checkpoint: Option<std::path::PathBuf>,
@@ -861,3 +1451,20 @@ pub struct WatchArgs {
     #[arg(long)]
     pub no_envelope: bool,
 }
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchTransport {
+    Poll,
+    Websocket,
+    Eventsource,
+}
+
+/// JMAP data types tracked by `history` / `watch`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeType {
+    Email,
+    Mailbox,
+    Thread,
+    /// EmailSubmission
+    Submission,
+}
@@ -0,0 +1,342 @@
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::{Backend, EmailPager};
+use crate::cli::AttachmentsExportArgs;
+use crate::error::XinErrorOut;
+use crate::filename;
+use crate::output::{Envelope, Meta};
+use crate::sugar;
+use crate::transfer::{self, Progress};
+
+/// Emails per `Email/query` page.
+const PAGE_SIZE: usize = 50;
+
+const EMAIL_PROPERTIES: &[&str] = &[
+    "id",
+    "threadId",
+    "receivedAt",
+    "subject",
+    "from",
+    "attachments",
+];
+
+/// Attachments written to one `--dir`, deduplicated by SHA-256 and recorded in its
+/// `manifest.json`. An existing manifest is loaded first, so re-runs skip blobs already
+/// exported and never overwrite earlier files.
+pub(super) struct AttachmentExport {
+    dir: PathBuf,
+    progress: bool,
+    /// `manifest.json` `files` entries.
+    files: Vec<Value>,
+    by_hash: HashMap<String, usize>,
+    by_blob: HashMap<String, usize>,
+    /// Lowercased names already used in `dir`.
+    taken: HashSet<String>,
+
+    emails: usize,
+    attachments: usize,
+    written: Vec<String>,
+    duplicates: usize,
+    already_exported: usize,
+    failed: Vec<Value>,
+}
+
+impl AttachmentExport {
+    pub(super) fn open(dir: &str, progress: bool) -> Result<Self, XinErrorOut> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)
+            .map_err(|e| XinErrorOut::usage(format!("failed to create {}: {e}", dir.display())))?;
+
+        let manifest_path = dir.join("manifest.json");
+        let files: Vec<Value> = match fs::read_to_string(&manifest_path) {
+            Ok(text) => serde_json::from_str::<Value>(&text)
+                .ok()
+                .and_then(|mut v| v.get_mut("files").map(Value::take))
+                .and_then(|f| match f {
+                    Value::Array(a) => Some(a),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    XinErrorOut::usage(format!(
+                        "{} is not an xin attachment manifest",
+                        manifest_path.display()
+                    ))
+                })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(XinErrorOut::usage(format!(
+                    "failed to read {}: {e}",
+                    manifest_path.display()
+                )));
+            }
+        };
+
+        let mut taken: HashSet<String> = fs::read_dir(&dir)
+            .map_err(|e| XinErrorOut::usage(format!("failed to read {}: {e}", dir.display())))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+            .collect();
+        taken.insert("manifest.json".to_string());
+
+        let mut by_hash: HashMap<String, usize> = HashMap::new();
+        let mut by_blob: HashMap<String, usize> = HashMap::new();
+        for (i, f) in files.iter().enumerate() {
+            if let Some(sha) = f.get("sha256").and_then(|v| v.as_str()) {
+                by_hash.insert(sha.to_string(), i);
+            }
+            let blob_ids = f
+                .get("sources")
+                .and_then(|s| s.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|s| s.get("blobId").and_then(|v| v.as_str()));
+            for blob_id in blob_ids {
+                by_blob.insert(blob_id.to_string(), i);
+            }
+            if let Some(name) = f.get("file").and_then(|v| v.as_str()) {
+                taken.insert(name.to_lowercase());
+            }
+        }
+
+        Ok(Self {
+            dir,
+            progress,
+            files,
+            by_hash,
+            by_blob,
+            taken,
+            emails: 0,
+            attachments: 0,
+            written: Vec::new(),
+            duplicates: 0,
+            already_exported: 0,
+            failed: Vec::new(),
+        })
+    }
+
+    /// Download the attachments of one email (in `Email/get` shape: `id`, `threadId`,
+    /// `receivedAt`, `subject`, `from`, `attachments`). A failed download is recorded in
+    /// `failed` and the export goes on. On an error the manifest is written first, so the
+    /// files already moved into place are not downloaded again by the next run.
+    pub(super) async fn add_email(
+        &mut self,
+        backend: &Backend,
+        email: &Value,
+    ) -> Result<(), XinErrorOut> {
+        let result = self.add_attachments(backend, email).await;
+        if result.is_err() {
+            let _ = self.write_manifest();
+        }
+        result
+    }
+
+    async fn add_attachments(
+        &mut self,
+        backend: &Backend,
+        email: &Value,
+    ) -> Result<(), XinErrorOut> {
+        self.emails += 1;
+        let attachments = email
+            .get("attachments")
+            .and_then(|a| a.as_array())
+            .into_iter()
+            .flatten();
+
+        for att in attachments {
+            let Some(blob_id) = att.get("blobId").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            self.attachments += 1;
+            let name = att.get("name").and_then(|v| v.as_str());
+            let source = json!({
+                "emailId": email.get("id"),
+                "threadId": email.get("threadId"),
+                "blobId": blob_id,
+                "name": name,
+                "from": email.pointer("/from/0"),
+                "receivedAt": email.get("receivedAt"),
+                "subject": email.get("subject"),
+            });
+
+            if let Some(&i) = self.by_blob.get(blob_id) {
+                self.already_exported += 1;
+                self.add_source(i, source);
+                continue;
+            }
+
+            let tmp = self.dir.join(format!(
+                ".xin-{}.download",
+                filename::sanitize(blob_id, "blob")
+            ));
+            let mut progress = Progress::new("download", blob_id, self.progress);
+            let download = match backend
+                .download_blob_to(blob_id, name.unwrap_or("attachment"), &tmp, &mut progress)
+                .await
+            {
+                Ok(d) => d,
+                Err(e) => {
+                    self.failed.push(json!({
+                        "emailId": email.get("id"),
+                        "blobId": blob_id,
+                        "name": name,
+                        "error": e.message,
+                    }));
+                    continue;
+                }
+            };
+            let sha256 = transfer::sha256_file(&tmp).map_err(|e| {
+                XinErrorOut::usage(format!("failed to read {}: {e}", tmp.display()))
+            })?;
+
+            if let Some(&i) = self.by_hash.get(&sha256) {
+                let _ = fs::remove_file(&tmp);
+                self.duplicates += 1;
+                self.by_blob.insert(blob_id.to_string(), i);
+                self.add_source(i, source);
+                continue;
+            }
+
+            let file = filename::unique(
+                &filename::sanitize(name.unwrap_or(""), "attachment"),
+                &self.taken,
+            );
+            fs::rename(&tmp, self.dir.join(&file))
+                .map_err(|e| XinErrorOut::usage(format!("failed to write {file}: {e}")))?;
+
+            let i = self.files.len();
+            self.files.push(json!({
+                "file": file,
+                "sha256": sha256,
+                "size": download.bytes,
+                "type": att.get("type"),
+                "sources": [source],
+            }));
+            self.taken.insert(file.to_lowercase());
+            self.by_hash.insert(sha256, i);
+            self.by_blob.insert(blob_id.to_string(), i);
+            self.written.push(file);
+        }
+        Ok(())
+    }
+
+    /// Record another email carrying the content of `files[i]` (once per email and blob).
+    fn add_source(&mut self, i: usize, source: Value) {
+        let Some(sources) = self.files[i]
+            .get_mut("sources")
+            .and_then(|s| s.as_array_mut())
+        else {
+            return;
+        };
+        let known = sources.iter().any(|s| {
+            s.get("emailId") == source.get("emailId") && s.get("blobId") == source.get("blobId")
+        });
+        if !known {
+            sources.push(source);
+        }
+    }
+
+    /// Write `manifest.json` (through a temporary file, so an interrupted run keeps the
+    /// previous one).
+    pub(super) fn write_manifest(&self) -> Result<(), XinErrorOut> {
+        let path = self.manifest_path();
+        let tmp = self.dir.join(".manifest.json.tmp");
+        let text =
+            serde_json::to_string_pretty(&json!({"files": self.files})).expect("manifest json");
+        fs::write(&tmp, text + "\n")
+            .and_then(|_| fs::rename(&tmp, &path))
+            .map_err(|e| XinErrorOut::usage(format!("failed to write {}: {e}", path.display())))
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join("manifest.json")
+    }
+
+    /// Failed downloads, as `meta.warnings` entries.
+    pub(super) fn warnings(&self) -> Vec<String> {
+        self.failed
+            .iter()
+            .map(|f| {
+                format!(
+                    "failed to download {} from {}: {}",
+                    f["blobId"].as_str().unwrap_or(""),
+                    f["emailId"].as_str().unwrap_or(""),
+                    f["error"].as_str().unwrap_or("")
+                )
+            })
+            .collect()
+    }
+
+    pub(super) fn summary(&self) -> Value {
+        json!({
+            "dir": path_string(&self.dir),
+            "manifest": path_string(&self.manifest_path()),
+            "emails": self.emails,
+            "attachments": self.attachments,
+            "written": self.written,
+            "duplicates": self.duplicates,
+            "alreadyExported": self.already_exported,
+            "failed": self.failed,
+        })
+    }
+}
+
+fn path_string(p: &Path) -> String {
+    p.to_string_lossy().into_owned()
+}
+
+pub async fn export(account: Option<String>, args: &AttachmentsExportArgs) -> Envelope<Value> {
+    let command_name = "attachments.export";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let filter =
+        sugar::query_filter(args.query.as_deref(), args.filter_json.as_deref(), &backend).await;
+    let filter = match filter {
+        Ok(f) if f.as_object().is_some_and(|o| o.is_empty()) => json!({"hasAttachment": true}),
+        Ok(f) => json!({"operator": "AND", "conditions": [f, {"hasAttachment": true}]}),
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let mut export = match AttachmentExport::open(&args.dir, !args.no_progress) {
+        Ok(x) => x,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    // Oldest first, so the first copy of a file keeps the plain name.
+    let sort = vec![json!({"property": "receivedAt", "isAscending": true})];
+    let mut pages = EmailPager::new(filter, sort, PAGE_SIZE, EMAIL_PROPERTIES);
+    loop {
+        let page = match pages.next_page(&backend).await {
+            Ok(Some(p)) => p,
+            Ok(None) => break,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+
+        for email in &page.emails {
+            if let Err(e) = export.add_email(&backend, email).await {
+                return Envelope::err(command_name, account, e);
+            }
+        }
+        // After every page, so an interrupted export resumes where it stopped.
+        if let Err(e) = export.write_manifest() {
+            return Envelope::err(command_name, account, e);
+        }
+    }
+
+    let warnings = export.warnings();
+    Envelope::ok(
+        command_name,
+        account,
+        export.summary(),
+        Meta {
+            warnings: (!warnings.is_empty()).then_some(warnings),
+            ..Meta::default()
+        },
+    )
+}
@@ -88,6 +88,8 @@ pub async fn list() -> Envelope<Value> {
             "sessionUrl": acct.session_url,
             "auth": auth,
             "trustRedirectHosts": acct.trust_redirect_hosts,
+            "timezone": acct.timezone,
+            "afterSend": acct.after_send.map(|a| a.as_str()),
         }));
     }
 
@@ -160,10 +162,20 @@ pub async fn show(cli_account: Option<&str>, args: &ConfigShowArgs) -> Envelope<
                 "runtime": {
                     "baseUrl": resolved.config.base_url,
                     "auth": auth,
-                    "trustRedirectHosts": resolved.config.follow_redirect_hosts
+                    "trustRedirectHosts": resolved.config.follow_redirect_hosts,
+                    "timezone": resolved.config.timezone.as_ref().ok().map(|tz| tz.name()),
+                    "afterSend": resolved.config.after_send.as_str()
                 }
             }),
-            Meta::default(),
+            Meta {
+                warnings: resolved
+                    .config
+                    .timezone
+                    .as_ref()
+                    .err()
+                    .map(|e| vec![e.message.clone()]),
+                ..Meta::default()
+            },
         );
     }
 
@@ -0,0 +1,457 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::history::fetch_changes;
+use crate::backend::{Backend, EmailPager};
+use crate::cli::{ChangeType, ExportArgs, ExportFormat};
+use crate::error::XinErrorOut;
+use crate::mailstore;
+use crate::output::{Envelope, Meta};
+use crate::sugar;
+use crate::transfer::Progress;
+
+/// Emails per `Email/query` page (and per `Email/get` in incremental runs).
+const PAGE_SIZE: usize = 100;
+
+/// `maxChanges` per `Email/changes` call in incremental runs.
+const MAX_CHANGES: usize = 500;
+
+const EMAIL_PROPERTIES: &[&str] = &["id", "blobId", "receivedAt", "keywords", "from"];
+
+fn format_name(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Mbox => "mbox",
+        ExportFormat::Maildir => "maildir",
+        ExportFormat::Eml => "eml",
+    }
+}
+
+/// The export state file: next to an mbox, inside a Maildir / .eml directory.
+fn state_path(format: ExportFormat, out: &Path) -> PathBuf {
+    match format {
+        ExportFormat::Mbox => {
+            let mut name = out.file_name().unwrap_or_default().to_os_string();
+            name.push(".xin-export.json");
+            out.with_file_name(name)
+        }
+        ExportFormat::Maildir | ExportFormat::Eml => out.join(".xin-export.json"),
+    }
+}
+
+/// What the previous export into `--out` saved.
+#[derive(Default)]
+struct SavedState {
+    state: Option<String>,
+    /// mbox only: emails appended after `state` by runs that could not advance it.
+    exported: Vec<String>,
+}
+
+fn read_state(path: &Path, format: ExportFormat) -> Result<SavedState, XinErrorOut> {
+    let text = match fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SavedState::default()),
+        Err(e) => {
+            return Err(XinErrorOut::usage(format!(
+                "failed to read {}: {e}",
+                path.display()
+            )));
+        }
+    };
+    let saved: Value = serde_json::from_str(&text).map_err(|_| {
+        XinErrorOut::usage(format!("{} is not an xin export state", path.display()))
+    })?;
+    let saved_format = saved.get("format").and_then(|v| v.as_str());
+    if saved_format != Some(format_name(format)) {
+        return Err(XinErrorOut::usage(format!(
+            "{} was written by a {} export, not {}",
+            path.display(),
+            saved_format.unwrap_or("different"),
+            format_name(format)
+        )));
+    }
+    Ok(SavedState {
+        state: saved
+            .get("state")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        exported: saved
+            .get("exported")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+fn write_state(
+    path: &Path,
+    format: ExportFormat,
+    state: &str,
+    exported: &[String],
+) -> Result<(), XinErrorOut> {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let mut saved = json!({
+        "format": format_name(format),
+        "state": state,
+    });
+    if !exported.is_empty() {
+        saved["exported"] = json!(exported);
+    }
+    let text = serde_json::to_string_pretty(&saved).expect("state json");
+    fs::write(&tmp, text + "\n")
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| XinErrorOut::usage(format!("failed to write {}: {e}", path.display())))
+}
+
+fn received_at(email: &Value) -> Option<DateTime<Utc>> {
+    email
+        .get("receivedAt")
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|d| d.with_timezone(&Utc))
+}
+
+fn local_error(path: &Path, e: std::io::Error) -> XinErrorOut {
+    XinErrorOut::usage(format!("failed to write {}: {e}", path.display()))
+}
+
+/// Messages written to one `--out`. Emails already there (a Maildir / .eml file, or an id
+/// recorded for an mbox in the state file) are skipped, so re-runs never duplicate them.
+struct ExportWriter {
+    format: ExportFormat,
+    out: PathBuf,
+    mbox: Option<BufWriter<File>>,
+    /// Email ids already in `out`.
+    existing: HashSet<String>,
+
+    emails: usize,
+    written: usize,
+    already_exported: usize,
+    failed: Vec<Value>,
+}
+
+impl ExportWriter {
+    /// `append`: add to an existing mbox instead of replacing it.
+    fn open(format: ExportFormat, out: &Path, append: bool) -> Result<Self, XinErrorOut> {
+        let mut mbox = None;
+        let mut existing = HashSet::new();
+        match format {
+            ExportFormat::Mbox => {
+                if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent).map_err(|e| local_error(parent, e))?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(append)
+                    .truncate(!append)
+                    .open(out)
+                    .map_err(|e| local_error(out, e))?;
+                mbox = Some(BufWriter::new(file));
+            }
+            ExportFormat::Maildir => {
+                for sub in ["tmp", "new", "cur"] {
+                    let dir = out.join(sub);
+                    fs::create_dir_all(&dir).map_err(|e| local_error(&dir, e))?;
+                }
+                for sub in ["new", "cur"] {
+                    existing.extend(
+                        file_names(&out.join(sub))?.iter().filter_map(|name| {
+                            mailstore::maildir_email_id(name).map(str::to_string)
+                        }),
+                    );
+                }
+            }
+            ExportFormat::Eml => {
+                fs::create_dir_all(out).map_err(|e| local_error(out, e))?;
+                existing.extend(
+                    file_names(out)?
+                        .iter()
+                        .filter_map(|name| mailstore::eml_email_id(name).map(str::to_string)),
+                );
+            }
+        }
+
+        Ok(Self {
+            format,
+            out: out.to_path_buf(),
+            mbox,
+            existing,
+            emails: 0,
+            written: 0,
+            already_exported: 0,
+            failed: Vec::new(),
+        })
+    }
+
+    /// Download one email's raw message (`id`, `blobId`, `receivedAt`, `keywords`, `from`)
+    /// into the container. A failed download is recorded in `failed` and the export goes on.
+    async fn add(&mut self, backend: &Backend, email: &Value) -> Result<(), XinErrorOut> {
+        self.emails += 1;
+        let Some(id) = email.get("id").and_then(|v| v.as_str()) else {
+            return Ok(());
+        };
+        if self.existing.contains(id) {
+            self.already_exported += 1;
+            return Ok(());
+        }
+        let Some(blob_id) = email.get("blobId").and_then(|v| v.as_str()) else {
+            self.failed
+                .push(json!({"emailId": id, "error": "no blobId"}));
+            return Ok(());
+        };
+        let received = received_at(email);
+
+        // Downloaded here first, then moved (or copied, for mbox) into place.
+        let (download_path, base) = match self.format {
+            ExportFormat::Eml => (self.out.join(mailstore::eml_name(id, received)), None),
+            ExportFormat::Maildir => {
+                let base = mailstore::maildir_base(id, received);
+                (self.out.join("tmp").join(&base), Some(base))
+            }
+            // One temporary file per blob, so an interrupted download is only ever resumed
+            // for the same message.
+            ExportFormat::Mbox => (self.out.with_file_name(format!(".xin-{blob_id}.eml")), None),
+        };
+
+        let mut progress = Progress::new("download", blob_id, false);
+        if let Err(e) = backend
+            .download_blob_to(blob_id, &format!("{id}.eml"), &download_path, &mut progress)
+            .await
+        {
+            self.failed.push(json!({
+                "emailId": id,
+                "blobId": blob_id,
+                "error": e.message,
+            }));
+            return Ok(());
+        }
+
+        match self.format {
+            ExportFormat::Eml => {}
+            ExportFormat::Maildir => {
+                let flags = mailstore::maildir_flags(email.get("keywords"));
+                let name = mailstore::maildir_name(base.as_deref().unwrap_or(id), &flags);
+                let target = self.out.join("cur").join(name);
+                fs::rename(&download_path, &target).map_err(|e| local_error(&target, e))?;
+            }
+            ExportFormat::Mbox => {
+                let mbox = self.mbox.as_mut().expect("mbox writer");
+                let sender = email.pointer("/from/0/email").and_then(|v| v.as_str());
+                let message =
+                    File::open(&download_path).map_err(|e| local_error(&download_path, e))?;
+                writeln!(mbox, "{}", mailstore::mbox_from_line(sender, received))
+                    .and_then(|_| mailstore::write_mboxrd(BufReader::new(message), mbox))
+                    .map_err(|e| local_error(&self.out, e))?;
+                let _ = fs::remove_file(&download_path);
+            }
+        }
+
+        self.existing.insert(id.to_string());
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), XinErrorOut> {
+        match self.mbox.as_mut() {
+            Some(mbox) => mbox.flush().map_err(|e| local_error(&self.out, e)),
+            None => Ok(()),
+        }
+    }
+
+    /// The mbox emails to record in the state file when it is saved without advancing.
+    fn appended(&self) -> Vec<String> {
+        if self.mbox.is_none() {
+            return Vec::new();
+        }
+        let mut ids: Vec<String> = self.existing.iter().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+fn file_names(dir: &Path) -> Result<Vec<String>, XinErrorOut> {
+    Ok(fs::read_dir(dir)
+        .map_err(|e| XinErrorOut::usage(format!("failed to read {}: {e}", dir.display())))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect())
+}
+
+pub async fn export(account: Option<String>, args: &ExportArgs) -> Envelope<Value> {
+    let command_name = "export";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let filter =
+        sugar::query_filter(args.query.as_deref(), args.filter_json.as_deref(), &backend).await;
+    let filter = match filter {
+        Ok(f) => f,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let out = PathBuf::from(&args.out);
+    let state_file = state_path(args.format, &out);
+    let saved = match (&args.since, args.incremental) {
+        (Some(s), _) => SavedState {
+            state: Some(s.clone()),
+            exported: Vec::new(),
+        },
+        (None, true) => match read_state(&state_file, args.format) {
+            Ok(s) => s,
+            Err(e) => return Envelope::err(command_name, account, e),
+        },
+        (None, false) => SavedState::default(),
+    };
+    let since = saved.state;
+
+    // A full mbox export replaces the file, so the old state no longer describes it.
+    if since.is_none() && args.format == ExportFormat::Mbox {
+        let _ = fs::remove_file(&state_file);
+    }
+
+    let mut writer = match ExportWriter::open(args.format, &out, since.is_some()) {
+        Ok(w) => w,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    writer.existing.extend(saved.exported);
+
+    let result = match &since {
+        Some(since) => export_changes(&backend, &filter, since, &mut writer).await,
+        None => export_all(&backend, &filter, &mut writer).await,
+    };
+    let result = result.and_then(|state| writer.finish().map(|_| state));
+
+    // An incremental mbox run that cannot advance the state records what it appended, so
+    // the next run from the same state does not append it again.
+    let keep_appended = |writer: &ExportWriter| match &since {
+        Some(since) if !writer.appended().is_empty() => {
+            write_state(&state_file, args.format, since, &writer.appended())
+        }
+        _ => Ok(()),
+    };
+    let new_state = match result {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = writer.finish().and_then(|_| keep_appended(&writer));
+            return Envelope::err(command_name, account, e);
+        }
+    };
+
+    // With failures the state stays where it was, so the next incremental run retries them.
+    let mut warnings: Vec<String> = writer
+        .failed
+        .iter()
+        .map(|f| {
+            format!(
+                "failed to download {}: {}",
+                f["emailId"].as_str().unwrap_or(""),
+                f["error"].as_str().unwrap_or("")
+            )
+        })
+        .collect();
+    let saved_state = if writer.failed.is_empty() {
+        if let Err(e) = write_state(&state_file, args.format, &new_state, &[]) {
+            return Envelope::err(command_name, account, e);
+        }
+        Some(new_state)
+    } else {
+        if let Err(e) = keep_appended(&writer) {
+            return Envelope::err(command_name, account, e);
+        }
+        warnings.push(format!(
+            "{} message(s) failed; export state not advanced, re-run to retry",
+            writer.failed.len()
+        ));
+        None
+    };
+
+    Envelope::ok(
+        command_name,
+        account,
+        json!({
+            "format": format_name(args.format),
+            "out": out.to_string_lossy(),
+            "since": since,
+            "state": saved_state,
+            "stateFile": state_file.to_string_lossy(),
+            "emails": writer.emails,
+            "written": writer.written,
+            "alreadyExported": writer.already_exported,
+            "failed": writer.failed,
+        }),
+        Meta {
+            warnings: (!warnings.is_empty()).then_some(warnings),
+            ..Meta::default()
+        },
+    )
+}
+
+/// Full export, oldest first. Returns the Email state taken before the first query, so
+/// mail arriving during the export is picked up by the next incremental run.
+async fn export_all(
+    backend: &Backend,
+    filter: &Value,
+    writer: &mut ExportWriter,
+) -> Result<String, XinErrorOut> {
+    let state = backend.email_state().await?;
+
+    let sort = vec![json!({"property": "receivedAt", "isAscending": true})];
+    let mut pages = EmailPager::new(filter.clone(), sort, PAGE_SIZE, EMAIL_PROPERTIES);
+    while let Some(page) = pages.next_page(backend).await? {
+        for email in &page.emails {
+            writer.add(backend, email).await?;
+        }
+    }
+    Ok(state)
+}
+
+/// Incremental export: emails created since `since` (`Email/changes`) that match
+/// `filter`, oldest first. Returns the new state.
+async fn export_changes(
+    backend: &Backend,
+    filter: &Value,
+    since: &str,
+    writer: &mut ExportWriter,
+) -> Result<String, XinErrorOut> {
+    let mut state = since.to_string();
+    let mut created: Vec<String> = Vec::new();
+    loop {
+        let changes = fetch_changes(backend, ChangeType::Email, &state, MAX_CHANGES, false).await?;
+        created.extend(changes.created);
+        state = changes.new_state;
+        if !changes.has_more {
+            break;
+        }
+    }
+
+    let mut matching = BTreeSet::new();
+    for chunk in created.chunks(PAGE_SIZE) {
+        matching.extend(backend.email_ids_matching(filter, chunk).await?);
+    }
+    let ids: Vec<String> = created
+        .into_iter()
+        .filter(|id| matching.contains(id))
+        .collect();
+
+    let mut emails = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(PAGE_SIZE) {
+        emails.extend(backend.get_emails_raw(chunk, EMAIL_PROPERTIES).await?);
+    }
+    emails.sort_by_key(received_at);
+    for email in &emails {
+        writer.add(backend, email).await?;
+    }
+    Ok(state)
+}
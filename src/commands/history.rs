@@ -1,8 +1,11 @@
+use std::collections::BTreeMap;
+
 use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jmap_client::email::Email;
 use serde_json::{Value, json};
 
 use crate::backend::Backend;
-use crate::cli::HistoryArgs;
+use crate::cli::{ChangeType, HistoryArgs};
 use crate::error::XinErrorOut;
 use crate::output::{Envelope, Meta};
 use crate::schema;
@@ -14,6 +17,251 @@ pub(crate) struct PageToken {
 
     #[serde(rename = "maxChanges")]
     pub(crate) max_changes: usize,
+
+    /// Per-type states (keyed by `--types` name) when tracking more than Email.
+    /// Email-only tokens keep the original `{sinceState,maxChanges}` shape.
+    #[serde(
+        rename = "sinceStates",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub(crate) since_states: BTreeMap<String, String>,
+}
+
+impl PageToken {
+    pub(crate) fn from_states(states: &BTreeMap<ChangeType, String>, max_changes: usize) -> Self {
+        let since_state = states.get(&ChangeType::Email).cloned().unwrap_or_default();
+        let since_states = if states.keys().eq([ChangeType::Email].iter()) {
+            BTreeMap::new()
+        } else {
+            states
+                .iter()
+                .map(|(t, s)| (change_type_name(*t).to_string(), s.clone()))
+                .collect()
+        };
+
+        Self {
+            since_state,
+            max_changes,
+            since_states,
+        }
+    }
+
+    pub(crate) fn states(&self) -> Result<BTreeMap<ChangeType, String>, XinErrorOut> {
+        if self.since_states.is_empty() {
+            return Ok(BTreeMap::from([(
+                ChangeType::Email,
+                self.since_state.clone(),
+            )]));
+        }
+
+        self.since_states
+            .iter()
+            .map(|(name, state)| {
+                parse_change_type(name)
+                    .map(|t| (t, state.clone()))
+                    .ok_or_else(|| XinErrorOut::usage(format!("invalid page token type: {name}")))
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn change_type_name(t: ChangeType) -> &'static str {
+    match t {
+        ChangeType::Email => "email",
+        ChangeType::Mailbox => "mailbox",
+        ChangeType::Thread => "thread",
+        ChangeType::Submission => "submission",
+    }
+}
+
+fn parse_change_type(name: &str) -> Option<ChangeType> {
+    match name {
+        "email" => Some(ChangeType::Email),
+        "mailbox" => Some(ChangeType::Mailbox),
+        "thread" => Some(ChangeType::Thread),
+        "submission" => Some(ChangeType::Submission),
+        _ => None,
+    }
+}
+
+/// JMAP data type name (as used in method names and StateChange objects).
+pub(crate) fn jmap_data_type(t: ChangeType) -> &'static str {
+    match t {
+        ChangeType::Email => "Email",
+        ChangeType::Mailbox => "Mailbox",
+        ChangeType::Thread => "Thread",
+        ChangeType::Submission => "EmailSubmission",
+    }
+}
+
+/// `--types` as a sorted, de-duplicated list (default: email).
+pub(crate) fn selected_types(types: &[ChangeType]) -> Vec<ChangeType> {
+    let mut out = types.to_vec();
+    out.sort();
+    out.dedup();
+    if out.is_empty() {
+        out.push(ChangeType::Email);
+    }
+    out
+}
+
+pub(crate) fn is_email_only(types: &[ChangeType]) -> bool {
+    types == [ChangeType::Email]
+}
+
+/// One `<Type>/changes` page.
+pub(crate) struct TypeChanges {
+    pub(crate) since_state: String,
+    pub(crate) new_state: String,
+    pub(crate) has_more: bool,
+    pub(crate) created: Vec<String>,
+    pub(crate) updated: Vec<String>,
+    pub(crate) destroyed: Vec<String>,
+    /// Mailbox/changes only: set when only counts changed.
+    pub(crate) updated_properties: Option<Vec<String>>,
+    /// Email only (`--hydrate`): summaries of created/updated emails.
+    pub(crate) hydrated: Option<(Vec<Email>, Vec<Email>)>,
+}
+
+impl TypeChanges {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty() && self.destroyed.is_empty()
+    }
+
+    /// Keep only created/updated ids (and hydrated emails) in `keep`; destroyed ids stay.
+    pub(crate) fn retain_ids(&mut self, keep: &std::collections::BTreeSet<String>) {
+        self.created.retain(|id| keep.contains(id));
+        self.updated.retain(|id| keep.contains(id));
+        if let Some((created, updated)) = &mut self.hydrated {
+            let kept = |e: &Email| e.id().is_some_and(|id| keep.contains(id));
+            created.retain(kept);
+            updated.retain(kept);
+        }
+    }
+
+    /// Per-type JSON (used by multi-type `history` and typed `watch` events).
+    pub(crate) fn to_json(&self) -> Value {
+        let mut v = json!({
+            "sinceState": self.since_state,
+            "newState": self.new_state,
+            "hasMoreChanges": self.has_more,
+            "created": self.created,
+            "updated": self.updated,
+            "destroyed": self.destroyed
+        });
+        let obj = v.as_object_mut().expect("changes object");
+        if let Some(props) = &self.updated_properties {
+            obj.insert("updatedProperties".to_string(), json!(props));
+        }
+        if let Some((created, updated)) = &self.hydrated {
+            obj.insert(
+                "hydrated".to_string(),
+                json!({
+                    "created": schema::email_summary_items(created),
+                    "updated": schema::email_summary_items(updated)
+                }),
+            );
+        }
+        v
+    }
+}
+
+fn string_list(v: &Value, key: &str) -> Vec<String> {
+    v.get(key)
+        .and_then(|x| x.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|x| x.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Fetch one page of changes for `t`.
+pub(crate) async fn fetch_changes(
+    backend: &Backend,
+    t: ChangeType,
+    since_state: &str,
+    max_changes: usize,
+    hydrate: bool,
+) -> Result<TypeChanges, XinErrorOut> {
+    if t == ChangeType::Email {
+        let (mut resp, hydrated) = if hydrate {
+            let (r, created, updated) = backend
+                .email_changes_hydrate(since_state, Some(max_changes))
+                .await?;
+            (r, Some((created, updated)))
+        } else {
+            (
+                backend
+                    .email_changes(since_state, Some(max_changes))
+                    .await?,
+                None,
+            )
+        };
+
+        return Ok(TypeChanges {
+            since_state: since_state.to_string(),
+            has_more: resp.has_more_changes(),
+            new_state: resp.take_new_state(),
+            created: resp.take_created(),
+            updated: resp.take_updated(),
+            destroyed: resp.take_destroyed(),
+            updated_properties: None,
+            hydrated,
+        });
+    }
+
+    let v = backend
+        .object_changes(jmap_data_type(t), since_state, Some(max_changes))
+        .await?;
+
+    Ok(TypeChanges {
+        since_state: since_state.to_string(),
+        new_state: v
+            .get("newState")
+            .and_then(|s| s.as_str())
+            .unwrap_or(since_state)
+            .to_string(),
+        has_more: v
+            .get("hasMoreChanges")
+            .and_then(|b| b.as_bool())
+            .unwrap_or(false),
+        created: string_list(&v, "created"),
+        updated: string_list(&v, "updated"),
+        destroyed: string_list(&v, "destroyed"),
+        updated_properties: v
+            .get("updatedProperties")
+            .filter(|p| !p.is_null())
+            .map(|_| string_list(&v, "updatedProperties")),
+        hydrated: None,
+    })
+}
+
+/// Current state for each type (bootstrap).
+pub(crate) async fn current_states(
+    backend: &Backend,
+    types: &[ChangeType],
+) -> Result<BTreeMap<ChangeType, String>, XinErrorOut> {
+    let mut out = BTreeMap::new();
+    for t in types {
+        let state = if *t == ChangeType::Email {
+            backend.email_state().await?
+        } else {
+            backend.object_state(jmap_data_type(*t)).await?
+        };
+        out.insert(*t, state);
+    }
+    Ok(out)
+}
+
+fn states_json(states: &BTreeMap<ChangeType, String>) -> Value {
+    states
+        .iter()
+        .map(|(t, s)| (change_type_name(*t).to_string(), json!(s)))
+        .collect::<serde_json::Map<String, Value>>()
+        .into()
 }
 
 pub(crate) fn encode_page_token(token: &PageToken) -> String {
@@ -38,68 +286,101 @@ pub async fn history(account: Option<String>, args: &HistoryArgs) -> Envelope<Va
     };
 
     let max_changes_default = 100;
+    let requested = (!args.types.is_empty()).then(|| selected_types(&args.types));
 
     // Bootstrap: return current state and no changes.
     if args.since.is_none() && args.page.is_none() {
-        let state = match backend.email_state().await {
+        let types = requested.unwrap_or_else(|| vec![ChangeType::Email]);
+        let states = match current_states(&backend, &types).await {
             Ok(s) => s,
             Err(e) => return Envelope::err(command_name, account, e),
         };
 
-        return Envelope::ok(
-            command_name,
-            account,
+        let data = if is_email_only(&types) {
+            let state = &states[&ChangeType::Email];
             json!({
                 "sinceState": state,
                 "newState": state,
                 "hasMoreChanges": false,
                 "changes": {"created": [], "updated": [], "destroyed": []}
-            }),
-            Meta::default(),
-        );
+            })
+        } else {
+            let changes: serde_json::Map<String, Value> = types
+                .iter()
+                .map(|t| {
+                    (
+                        change_type_name(*t).to_string(),
+                        json!({"created": [], "updated": [], "destroyed": []}),
+                    )
+                })
+                .collect();
+            json!({
+                "types": types.iter().map(|t| change_type_name(*t)).collect::<Vec<_>>(),
+                "sinceStates": states_json(&states),
+                "newStates": states_json(&states),
+                "hasMoreChanges": false,
+                "changes": changes
+            })
+        };
+
+        return Envelope::ok(command_name, account, data, Meta::default());
     }
 
-    // Normal: Email/changes.
+    // Normal: <Type>/changes.
     //
     // Paging rule:
-    // - If --page is provided, the token is the source of truth for sinceState/maxChanges.
-    // - If the user explicitly also provides --since/--max, they MUST match the token.
-    let (since_state, used_max) = match &args.page {
+    // - If --page is provided, the token is the source of truth for the per-type states and
+    //   maxChanges.
+    // - If the user explicitly also provides --since/--max/--types, they MUST match the token.
+    let (states, used_max) = match &args.page {
         Some(token) => {
             let t = match decode_page_token(token) {
                 Ok(t) => t,
                 Err(e) => return Envelope::err(command_name, account, e),
             };
+            let states = match t.states() {
+                Ok(s) => s,
+                Err(e) => return Envelope::err(command_name, account, e),
+            };
 
-            if let Some(max) = args.max {
-                if max != t.max_changes {
-                    return Envelope::err(
-                        command_name,
-                        account,
-                        XinErrorOut::usage("page token does not match args".to_string()),
-                    );
-                }
+            let mismatch = args.max.is_some_and(|max| max != t.max_changes)
+                || requested
+                    .as_ref()
+                    .is_some_and(|types| !states.keys().eq(types.iter()))
+                || args.since.as_ref().is_some_and(|since| {
+                    states.len() != 1 || states.values().next() != Some(since)
+                });
+            if mismatch {
+                return Envelope::err(
+                    command_name,
+                    account,
+                    XinErrorOut::usage("page token does not match args".to_string()),
+                );
             }
 
-            if let Some(since) = &args.since {
-                if since != &t.since_state {
-                    return Envelope::err(
-                        command_name,
-                        account,
-                        XinErrorOut::usage("page token does not match args".to_string()),
-                    );
-                }
+            (states, t.max_changes)
+        }
+        None => {
+            let types = requested.unwrap_or_else(|| vec![ChangeType::Email]);
+            if types.len() > 1 {
+                return Envelope::err(
+                    command_name,
+                    account,
+                    XinErrorOut::usage(
+                        "--since tracks a single type; use --page to resume multiple --types"
+                            .to_string(),
+                    ),
+                );
             }
 
-            (t.since_state, t.max_changes)
+            (
+                BTreeMap::from([(types[0], args.since.clone().unwrap_or_default())]),
+                args.max.unwrap_or(max_changes_default),
+            )
         }
-        None => (
-            args.since.clone().unwrap_or_default(),
-            args.max.unwrap_or(max_changes_default),
-        ),
     };
 
-    if since_state.trim().is_empty() {
+    if states.values().any(|s| s.trim().is_empty()) {
         return Envelope::err(
             command_name,
             account,
@@ -107,58 +388,119 @@ pub async fn history(account: Option<String>, args: &HistoryArgs) -> Envelope<Va
         );
     }
 
-    let (mut resp, hydrated_created, hydrated_updated) = if args.hydrate {
-        match backend
-            .email_changes_hydrate(&since_state, Some(used_max))
-            .await
-        {
-            Ok((r, created, updated)) => (r, Some(created), Some(updated)),
-            Err(e) => return Envelope::err(command_name, account, e),
-        }
-    } else {
-        match backend.email_changes(&since_state, Some(used_max)).await {
-            Ok(r) => (r, None, None),
+    let mut results: BTreeMap<ChangeType, TypeChanges> = BTreeMap::new();
+    for (t, since_state) in &states {
+        match fetch_changes(&backend, *t, since_state, used_max, args.hydrate).await {
+            Ok(r) => {
+                results.insert(*t, r);
+            }
             Err(e) => return Envelope::err(command_name, account, e),
         }
-    };
-
-    let has_more = resp.has_more_changes();
-    let new_state = resp.take_new_state();
+    }
 
-    let created = resp.take_created();
-    let updated = resp.take_updated();
-    let destroyed = resp.take_destroyed();
+    let has_more = results.values().any(|r| r.has_more);
+    let new_states: BTreeMap<ChangeType, String> = results
+        .iter()
+        .map(|(t, r)| (*t, r.new_state.clone()))
+        .collect();
 
     let mut meta = Meta::default();
     if has_more {
         // JMAP /changes pagination: continue with the `newState` returned by the
-        // previous call.
-        meta.next_page = Some(encode_page_token(&PageToken {
-            since_state: new_state.clone(),
-            max_changes: used_max,
-        }));
-    }
-
-    let mut data = json!({
-        "sinceState": since_state,
-        "newState": new_state,
-        "hasMoreChanges": has_more,
-        "changes": {
-            "created": created,
-            "updated": updated,
-            "destroyed": destroyed
+        // previous call (per type).
+        meta.next_page = Some(encode_page_token(&PageToken::from_states(
+            &new_states,
+            used_max,
+        )));
+    }
+
+    let data = if is_email_only(&states.keys().copied().collect::<Vec<_>>()) {
+        let r = &results[&ChangeType::Email];
+        let mut data = json!({
+            "sinceState": r.since_state,
+            "newState": r.new_state,
+            "hasMoreChanges": r.has_more,
+            "changes": {
+                "created": r.created,
+                "updated": r.updated,
+                "destroyed": r.destroyed
+            }
+        });
+
+        if let Some((created_emails, updated_emails)) = &r.hydrated {
+            data.as_object_mut().expect("data object").insert(
+                "hydrated".to_string(),
+                json!({
+                    "created": schema::email_summary_items(created_emails),
+                    "updated": schema::email_summary_items(updated_emails)
+                }),
+            );
         }
-    });
+        data
+    } else {
+        let changes: serde_json::Map<String, Value> = results
+            .iter()
+            .map(|(t, r)| (change_type_name(*t).to_string(), r.to_json()))
+            .collect();
+        json!({
+            "types": states.keys().map(|t| change_type_name(*t)).collect::<Vec<_>>(),
+            "sinceStates": states_json(&states),
+            "newStates": states_json(&new_states),
+            "hasMoreChanges": has_more,
+            "changes": changes
+        })
+    };
 
-    if let (Some(created_emails), Some(updated_emails)) = (hydrated_created, hydrated_updated) {
-        data.as_object_mut().expect("data object").insert(
-            "hydrated".to_string(),
-            json!({
-                "created": schema::email_summary_items(&created_emails),
-                "updated": schema::email_summary_items(&updated_emails)
-            }),
+    Envelope::ok(command_name, account, data, meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_token_keeps_legacy_shape_for_email_only() {
+        let states = BTreeMap::from([(ChangeType::Email, "S1".to_string())]);
+        let t = PageToken::from_states(&states, 50);
+        assert_eq!(
+            serde_json::to_value(&t).unwrap(),
+            json!({"sinceState": "S1", "maxChanges": 50})
         );
+        assert_eq!(t.states().unwrap(), states);
+
+        // Tokens minted before --types existed still decode.
+        let legacy = encode_page_token(&t);
+        assert_eq!(decode_page_token(&legacy).unwrap(), t);
     }
 
-    Envelope::ok(command_name, account, data, meta)
+    #[test]
+    fn page_token_roundtrips_per_type_states() {
+        let states = BTreeMap::from([
+            (ChangeType::Email, "E1".to_string()),
+            (ChangeType::Mailbox, "M1".to_string()),
+            (ChangeType::Submission, "U1".to_string()),
+        ]);
+        let t = PageToken::from_states(&states, 100);
+        assert_eq!(t.since_state, "E1");
+        assert_eq!(
+            t.since_states.get("mailbox").map(String::as_str),
+            Some("M1")
+        );
+
+        let decoded = decode_page_token(&encode_page_token(&t)).unwrap();
+        assert_eq!(decoded.states().unwrap(), states);
+    }
+
+    #[test]
+    fn selected_types_defaults_to_email_and_dedupes() {
+        assert_eq!(selected_types(&[]), vec![ChangeType::Email]);
+        assert_eq!(
+            selected_types(&[
+                ChangeType::Submission,
+                ChangeType::Email,
+                ChangeType::Submission
+            ]),
+            vec![ChangeType::Email, ChangeType::Submission]
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use serde_json::{Value, json};
 
-use crate::cli::{InboxAction, InboxDoArgs, InboxNextArgs, SearchArgs, SortKey};
+use crate::cli::{InboxAction, InboxDoArgs, InboxNextArgs, SearchArgs};
 use crate::output::Envelope;
 
 use super::{organize, read};
@@ -13,8 +13,10 @@ pub async fn next(account: Option<String>, args: &InboxNextArgs) -> Envelope<Val
     if let Some(extra) = &args.query {
         let extra = extra.trim();
         if !extra.is_empty() {
-            q.push(' ');
+            // Group the extra query so a top-level OR cannot escape the inbox/unread terms.
+            q.push_str(" (");
             q.push_str(extra);
+            q.push(')');
         }
     }
 
@@ -25,7 +27,9 @@ pub async fn next(account: Option<String>, args: &InboxNextArgs) -> Envelope<Val
         oldest: args.oldest,
         filter_json: None,
         collapse_threads: Some(false),
-        sort: SortKey::ReceivedAt,
+        sort: Vec::new(),
+        fail_on_drift: false,
+        explain: args.explain,
     };
 
     let mut env = read::search("inbox.next", account, &search_args).await;
@@ -37,13 +41,15 @@ pub async fn next(account: Option<String>, args: &InboxNextArgs) -> Envelope<Val
     // Add a convenience `item` field, keeping `items` intact.
     if let Some(data) = env.data.as_mut() {
         if let Some(obj) = data.as_object_mut() {
-            let first = obj
-                .get("items")
-                .and_then(|v| v.as_array())
-                .and_then(|a| a.first())
-                .cloned()
-                .unwrap_or(Value::Null);
-            obj.insert("item".to_string(), first);
+            if !args.explain {
+                let first = obj
+                    .get("items")
+                    .and_then(|v| v.as_array())
+                    .and_then(|a| a.first())
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                obj.insert("item".to_string(), first);
+            }
             obj.insert(
                 "query".to_string(),
                 json!({
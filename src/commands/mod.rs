@@ -1,14 +1,18 @@
 use crate::cli::*;
 use crate::output::Envelope;
 
+mod attachments;
 mod auth_cmd;
 mod config_cmd;
+mod export;
 mod history;
 mod inbox;
 mod labels;
 mod organize;
+mod queries;
 mod read;
 mod send;
+mod submissions;
 mod url;
 mod watch;
 
@@ -48,6 +52,10 @@ pub async fn dispatch(cli: &Cli) -> Envelope<serde_json::Value> {
             command: ThreadCommand::Delete(args),
         } => organize::thread_delete(account.clone(), args, cli.dry_run, cli.force).await,
         Command::Attachment(args) => read::attachment_download(account.clone(), args).await,
+        Command::Attachments {
+            command: AttachmentsCommand::Export(args),
+        } => attachments::export(account.clone(), args).await,
+        Command::Export(args) => export::export(account.clone(), args).await,
         Command::Url(args) => url::url(account.clone(), args).await,
         Command::Archive(args) => organize::archive(account.clone(), args, cli.dry_run).await,
         Command::Read(args) => organize::read(account.clone(), args, cli.dry_run).await,
@@ -106,9 +114,19 @@ pub async fn dispatch(cli: &Cli) -> Envelope<serde_json::Value> {
         Command::Identities {
             command: IdentitiesCommand::Get(args),
         } => send::identities_get(account.clone(), args).await,
+        Command::Identities {
+            command: IdentitiesCommand::Create(args),
+        } => send::identities_create(account.clone(), args).await,
+        Command::Identities {
+            command: IdentitiesCommand::Update(args),
+        } => send::identities_update(account.clone(), args).await,
+        Command::Identities {
+            command: IdentitiesCommand::Delete(args),
+        } => send::identities_delete(account.clone(), args, cli.force).await,
 
-        Command::Send(args) => send::send(account.clone(), args).await,
+        Command::Send(args) => send::send(account.clone(), args, cli.dry_run).await,
         Command::Reply(args) => send::reply(account.clone(), args).await,
+        Command::Forward(args) => send::forward(account.clone(), args).await,
 
         Command::Drafts { command: sub } => match sub {
             DraftsCommand::List(args) => send::drafts_list(account.clone(), args).await,
@@ -125,6 +143,13 @@ pub async fn dispatch(cli: &Cli) -> Envelope<serde_json::Value> {
             DraftsCommand::Send(args) => send::drafts_send(account.clone(), args).await,
         },
 
+        Command::Submissions { command: sub } => match sub {
+            SubmissionsCommand::List(args) => submissions::list(account.clone(), args).await,
+            SubmissionsCommand::Get(args) => submissions::get(account.clone(), args).await,
+            SubmissionsCommand::Cancel(args) => submissions::cancel(account.clone(), args).await,
+            SubmissionsCommand::Changes(args) => submissions::changes(account.clone(), args).await,
+        },
+
         Command::History(args) => history::history(account.clone(), args).await,
         Command::Watch(args) => watch::watch(account.clone(), args, cli.plain).await,
 
@@ -135,6 +160,12 @@ pub async fn dispatch(cli: &Cli) -> Envelope<serde_json::Value> {
             ConfigCommand::Show(args) => config_cmd::show(account.as_deref(), args).await,
         },
 
+        Command::Queries { command: sub } => match sub {
+            QueriesCommand::List => queries::list(account.as_deref()).await,
+            QueriesCommand::Set(args) => queries::set(account.as_deref(), args).await,
+            QueriesCommand::Delete(args) => queries::delete(account.as_deref(), args).await,
+        },
+
         Command::Auth { command: sub } => match sub {
             AuthCommand::SetToken(args) => auth_cmd::set_token(account.as_deref(), args).await,
         },
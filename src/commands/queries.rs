@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+
+use crate::app_config;
+use crate::cli::{QueriesDeleteArgs, QueriesSetArgs};
+use crate::error::XinErrorOut;
+use crate::output::{Envelope, Meta};
+use crate::sugar;
+
+/// Saved query names: what `@name` in query sugar can refer to.
+fn validate_name(name: &str) -> Result<(), XinErrorOut> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(XinErrorOut::usage(format!(
+            "invalid saved query name '{name}' (use letters, digits, `_` and `-`)"
+        )))
+    }
+}
+
+/// The map `set`/`delete` edit: the account's when `--account` is given, otherwise global.
+fn target_queries<'a>(
+    cfg: &'a mut app_config::AppConfig,
+    account: Option<&str>,
+) -> Result<&'a mut BTreeMap<String, String>, XinErrorOut> {
+    match account {
+        Some(a) => cfg
+            .accounts
+            .get_mut(a)
+            .map(|acct| &mut acct.queries)
+            .ok_or_else(|| XinErrorOut::usage(format!("unknown account '{a}'"))),
+        None => Ok(&mut cfg.queries),
+    }
+}
+
+fn query_item(name: &str, query: &str, account: Option<&str>) -> Value {
+    json!({
+        "name": name,
+        "query": query,
+        "scope": if account.is_some() { "account" } else { "global" },
+        "account": account,
+    })
+}
+
+pub async fn list(cli_account: Option<&str>) -> Envelope<Value> {
+    let command_name = "queries.list";
+
+    let (cfg, path) = match app_config::read_config() {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+
+    if let Some(a) = cli_account.filter(|a| !cfg.accounts.contains_key(*a)) {
+        return Envelope::err(
+            command_name,
+            None,
+            XinErrorOut::usage(format!("unknown account '{a}'")),
+        );
+    }
+
+    let mut items: Vec<Value> = cfg
+        .queries
+        .iter()
+        .map(|(name, q)| query_item(name, q, None))
+        .collect();
+    for (acct_name, acct) in &cfg.accounts {
+        if cli_account.is_some_and(|a| a != acct_name.as_str()) {
+            continue;
+        }
+        items.extend(
+            acct.queries
+                .iter()
+                .map(|(name, q)| query_item(name, q, Some(acct_name.as_str()))),
+        );
+    }
+
+    Envelope::ok(
+        command_name,
+        None,
+        json!({
+            "path": path.to_string_lossy(),
+            "queries": items
+        }),
+        Meta::default(),
+    )
+}
+
+pub async fn set(cli_account: Option<&str>, args: &QueriesSetArgs) -> Envelope<Value> {
+    let command_name = "queries.set";
+
+    if let Err(e) = validate_name(&args.name) {
+        return Envelope::err(command_name, None, e);
+    }
+
+    let (mut cfg, _path) = match app_config::read_config() {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+
+    // Validate against what `@name` will see: global queries, the account's, then this one.
+    let mut visible = cfg.queries.clone();
+    if let Some(acct) = cli_account.and_then(|a| cfg.accounts.get(a)) {
+        visible.extend(acct.queries.clone());
+    }
+    visible.insert(args.name.clone(), args.query.clone());
+    if let Err(e) = sugar::validate_query(&args.query, &visible) {
+        return Envelope::err(command_name, None, e);
+    }
+
+    let previous = match target_queries(&mut cfg, cli_account) {
+        Ok(q) => q.insert(args.name.clone(), args.query.clone()),
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+
+    let path = match app_config::write_config(&cfg) {
+        Ok(p) => p,
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+
+    let mut data = query_item(&args.name, &args.query, cli_account);
+    let obj = data.as_object_mut().expect("query item");
+    obj.insert("path".to_string(), json!(path.to_string_lossy()));
+    obj.insert("replaced".to_string(), json!(previous.is_some()));
+
+    Envelope::ok(command_name, None, data, Meta::default())
+}
+
+pub async fn delete(cli_account: Option<&str>, args: &QueriesDeleteArgs) -> Envelope<Value> {
+    let command_name = "queries.delete";
+
+    let (mut cfg, _path) = match app_config::read_config() {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+
+    let removed = match target_queries(&mut cfg, cli_account) {
+        Ok(q) => q.remove(&args.name),
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+    let Some(query) = removed else {
+        let scope = cli_account.map_or("global".to_string(), |a| format!("account '{a}'"));
+        return Envelope::err(
+            command_name,
+            None,
+            XinErrorOut::usage(format!("no saved query '{}' ({scope})", args.name)),
+        );
+    };
+
+    let path = match app_config::write_config(&cfg) {
+        Ok(p) => p,
+        Err(e) => return Envelope::err(command_name, None, e),
+    };
+
+    let mut data = query_item(&args.name, &query, cli_account);
+    data.as_object_mut()
+        .expect("query item")
+        .insert("path".to_string(), json!(path.to_string_lossy()));
+
+    Envelope::ok(command_name, None, data, Meta::default())
+}
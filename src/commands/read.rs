@@ -3,17 +3,20 @@ use serde_json::{Value, json};
 
 use crate::backend::Backend;
 use crate::cli::{
-    AttachmentArgs, GetArgs, GetFormat, MessagesSearchArgs, SearchArgs, ThreadAttachmentsArgs,
-    ThreadGetArgs,
+    AttachmentArgs, GetArgs, GetFormat, MessagesSearchArgs, SearchArgs, SortSpec,
+    ThreadAttachmentsArgs, ThreadGetArgs,
 };
 use crate::config::read_json_arg;
 use crate::error::XinErrorOut;
 use crate::output::{Envelope, Meta};
 use crate::schema;
 use crate::sugar;
+use crate::transfer::Progress;
 use std::fs;
 use std::path::PathBuf;
 
+use super::attachments::AttachmentExport;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
 struct PageToken {
     position: i32,
@@ -23,6 +26,51 @@ struct PageToken {
     #[serde(rename = "isAscending")]
     is_ascending: bool,
     filter: Value,
+    /// `Email/query` comparators. Absent in older tokens, which always sorted by receivedAt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sort: Option<Vec<Value>>,
+    /// Last email id of the previous page; the next page starts right after it.
+    /// `position` stays as the fallback for older tokens and `anchorNotFound`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anchor: Option<String>,
+    /// `queryState` of the previous page, to detect result-set drift.
+    #[serde(
+        rename = "queryState",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    query_state: Option<String>,
+}
+
+impl PageToken {
+    fn sort(&self) -> Vec<Value> {
+        self.sort.clone().unwrap_or_else(|| {
+            vec![json!({"property": "receivedAt", "isAscending": self.is_ascending})]
+        })
+    }
+}
+
+/// `--sort` as JMAP comparators (default: receivedAt, newest first unless `--oldest`).
+fn sort_comparators(specs: &[SortSpec], oldest: bool) -> Vec<Value> {
+    if specs.is_empty() {
+        return vec![json!({"property": "receivedAt", "isAscending": oldest})];
+    }
+
+    specs
+        .iter()
+        .map(|s| {
+            let mut c = json!({
+                "property": s.key.property(),
+                "isAscending": s.is_ascending(oldest)
+            });
+            if let Some(kw) = &s.keyword {
+                c.as_object_mut()
+                    .expect("comparator")
+                    .insert("keyword".to_string(), json!(kw));
+            }
+            c
+        })
+        .collect()
 }
 
 fn encode_page_token(token: &PageToken) -> String {
@@ -51,16 +99,30 @@ pub async fn search(
     let limit = args.max.unwrap_or(20);
     let collapse_threads = args.collapse_threads.unwrap_or(true);
     let is_ascending = args.oldest;
+    let sort = sort_comparators(&args.sort, args.oldest);
+
+    // `--explain` details: where the filter came from and how `in:` terms resolved.
+    let mut filter_source = "none";
+    let mut resolved_mailboxes: Vec<Value> = Vec::new();
+    let mut saved_queries: Vec<Value> = Vec::new();
 
     let filter_json = match &args.filter_json {
         Some(s) => match read_json_arg(s) {
-            Ok(v) => v,
+            Ok(v) => {
+                filter_source = "filterJson";
+                v
+            }
             Err(e) => return Envelope::err(command_name, account, e),
         },
         None => match &args.query {
             Some(q) if !q.trim().is_empty() => {
-                match sugar::compile_search_filter(q, &backend).await {
-                    Ok(v) => v,
+                match sugar::explain_search_filter(q, &backend).await {
+                    Ok(c) => {
+                        filter_source = "sugar";
+                        resolved_mailboxes = c.mailboxes;
+                        saved_queries = c.saved_queries;
+                        c.filter
+                    }
                     Err(e) => return Envelope::err(command_name, account, e),
                 }
             }
@@ -68,12 +130,13 @@ pub async fn search(
         },
     };
 
-    let (position, stable_filter_json) = match &args.page {
+    let (position, anchor, prev_query_state, stable_filter_json) = match &args.page {
         Some(token) => match decode_page_token(token) {
             Ok(t) => {
                 if t.limit != limit
                     || t.collapse_threads != collapse_threads
                     || t.is_ascending != is_ascending
+                    || t.sort() != sort
                     || t.filter != filter_json
                 {
                     return Envelope::err(
@@ -82,31 +145,116 @@ pub async fn search(
                         XinErrorOut::usage("page token does not match args".to_string()),
                     );
                 }
-                (t.position, t.filter)
+                (t.position, t.anchor, t.query_state, t.filter)
             }
             Err(e) => return Envelope::err(command_name, account, e),
         },
-        None => (0, filter_json.clone()),
+        None => (0, None, None, filter_json.clone()),
     };
 
-    let result = match backend
+    if args.explain {
+        // Dry mode: report what would be sent, without calling Email/query.
+        let email_query = backend.email_query_args(
+            stable_filter_json.clone(),
+            position,
+            anchor.as_deref(),
+            limit,
+            collapse_threads,
+            &sort,
+        );
+        return Envelope::ok(
+            command_name,
+            account,
+            json!({
+                "explain": {
+                    "query": args.query,
+                    "filterSource": filter_source,
+                    "filter": stable_filter_json,
+                    "mailboxes": resolved_mailboxes,
+                    "savedQueries": saved_queries,
+                    "timezone": backend.timezone().ok().map(|tz| tz.name()),
+                    "emailQuery": email_query
+                }
+            }),
+            Meta {
+                warnings: backend.timezone().err().map(|e| vec![e.message]),
+                ..Meta::default()
+            },
+        );
+    }
+
+    let mut warnings: Vec<String> = Vec::new();
+
+    let mut result = backend
         .search_raw_filter_json(
             stable_filter_json.clone(),
             position,
+            anchor.as_deref(),
             limit,
             collapse_threads,
-            is_ascending,
+            &sort,
         )
-        .await
-    {
+        .await;
+
+    // The anchor email is gone from the results: resume by position instead.
+    let anchor_not_found = result.as_ref().err().is_some_and(|e| {
+        e.jmap
+            .as_ref()
+            .and_then(|j| j.get("type"))
+            .and_then(|t| t.as_str())
+            == Some("anchorNotFound")
+    });
+    if anchor.is_some() && anchor_not_found {
+        warnings.push(format!(
+            "the last email of the previous page is no longer in the results; resumed at position {position}, so items may be skipped or repeated"
+        ));
+        result = backend
+            .search_raw_filter_json(
+                stable_filter_json.clone(),
+                position,
+                None,
+                limit,
+                collapse_threads,
+                &sort,
+            )
+            .await;
+    }
+
+    let result = match result {
         Ok(r) => r,
         Err(e) => return Envelope::err(command_name, account, e),
     };
 
+    let query_state = result.query.query_state().to_string();
+    if let Some(prev) = prev_query_state.filter(|prev| *prev != query_state) {
+        if args.fail_on_drift {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut {
+                    kind: "xinQueryChanged".to_string(),
+                    message: format!(
+                        "search results changed since the previous page (queryState {prev} -> {query_state}); restart without --page"
+                    ),
+                    http: None,
+                    jmap: None,
+                },
+            );
+        }
+        warnings.push(format!(
+            "search results changed since the previous page (queryState {prev} -> {query_state})"
+        ));
+    }
+
     let items = schema::email_summary_items(&result.emails);
 
     let mut meta = Meta::default();
-    let next_position = position + items.len() as i32;
+    if !warnings.is_empty() {
+        meta.warnings = Some(warnings);
+    }
+
+    // With an anchor the server reports where the window actually started.
+    let next_position = result.query.position() + result.query.ids().len() as i32;
 
     // Prefer `total` when available, but don't require it.
     // Some servers omit `total` even though they support paging via position/limit.
@@ -122,6 +270,9 @@ pub async fn search(
             collapse_threads,
             is_ascending,
             filter: stable_filter_json,
+            sort: Some(sort),
+            anchor: result.query.ids().last().cloned(),
+            query_state: Some(query_state),
         }));
     }
 
@@ -139,7 +290,9 @@ pub async fn messages_search(
         oldest: false,
         filter_json: args.filter_json.clone(),
         collapse_threads: Some(false),
-        sort: crate::cli::SortKey::ReceivedAt,
+        sort: args.sort.clone(),
+        fail_on_drift: args.fail_on_drift,
+        explain: args.explain,
     };
 
     search("messages.search", account, &search_args).await
@@ -376,11 +529,36 @@ pub async fn thread_attachments(
         Err(e) => return Envelope::err(command_name, account, e),
     };
 
+    let mut data = schema::thread_attachments_data(&result.thread_id, &result.emails);
+    if !args.download {
+        return Envelope::ok(command_name, account, data, Meta::default());
+    }
+
+    // `--download`: same export as `xin attachments export`.
+    let mut export = match AttachmentExport::open(&args.dir, !args.no_progress) {
+        Ok(x) => x,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    for email in &result.emails {
+        let source = schema::email_attachment_source(email);
+        if let Err(e) = export.add_email(&backend, &source).await {
+            return Envelope::err(command_name, account, e);
+        }
+    }
+    if let Err(e) = export.write_manifest() {
+        return Envelope::err(command_name, account, e);
+    }
+
+    let warnings = export.warnings();
+    data["export"] = export.summary();
     Envelope::ok(
         command_name,
         account,
-        schema::thread_attachments_data(&result.thread_id, &result.emails),
-        Meta::default(),
+        data,
+        Meta {
+            warnings: (!warnings.is_empty()).then_some(warnings),
+            ..Meta::default()
+        },
     )
 }
 
@@ -395,11 +573,6 @@ pub async fn attachment_download(
         Err(e) => return Envelope::err(command_name, account, e),
     };
 
-    let bytes = match backend.download_blob(&args.blob_id).await {
-        Ok(b) => b,
-        Err(e) => return Envelope::err(command_name, account, e),
-    };
-
     let out_path: PathBuf = if let Some(out) = &args.out {
         out.into()
     } else if let Some(name) = &args.name {
@@ -420,13 +593,20 @@ pub async fn attachment_download(
         }
     }
 
-    if let Err(e) = fs::write(&out_path, &bytes) {
-        return Envelope::err(
-            command_name,
-            account,
-            XinErrorOut::usage(format!("failed to write output file: {e}")),
-        );
-    }
+    // Streamed to `<out>.<blobId>.part` and renamed when complete; a leftover one is resumed.
+    let mut progress = Progress::new("download", &args.blob_id, !args.no_progress);
+    let download = match backend
+        .download_blob_to(
+            &args.blob_id,
+            args.name.as_deref().unwrap_or("none"),
+            &out_path,
+            &mut progress,
+        )
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
 
     Envelope::ok(
         command_name,
@@ -435,7 +615,8 @@ pub async fn attachment_download(
             "emailId": args.email_id,
             "blobId": args.blob_id,
             "out": out_path.to_string_lossy(),
-            "bytes": bytes.len(),
+            "bytes": download.bytes,
+            "resumedFrom": download.resumed_from,
         }),
         Meta::default(),
     )
@@ -1,15 +1,29 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::backend::{Backend, ModifyPlan, UploadedBlob};
+use crate::app_config::AfterSend;
+use crate::backend::{
+    Backend, ModifyPlan, OnSuccess, Submission, UploadFile, UploadLimits, UploadedBlob,
+};
 use crate::cli::{
     DraftsCreateArgs, DraftsDeleteArgs, DraftsDestroyArgs, DraftsGetArgs, DraftsListArgs,
-    DraftsRewriteArgs, DraftsSendArgs, DraftsUpdateArgs, IdentitiesGetArgs, ReplyArgs, SendArgs,
+    DraftsRewriteArgs, DraftsSendArgs, DraftsUpdateArgs, ForwardArgs, IdentitiesCreateArgs,
+    IdentitiesDeleteArgs, IdentitiesGetArgs, IdentitiesUpdateArgs, ReplyArgs, SendArgs,
 };
+use crate::eml;
 use crate::error::XinErrorOut;
+use crate::inline_images;
+use crate::markdown;
+use crate::merge;
+use crate::mime;
 use crate::output::{Envelope, Meta};
+use crate::quote;
+use crate::schema;
+
+use super::submissions::wait_for_delivery;
 
 fn read_text_arg(value: &str) -> Result<String, XinErrorOut> {
     if let Some(path) = value.strip_prefix('@') {
@@ -20,11 +34,6 @@ fn read_text_arg(value: &str) -> Result<String, XinErrorOut> {
     }
 }
 
-fn to_rfc3339(ts: Option<i64>) -> Option<String> {
-    ts.and_then(|v| DateTime::<Utc>::from_timestamp(v, 0))
-        .map(|dt| dt.to_rfc3339())
-}
-
 fn resolve_mailbox_id(s: &str, mailboxes: &[jmap_client::mailbox::Mailbox]) -> Option<String> {
     let needle = s.trim();
     if needle.is_empty() {
@@ -369,7 +378,7 @@ fn infer_reply_recipients(
     (to, cc)
 }
 
-fn find_drafts_mailbox_id(
+pub(crate) fn find_drafts_mailbox_id(
     mailboxes: &[jmap_client::mailbox::Mailbox],
 ) -> Result<String, XinErrorOut> {
     // Prefer RFC role-based resolution, but some servers may omit roles.
@@ -411,6 +420,411 @@ fn find_drafts_mailbox_id(
     Err(XinErrorOut::config("drafts mailbox not found".to_string()))
 }
 
+pub(crate) fn find_sent_mailbox_id(mailboxes: &[jmap_client::mailbox::Mailbox]) -> Option<String> {
+    mailboxes
+        .iter()
+        .find(|m| m.role() == jmap_client::mailbox::Role::Sent)
+        .or_else(|| {
+            mailboxes.iter().find(|m| {
+                m.name()
+                    .is_some_and(|n| matches!(n.to_lowercase().as_str(), "sent" | "sent items"))
+            })
+        })
+        .and_then(|m| m.id())
+        .map(|id| id.to_string())
+}
+
+/// What the account's `afterSend` policy asks the server to do once the submission succeeds,
+/// and how that is reported as `data.afterSend`.
+fn after_send_plan(
+    policy: AfterSend,
+    mailboxes: &[jmap_client::mailbox::Mailbox],
+    drafts_id: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> (OnSuccess, Value) {
+    let sent_id = match policy {
+        AfterSend::Move | AfterSend::Copy => {
+            let id = find_sent_mailbox_id(mailboxes);
+            if id.is_none() {
+                warnings.push(
+                    "no Sent mailbox found; the sent email keeps its mailboxes (afterSend only updates keywords)"
+                        .to_string(),
+                );
+            }
+            id
+        }
+        _ => None,
+    };
+
+    let on_success = match policy {
+        AfterSend::None => OnSuccess::Nothing,
+        AfterSend::Destroy => OnSuccess::Destroy,
+        AfterSend::Move | AfterSend::Copy | AfterSend::Keep => {
+            let mut patch = serde_json::Map::new();
+            if let Some(sent) = &sent_id {
+                if let Some(drafts) = drafts_id.filter(|d| policy == AfterSend::Move && *d != sent)
+                {
+                    patch.insert(format!("mailboxIds/{drafts}"), Value::Null);
+                }
+                patch.insert(format!("mailboxIds/{sent}"), json!(true));
+            }
+            patch.insert("keywords/$draft".to_string(), Value::Null);
+            patch.insert("keywords/$seen".to_string(), json!(true));
+            OnSuccess::Update(Value::Object(patch))
+        }
+    };
+
+    (
+        on_success,
+        json!({"action": policy.as_str(), "sentMailboxId": sent_id}),
+    )
+}
+
+/// `data.submission` and `data.afterSend` for a created submission.
+fn submission_out(
+    submission: &Submission,
+    mut after_send: Value,
+    warnings: &mut Vec<String>,
+) -> (Value, Value) {
+    let obj = after_send.as_object_mut().expect("afterSend object");
+    let applied = obj.get("action").and_then(|a| a.as_str()) != Some("none")
+        && submission.email_error.is_none();
+    obj.insert("applied".to_string(), json!(applied));
+    if let Some(err) = &submission.email_error {
+        warnings.push(format!(
+            "sent, but the afterSend update of the email failed: {}",
+            err.get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or("unknown")
+        ));
+        obj.insert("error".to_string(), err.clone());
+    }
+
+    (
+        json!({
+            "id": submission.id,
+            "sendAt": submission.send_at,
+            "undoStatus": submission.undo_status
+        }),
+        after_send,
+    )
+}
+
+/// `--send-at`: a delay (`+10m`) or an absolute RFC3339 time.
+#[derive(Debug, Clone, PartialEq)]
+enum SendAt {
+    /// FUTURERELEASE `HOLDFOR`, in seconds.
+    For(i64),
+    /// FUTURERELEASE `HOLDUNTIL`.
+    Until(DateTime<Utc>),
+}
+
+fn parse_send_at(value: &str, now: DateTime<Utc>) -> Result<SendAt, XinErrorOut> {
+    let v = value.trim();
+    let invalid = || {
+        XinErrorOut::usage(format!(
+            "invalid --send-at '{v}': use an RFC3339 time (2026-03-01T09:00:00Z) or a delay like +10m (s/m/h/d)"
+        ))
+    };
+
+    if let Some(rest) = v.strip_prefix('+') {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (num, unit) = rest.split_at(split);
+        let n: i64 = num.parse().map_err(|_| invalid())?;
+        let secs = match unit {
+            "s" => Some(n),
+            "m" => n.checked_mul(60),
+            "h" => n.checked_mul(3600),
+            "d" => n.checked_mul(86400),
+            _ => None,
+        }
+        .filter(|s| *s > 0)
+        .ok_or_else(invalid)?;
+        return Ok(SendAt::For(secs));
+    }
+
+    let t = DateTime::parse_from_rfc3339(v)
+        .map_err(|_| invalid())?
+        .with_timezone(&Utc);
+    if t <= now {
+        return Err(XinErrorOut::usage(format!("--send-at {v} is in the past")));
+    }
+    Ok(SendAt::Until(t))
+}
+
+/// FUTURERELEASE `MAIL FROM` parameters for `send_at`, checked against the submission
+/// capability (`submissionExtensions`, `maxDelayedSend`).
+fn hold_parameters(
+    send_at: &SendAt,
+    capability: Option<&Value>,
+    now: DateTime<Utc>,
+) -> Result<Value, XinErrorOut> {
+    let advertised = capability
+        .and_then(|c| c.get("submissionExtensions"))
+        .and_then(|e| e.as_object())
+        .is_some_and(|e| e.keys().any(|k| k.eq_ignore_ascii_case("FUTURERELEASE")));
+    let max_delay = capability
+        .and_then(|c| c.get("maxDelayedSend"))
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+
+    if !advertised || max_delay <= 0 {
+        return Err(XinErrorOut::not_supported(
+            "scheduled send is not supported by this server (no FUTURERELEASE in submissionExtensions)"
+                .to_string(),
+        ));
+    }
+
+    let delay = match send_at {
+        SendAt::For(secs) => *secs,
+        SendAt::Until(t) => (*t - now).num_seconds(),
+    };
+    if delay > max_delay {
+        return Err(XinErrorOut::usage(format!(
+            "--send-at is {delay}s away; the server allows at most {max_delay}s (maxDelayedSend)"
+        )));
+    }
+
+    Ok(match send_at {
+        SendAt::For(secs) => json!({"HOLDFOR": secs.to_string()}),
+        SendAt::Until(t) => json!({"HOLDUNTIL": t.to_rfc3339_opts(SecondsFormat::Secs, true)}),
+    })
+}
+
+/// `--send-at` as envelope `MAIL FROM` parameters (`None` when sending now).
+fn send_at_parameters(
+    backend: &Backend,
+    value: Option<&str>,
+) -> Result<Option<Value>, XinErrorOut> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let now = Utc::now();
+    let send_at = parse_send_at(value, now)?;
+    hold_parameters(&send_at, backend.submission_capability().as_ref(), now).map(Some)
+}
+
+/// An explicit submission envelope, needed to carry `MAIL FROM` parameters.
+fn hold_envelope<'a>(
+    from_email: &str,
+    recipients: impl IntoIterator<Item = &'a String>,
+    parameters: Value,
+) -> Value {
+    let mut seen = std::collections::HashSet::new();
+    let rcpt_to: Vec<Value> = recipients
+        .into_iter()
+        .map(|r| address_email(r))
+        .filter(|r| !r.is_empty() && seen.insert(r.to_lowercase()))
+        .map(|r| json!({"email": r, "parameters": null}))
+        .collect();
+
+    json!({
+        "mailFrom": {"email": from_email, "parameters": parameters},
+        "rcptTo": rcpt_to
+    })
+}
+
+/// `a@b` from `a@b` or `Name <a@b>`.
+fn address_email(s: &str) -> String {
+    match (s.rfind('<'), s.rfind('>')) {
+        (Some(l), Some(r)) if l < r => s[l + 1..r].trim().to_string(),
+        _ => s.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod send_at_tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn send_at_accepts_delays_and_future_times() {
+        let now = at("2026-03-01T00:00:00Z");
+        assert_eq!(parse_send_at("+10m", now).unwrap(), SendAt::For(600));
+        assert_eq!(parse_send_at("+2h", now).unwrap(), SendAt::For(7200));
+        assert_eq!(
+            parse_send_at("2026-03-01T18:00:00+09:00", now).unwrap(),
+            SendAt::Until(at("2026-03-01T09:00:00Z"))
+        );
+
+        for bad in [
+            "+10",
+            "+m",
+            "+0s",
+            "10m",
+            "tomorrow",
+            "2026-02-28T23:00:00Z",
+        ] {
+            let e = parse_send_at(bad, now).unwrap_err();
+            assert_eq!(e.kind, "xinUsageError", "{bad}");
+        }
+    }
+
+    #[test]
+    fn hold_parameters_need_futurerelease_within_max_delay() {
+        let now = at("2026-03-01T00:00:00Z");
+        let cap = json!({"maxDelayedSend": 3600, "submissionExtensions": {"FUTURERELEASE": []}});
+
+        assert_eq!(
+            hold_parameters(&SendAt::For(600), Some(&cap), now).unwrap(),
+            json!({"HOLDFOR": "600"})
+        );
+        assert_eq!(
+            hold_parameters(&SendAt::Until(at("2026-03-01T00:30:00Z")), Some(&cap), now).unwrap(),
+            json!({"HOLDUNTIL": "2026-03-01T00:30:00Z"})
+        );
+
+        let too_far = hold_parameters(&SendAt::For(7200), Some(&cap), now).unwrap_err();
+        assert!(too_far.message.contains("maxDelayedSend"));
+
+        let none = json!({"maxDelayedSend": 0, "submissionExtensions": {}});
+        for cap in [Some(&none), None] {
+            let e = hold_parameters(&SendAt::For(600), cap, now).unwrap_err();
+            assert_eq!(e.kind, "xinNotSupported");
+        }
+    }
+}
+
+/// Body bytes fetched from the original when quoting or forwarding it.
+const QUOTE_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// The original's `text/plain` and `text/html` bodies (JMAP falls back to the other type in
+/// `textBody`/`htmlBody`, so the part type is checked).
+fn original_bodies(
+    email: &jmap_client::email::Email,
+    warnings: &mut Vec<String>,
+) -> (Option<String>, Option<String>) {
+    let mut body = |parts: Option<&[jmap_client::email::EmailBodyPart]>, ty: &str| {
+        let part_id = parts?
+            .iter()
+            .find(|p| p.content_type().is_some_and(|t| t.eq_ignore_ascii_case(ty)))?
+            .part_id()?;
+        let value = email.body_value(part_id)?;
+        if value.is_truncated() {
+            warnings.push(format!(
+                "original {ty} body truncated at {QUOTE_MAX_BODY_BYTES} bytes"
+            ));
+        }
+        Some(value.value().to_string())
+    };
+    let text = body(email.text_body(), "text/plain");
+    let html = body(email.html_body(), "text/html");
+    (text, html)
+}
+
+fn format_addresses(addrs: Option<&[jmap_client::email::EmailAddress]>) -> Vec<String> {
+    addrs
+        .unwrap_or_default()
+        .iter()
+        .map(|a| quote::format_address(a.name(), a.email()))
+        .collect()
+}
+
+/// `reply --quote`: the reply followed by the attribution line and the quoted original.
+///
+/// A text body is always produced; an HTML body (blockquote) when the reply or the original
+/// has one. The attribution date is shown in `tz`.
+fn quoted_reply_bodies(
+    original: &jmap_client::email::Email,
+    text: Option<String>,
+    html: Option<String>,
+    tz: chrono_tz::Tz,
+    warnings: &mut Vec<String>,
+) -> (Option<String>, Option<String>) {
+    let from = format_addresses(original.from()).into_iter().next();
+    let attribution = quote::attribution(
+        original.sent_at().or(original.received_at()),
+        from.as_deref(),
+        tz,
+    );
+    let (orig_text, orig_html) = original_bodies(original, warnings);
+
+    let reply_text = text.or_else(|| html.as_deref().map(quote::html_to_text));
+    let quoted_text = orig_text
+        .clone()
+        .or_else(|| orig_html.as_deref().map(quote::html_to_text))
+        .unwrap_or_default();
+    let text_out = quote::quoted_reply_text(reply_text.as_deref(), &attribution, &quoted_text);
+
+    let html_out = (html.is_some() || orig_html.is_some()).then(|| {
+        let reply_html = html.or_else(|| reply_text.as_deref().map(quote::text_to_html));
+        let quoted_html = orig_html.unwrap_or_else(|| quote::text_to_html(&quoted_text));
+        quote::quoted_reply_html(reply_html.as_deref(), &attribution, &quoted_html)
+    });
+
+    (Some(text_out), html_out)
+}
+
+/// Inline forward: the note, a `Forwarded message` header block and the original bodies
+/// (same text/HTML rule as `quoted_reply_bodies`).
+fn forward_inline_bodies(
+    original: &jmap_client::email::Email,
+    note_text: Option<String>,
+    note_html: Option<String>,
+    tz: chrono_tz::Tz,
+    warnings: &mut Vec<String>,
+) -> (Option<String>, Option<String>) {
+    let from = format_addresses(original.from());
+    let headers = quote::forward_header_lines(
+        (!from.is_empty()).then(|| from.join(", ")).as_deref(),
+        original.sent_at().or(original.received_at()),
+        original.subject(),
+        &format_addresses(original.to()),
+        &format_addresses(original.cc()),
+        tz,
+    );
+    let (orig_text, orig_html) = original_bodies(original, warnings);
+
+    let note = note_text.or_else(|| note_html.as_deref().map(quote::html_to_text));
+    let forwarded_text = orig_text
+        .clone()
+        .or_else(|| orig_html.as_deref().map(quote::html_to_text))
+        .unwrap_or_default();
+    let text_out = quote::forward_text(note.as_deref(), &headers, &forwarded_text);
+
+    let html_out = (note_html.is_some() || orig_html.is_some()).then(|| {
+        let note_html = note_html.or_else(|| note.as_deref().map(quote::text_to_html));
+        let forwarded_html = orig_html.unwrap_or_else(|| quote::text_to_html(&forwarded_text));
+        quote::forward_html(note_html.as_deref(), &headers, &forwarded_html)
+    });
+
+    (Some(text_out), html_out)
+}
+
+/// The original's attachments as blobs for the new draft (reused by blobId, not re-uploaded).
+/// Parts that `html` references as `cid:` keep their Content-ID, so they stay inline images
+/// of the quoted HTML (multipart/related) instead of becoming plain attachments.
+fn original_attachment_blobs(
+    original: &jmap_client::email::Email,
+    html: Option<&str>,
+) -> Vec<UploadedBlob> {
+    original
+        .attachments()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|p| {
+            let cid = p
+                .cid()
+                .filter(|cid| html.is_some_and(|h| h.contains(&format!("cid:{cid}"))));
+            Some(UploadedBlob {
+                blob_id: p.blob_id()?.to_string(),
+                content_type: p
+                    .content_type()
+                    .unwrap_or("application/octet-stream")
+                    .to_string(),
+                size: Some(p.size()),
+                name: p.name().map(|n| n.to_string()),
+                cid: cid.map(|c| c.to_string()),
+            })
+        })
+        .collect()
+}
+
 fn resolve_identity(
     identities: &[jmap_client::identity::Identity],
     selector: Option<&str>,
@@ -452,6 +866,301 @@ fn resolve_identity(
     Ok((identity_id, from_name, from_email))
 }
 
+/// `header:Delivered-To:asText:all`, used to match the alias a message was delivered to.
+fn delivered_to_property() -> jmap_client::email::Property {
+    jmap_client::email::Property::Header(jmap_client::email::Header::as_text("Delivered-To", true))
+}
+
+/// Addresses the original was sent to, in identity-matching order: To, Cc, Delivered-To.
+fn original_recipients(original: &jmap_client::email::Email) -> Vec<(&'static str, String)> {
+    let mut out: Vec<(&'static str, String)> = Vec::new();
+    for (source, addrs) in [("to", original.to()), ("cc", original.cc())] {
+        for a in addrs.unwrap_or_default() {
+            out.push((source, a.email().to_string()));
+        }
+    }
+
+    let delivered_to = jmap_client::email::Header::as_text("Delivered-To", true);
+    if let Some(jmap_client::email::HeaderValue::AsTextAll(values)) = original.header(&delivered_to)
+    {
+        for v in values {
+            out.push(("deliveredTo", address_email(v)));
+        }
+    }
+    out
+}
+
+/// The identity a reply/forward should be sent from, given the addresses the original was
+/// sent to: an identity with exactly that address first, then a `*@domain` wildcard identity
+/// (sending as the matched address). Returns `(identity index, rule, source, address)`.
+fn match_identity(
+    identities: &[jmap_client::identity::Identity],
+    recipients: &[(&'static str, String)],
+) -> Option<(usize, &'static str, &'static str, String)> {
+    let exact = recipients.iter().find_map(|(source, addr)| {
+        identities
+            .iter()
+            .position(|i| i.email().is_some_and(|e| e.eq_ignore_ascii_case(addr)))
+            .map(|idx| (idx, "exact", *source, addr.clone()))
+    });
+    exact.or_else(|| {
+        recipients.iter().find_map(|(source, addr)| {
+            let domain = addr.rsplit_once('@')?.1;
+            identities
+                .iter()
+                .position(|i| {
+                    i.email()
+                        .and_then(|e| e.strip_prefix("*@"))
+                        .is_some_and(|d| d.eq_ignore_ascii_case(domain))
+                })
+                .map(|idx| (idx, "wildcard", *source, addr.clone()))
+        })
+    })
+}
+
+/// Resolve the sending identity for a reply or forward.
+///
+/// `--identity` wins; otherwise the identity matching the original's recipients (see
+/// `match_identity`); otherwise the default (first) identity. Also returns `data.identity`,
+/// which reports the rule that picked it.
+fn select_identity(
+    identities: &[jmap_client::identity::Identity],
+    selector: Option<&str>,
+    original: Option<&jmap_client::email::Email>,
+) -> Result<(String, Option<String>, String, Value), XinErrorOut> {
+    let recipients = original.map(original_recipients).unwrap_or_default();
+    select_identity_matching(identities, selector, &recipients)
+}
+
+/// `select_identity` against a list of `(header, address)` candidates.
+fn select_identity_matching(
+    identities: &[jmap_client::identity::Identity],
+    selector: Option<&str>,
+    candidates: &[(&'static str, String)],
+) -> Result<(String, Option<String>, String, Value), XinErrorOut> {
+    let matched = match selector {
+        None => match_identity(identities, candidates),
+        Some(_) => None,
+    };
+
+    let Some((idx, rule, source, address)) = matched else {
+        let (id, name, email) = resolve_identity(identities, selector)?;
+        let rule = if selector.is_some() {
+            "explicit"
+        } else {
+            "default"
+        };
+        let out = json!({"id": id, "email": email, "rule": rule});
+        return Ok((id, name, email, out));
+    };
+
+    let idt = &identities[idx];
+    let id = idt
+        .id()
+        .map(|id| id.to_string())
+        .ok_or_else(|| XinErrorOut::config("identity missing id".to_string()))?;
+    // A wildcard identity sends as the address that was matched.
+    let email = if rule == "wildcard" {
+        address.clone()
+    } else {
+        idt.email().unwrap_or(&address).to_string()
+    };
+    let out = json!({
+        "id": id,
+        "email": email,
+        "rule": rule,
+        "matched": {"header": source, "address": address}
+    });
+    Ok((id, idt.name().map(|n| n.to_string()), email, out))
+}
+
+#[cfg(test)]
+mod identity_selection_tests {
+    use super::*;
+
+    fn identities() -> Vec<jmap_client::identity::Identity> {
+        serde_json::from_value(json!([
+            {"id": "i1", "name": "Me", "email": "me@example.com"},
+            {"id": "i2", "name": "Sales", "email": "sales@example.com"},
+            {"id": "i3", "name": "Corp", "email": "*@corp.example"}
+        ]))
+        .expect("deserialize identities")
+    }
+
+    fn recipients(list: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+        list.iter().map(|(s, a)| (*s, a.to_string())).collect()
+    }
+
+    #[test]
+    fn exact_match_beats_wildcard_and_order() {
+        let ids = identities();
+        let r = recipients(&[("to", "ops@corp.example"), ("cc", "Sales@Example.com")]);
+        let (idx, rule, source, addr) = match_identity(&ids, &r).expect("match");
+        assert_eq!(
+            (idx, rule, source, addr.as_str()),
+            (1, "exact", "cc", "Sales@Example.com")
+        );
+    }
+
+    #[test]
+    fn wildcard_sends_as_matched_address() {
+        let ids = identities();
+        let original: jmap_client::email::Email = serde_json::from_value(json!({
+            "to": [{"name": null, "email": "list@other.example"}],
+            "header:Delivered-To:asText:all": ["ops@corp.example"]
+        }))
+        .expect("deserialize Email");
+
+        let (id, _, email, out) = select_identity(&ids, None, Some(&original)).expect("select");
+        assert_eq!(id, "i3");
+        assert_eq!(email, "ops@corp.example");
+        assert_eq!(out["rule"], "wildcard");
+        assert_eq!(out["matched"]["header"], "deliveredTo");
+    }
+
+    #[test]
+    fn falls_back_to_explicit_or_default() {
+        let ids = identities();
+        let original: jmap_client::email::Email =
+            serde_json::from_value(json!({"to": [{"name": null, "email": "sales@example.com"}]}))
+                .expect("deserialize Email");
+
+        let (id, _, _, out) = select_identity(&ids, Some("i1"), Some(&original)).expect("select");
+        assert_eq!(
+            (id.as_str(), out["rule"].as_str()),
+            ("i1", Some("explicit"))
+        );
+
+        let (id, _, _, out) = select_identity(&ids, None, None).expect("select");
+        assert_eq!((id.as_str(), out["rule"].as_str()), ("i1", Some("default")));
+    }
+}
+
+/// `--signature`: append the identity's `textSignature` / `htmlSignature` to the bodies.
+async fn with_signature(
+    backend: &Backend,
+    identity_id: &str,
+    text: Option<String>,
+    html: Option<String>,
+    warnings: &mut Vec<String>,
+) -> Result<(Option<String>, Option<String>), XinErrorOut> {
+    let (text_signature, html_signature) = identity_signature(backend, identity_id).await?;
+
+    match quote::append_signature(text.clone(), html.clone(), &text_signature, &html_signature) {
+        Some(bodies) => Ok(bodies),
+        None => {
+            warnings.push(format!(
+                "--signature: identity {identity_id} has no signature"
+            ));
+            Ok((text, html))
+        }
+    }
+}
+
+/// The identity's `(textSignature, htmlSignature)`, empty when unset.
+async fn identity_signature(
+    backend: &Backend,
+    identity_id: &str,
+) -> Result<(String, String), XinErrorOut> {
+    let identity = backend
+        .identities_get_raw(Some(&[identity_id.to_string()]))
+        .await?
+        .into_iter()
+        .next();
+    let signature = |key: &str| {
+        identity
+            .as_ref()
+            .and_then(|i| i.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    Ok((signature("textSignature"), signature("htmlSignature")))
+}
+
+/// Directory that relative `<img src>` paths resolve against: that of an `@file` body.
+fn body_base_dir(arg: Option<&str>) -> Option<&Path> {
+    arg.and_then(|v| v.strip_prefix('@'))
+        .and_then(|p| Path::new(p).parent())
+}
+
+/// A local file to upload: an `--attach` file or an inline image.
+struct LocalFile {
+    /// The path as given, for error messages.
+    label: String,
+    path: PathBuf,
+    size: u64,
+    content_type: String,
+    name: Option<String>,
+    cid: Option<String>,
+}
+
+/// Read the local images an HTML body references (`<img src="./logo.png">`) as inline parts
+/// and point their `src` at `cid:`. Relative paths resolve against `base_dir`, or the working
+/// directory. Nothing is uploaded yet (see `upload_attachments`).
+fn inline_local_images(
+    html: Option<String>,
+    base_dir: Option<&Path>,
+) -> Result<(Option<String>, Vec<LocalFile>), XinErrorOut> {
+    let Some(html) = html else {
+        return Ok((None, Vec::new()));
+    };
+    let srcs = inline_images::local_image_srcs(&html);
+    if srcs.is_empty() {
+        return Ok((Some(html), Vec::new()));
+    }
+
+    // Images may only come from the body file's directory (the working directory for a
+    // literal body), so a body cannot pull in arbitrary local files.
+    let root = match base_dir {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.canonicalize(),
+        _ => std::env::current_dir().and_then(|d| d.canonicalize()),
+    }
+    .map_err(|e| {
+        XinErrorOut::usage(format!("failed to resolve the inline image directory: {e}"))
+    })?;
+
+    let mut cids: HashMap<String, String> = HashMap::new();
+    let mut files: Vec<LocalFile> = Vec::with_capacity(srcs.len());
+    for (n, src) in srcs.into_iter().enumerate() {
+        let joined = root.join(inline_images::src_path(&src));
+        let read_err = |e: std::io::Error| {
+            XinErrorOut::usage(format!(
+                "failed to read inline image {src} ({}): {e}",
+                joined.display()
+            ))
+        };
+        let path = joined.canonicalize().map_err(read_err)?;
+        if !path.starts_with(&root) {
+            return Err(XinErrorOut::usage(format!(
+                "inline image {src} is outside {}; only files under the body's directory are embedded",
+                root.display()
+            )));
+        }
+        let (size, head) = mime::file_head(&path).map_err(read_err)?;
+        let Some(content_type) = mime::sniff(&head).filter(|t| t.starts_with("image/")) else {
+            return Err(XinErrorOut::usage(format!(
+                "inline image {src} is not an image file"
+            )));
+        };
+
+        let path_str = path.to_string_lossy();
+        let name = infer_filename(&path_str);
+        let cid = inline_images::content_id(n + 1, name.as_deref().unwrap_or("image"));
+        files.push(LocalFile {
+            label: src.clone(),
+            content_type: content_type.to_string(),
+            name,
+            cid: Some(cid.clone()),
+            path,
+            size,
+        });
+        cids.insert(src, cid);
+    }
+
+    Ok((Some(inline_images::rewrite_srcs(&html, &cids)), files))
+}
+
 fn infer_filename(path: &str) -> Option<String> {
     Path::new(path)
         .file_name()
@@ -459,44 +1168,255 @@ fn infer_filename(path: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-fn guess_content_type(path: &str) -> Option<&'static str> {
-    // Minimal heuristics; unknown types fall back to application/octet-stream.
-    let ext = Path::new(path)
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    match ext.as_str() {
-        "txt" => Some("text/plain"),
-        "md" => Some("text/markdown"),
-        "html" | "htm" => Some("text/html"),
-        "json" => Some("application/json"),
-        "pdf" => Some("application/pdf"),
-        "png" => Some("image/png"),
-        "jpg" | "jpeg" => Some("image/jpeg"),
-        _ => None,
+/// Read `--attach path[;type=...][;name=...]` files. The type is the `type=` option, else
+/// guessed from the extension and then the content.
+fn read_attachments(specs: &[String]) -> Result<Vec<LocalFile>, XinErrorOut> {
+    let mut files: Vec<LocalFile> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let spec = mime::parse_attach_spec(spec)?;
+        let p = &spec.path;
+        let (size, head) = mime::file_head(Path::new(p))
+            .map_err(|e| XinErrorOut::usage(format!("failed to read attachment {p}: {e}")))?;
+
+        let content_type = spec
+            .content_type
+            .unwrap_or_else(|| mime::content_type(p, &head).to_string());
+        files.push(LocalFile {
+            name: spec.name.or_else(|| infer_filename(p)),
+            path: PathBuf::from(p),
+            label: spec.path,
+            size,
+            content_type,
+            cid: None,
+        });
+    }
+    Ok(files)
+}
+
+/// Headers of the message itself, in the size estimate.
+const MESSAGE_HEADER_BYTES: usize = 1024;
+
+/// Estimated size of a `size`-byte blob once base64-encoded as a MIME part.
+fn encoded_part_size(size: usize) -> usize {
+    256 + mime::base64_size(size)
+}
+
+/// Estimated size of a message with these bodies and already-uploaded blobs, without any
+/// files still to upload.
+fn message_size_estimate(text: Option<&str>, html: Option<&str>, blobs: &[UploadedBlob]) -> usize {
+    MESSAGE_HEADER_BYTES
+        + text.map_or(0, |t| 256 + t.len())
+        + html.map_or(0, |h| 256 + h.len())
+        + blobs
+            .iter()
+            .map(|b| encoded_part_size(b.size.unwrap_or(0)))
+            .sum::<usize>()
+}
+
+/// Fail with `xinTooLarge` when a file exceeds `maxSizeUpload`, or the message (`rest_size`
+/// plus the encoded files) exceeds the submission `SIZE` limit.
+fn preflight_upload(
+    limits: UploadLimits,
+    files: &[LocalFile],
+    rest_size: usize,
+) -> Result<(), XinErrorOut> {
+    for f in files {
+        preflight_upload_size(limits, &f.label, f.size)?;
+    }
+
+    let total = rest_size
+        + files
+            .iter()
+            .map(|f| encoded_part_size(f.size as usize))
+            .sum::<usize>();
+    match limits.max_message_size {
+        Some(max) if total as u64 > max => Err(XinErrorOut::too_large(format!(
+            "the message would be about {total} bytes once encoded; the server accepts messages up to {max} bytes (SIZE)"
+        ))),
+        _ => Ok(()),
     }
 }
 
+/// A temporary copy of `path` (removed by the caller) with its first `head_len` bytes
+/// replaced by `head`, e.g. a header section without `Bcc`.
+fn copy_with_header(path: &Path, head_len: u64, head: &[u8]) -> std::io::Result<PathBuf> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let tmp = std::env::temp_dir().join(format!("xin-send-{}.eml", std::process::id()));
+    let mut src = fs::File::open(path)?;
+    src.seek(SeekFrom::Start(head_len))?;
+    let mut out = fs::File::create(&tmp)?;
+    out.write_all(head)?;
+    std::io::copy(&mut src, &mut out)?;
+    Ok(tmp)
+}
+
+/// Fail with `xinTooLarge` when one upload of `size` bytes exceeds `maxSizeUpload`.
+fn preflight_upload_size(limits: UploadLimits, label: &str, size: u64) -> Result<(), XinErrorOut> {
+    match limits.max_size_upload {
+        Some(max) if size > max => Err(XinErrorOut::too_large(format!(
+            "{label} is {size} bytes; the server accepts uploads up to {max} bytes (maxSizeUpload)"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Read the `--attach` files, check them and `inline` against the server limits (before
+/// anything is uploaded), then upload them all, streamed and in parallel. `rest_size` is
+/// `message_size_estimate` of the rest of the message.
 async fn upload_attachments(
     backend: &Backend,
-    paths: &[String],
+    specs: &[String],
+    inline: Vec<LocalFile>,
+    rest_size: usize,
 ) -> Result<Vec<UploadedBlob>, XinErrorOut> {
-    let mut uploaded: Vec<UploadedBlob> = Vec::new();
-
-    for p in paths {
-        let bytes = fs::read(p)
-            .map_err(|e| XinErrorOut::usage(format!("failed to read attachment {p}: {e}")))?;
+    let mut files = read_attachments(specs)?;
+    files.extend(inline);
+    preflight_upload(backend.upload_limits(), &files, rest_size)?;
 
-        let content_type = guess_content_type(p).unwrap_or("application/octet-stream");
-        let name = infer_filename(p);
-        uploaded.push(backend.upload_blob(bytes, Some(content_type), name).await?);
+    let uploads: Vec<UploadFile> = files
+        .iter()
+        .map(|f| UploadFile {
+            path: &f.path,
+            size: f.size,
+            content_type: &f.content_type,
+            name: f.name.clone(),
+        })
+        .collect();
+    let mut uploaded = backend.upload_files(&uploads).await?;
+    for (blob, f) in uploaded.iter_mut().zip(files) {
+        blob.cid = f.cid;
     }
 
     Ok(uploaded)
 }
 
+#[cfg(test)]
+mod upload_preflight_tests {
+    use super::*;
+
+    fn file(label: &str, size: u64) -> LocalFile {
+        LocalFile {
+            label: label.to_string(),
+            path: PathBuf::from(label),
+            size,
+            content_type: "application/octet-stream".to_string(),
+            name: None,
+            cid: None,
+        }
+    }
+
+    #[test]
+    fn rejects_files_over_max_size_upload() {
+        let limits = UploadLimits {
+            max_size_upload: Some(100),
+            ..UploadLimits::default()
+        };
+        assert!(preflight_upload(limits, &[file("a.bin", 100)], 0).is_ok());
+
+        let e = preflight_upload(limits, &[file("a.bin", 10), file("b.bin", 101)], 0)
+            .expect_err("too large");
+        assert_eq!(e.kind, "xinTooLarge");
+        assert_eq!(
+            e.message,
+            "b.bin is 101 bytes; the server accepts uploads up to 100 bytes (maxSizeUpload)"
+        );
+    }
+
+    #[test]
+    fn rejects_messages_over_submission_size() {
+        let limits = UploadLimits {
+            max_message_size: Some(10_000),
+            ..UploadLimits::default()
+        };
+        // 6000 bytes encode to 8000 + line breaks: over the limit with the rest of the message.
+        assert!(preflight_upload(limits, &[file("a.bin", 6000)], 0).is_ok());
+        let e = preflight_upload(limits, &[file("a.bin", 6000)], 2000).expect_err("too large");
+        assert!(e.message.ends_with("up to 10000 bytes (SIZE)"));
+
+        // A message uploaded as is (`--eml`) counts at its raw size.
+        assert!(preflight_upload(limits, &[], 10_000).is_ok());
+        assert!(preflight_upload(limits, &[], 10_001).is_err());
+    }
+}
+
+/// `--attach-blob <emailId>:<blobId>` / `<blobId>`: existing blobs referenced as attachments,
+/// nothing downloaded or re-uploaded. With an emailId, the name, type and size come from that
+/// email's attachment metadata; a bare blobId is sent as `application/octet-stream`.
+async fn attachment_blob_refs(
+    backend: &Backend,
+    specs: &[String],
+) -> Result<Vec<UploadedBlob>, XinErrorOut> {
+    let mut emails: HashMap<String, Vec<Value>> = HashMap::new();
+    let mut out: Vec<UploadedBlob> = Vec::with_capacity(specs.len());
+
+    for spec in specs {
+        let Some((email_id, blob_id)) = spec.trim().split_once(':') else {
+            let blob_id = spec.trim();
+            if blob_id.is_empty() {
+                return Err(XinErrorOut::usage(
+                    "--attach-blob: empty blobId".to_string(),
+                ));
+            }
+            out.push(UploadedBlob {
+                blob_id: blob_id.to_string(),
+                content_type: "application/octet-stream".to_string(),
+                size: None,
+                name: None,
+                cid: None,
+            });
+            continue;
+        };
+        if email_id.is_empty() || blob_id.is_empty() {
+            return Err(XinErrorOut::usage(format!(
+                "--attach-blob {spec}: expected <emailId>:<blobId> or <blobId>"
+            )));
+        }
+
+        if !emails.contains_key(email_id) {
+            let email = backend
+                .get_email(
+                    email_id,
+                    Some(vec![
+                        jmap_client::email::Property::Id,
+                        jmap_client::email::Property::Attachments,
+                    ]),
+                )
+                .await?
+                .ok_or_else(|| XinErrorOut::usage(format!("email not found: {email_id}")))?;
+            emails.insert(email_id.to_string(), schema::extract_attachments(&email));
+        }
+
+        let attachment = emails[email_id]
+            .iter()
+            .find(|a| a.get("blobId").and_then(|v| v.as_str()) == Some(blob_id))
+            .ok_or_else(|| {
+                XinErrorOut::usage(format!(
+                    "--attach-blob {spec}: blob {blob_id} is not an attachment of email {email_id} (see `xin get {email_id} --format full`)"
+                ))
+            })?;
+        out.push(UploadedBlob {
+            blob_id: blob_id.to_string(),
+            content_type: attachment
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("application/octet-stream")
+                .to_string(),
+            size: attachment
+                .get("size")
+                .and_then(|v| v.as_u64())
+                .map(|s| s as usize),
+            name: attachment
+                .get("name")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            cid: None,
+        });
+    }
+
+    Ok(out)
+}
+
 pub async fn identities_list(account: Option<String>) -> Envelope<Value> {
     let backend = match Backend::connect(account.as_deref()).await {
         Ok(b) => b,
@@ -533,37 +1453,230 @@ pub async fn identities_get(account: Option<String>, args: &IdentitiesGetArgs) -
         Err(e) => return Envelope::err("identities.get", account, e),
     };
 
-    let identities = match backend.list_identities().await {
+    let identity = match find_identity_raw(&backend, &args.id).await {
         Ok(i) => i,
         Err(e) => return Envelope::err("identities.get", account, e),
     };
 
-    let sel = args.id.as_str();
-    let found = identities.iter().find(|i| {
-        i.id().map(|id| id == sel).unwrap_or(false) || i.email().map(|e| e == sel).unwrap_or(false)
-    });
-
-    let Some(i) = found else {
-        return Envelope::err(
-            "identities.get",
-            account,
+    Envelope::ok(
+        "identities.get",
+        account,
+        json!({ "identity": identity }),
+        Meta::default(),
+    )
+}
+
+/// An identity (all reported properties) by id or email.
+async fn find_identity_raw(backend: &Backend, sel: &str) -> Result<Value, XinErrorOut> {
+    let identities = backend.identities_get_raw(None).await?;
+    let field = |i: &Value, key: &str| i.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+    identities
+        .iter()
+        .find(|i| field(i, "id").as_deref() == Some(sel))
+        .or_else(|| {
+            identities
+                .iter()
+                .find(|i| field(i, "email").is_some_and(|e| e.eq_ignore_ascii_case(sel)))
+        })
+        .cloned()
+        .ok_or_else(|| {
             XinErrorOut::usage(format!(
                 "identity not found: {sel} (use `xin identities list`)"
-            )),
+            ))
+        })
+}
+
+/// `Name <email>` / `email` as a JMAP `EmailAddress`.
+fn email_address_value(s: &str) -> Value {
+    let email = address_email(s);
+    let name = s
+        .rfind('<')
+        .map(|l| s[..l].trim().trim_matches('"').trim())
+        .filter(|n| !n.is_empty());
+    json!({"name": name, "email": email})
+}
+
+fn email_addresses_value(list: &[String]) -> Value {
+    Value::Array(list.iter().map(|s| email_address_value(s)).collect())
+}
+
+pub async fn identities_create(
+    account: Option<String>,
+    args: &IdentitiesCreateArgs,
+) -> Envelope<Value> {
+    let command_name = "identities.create";
+
+    let mut identity = json!({
+        "email": args.email.trim(),
+        "name": args.name.clone().unwrap_or_default()
+    });
+    let obj = identity.as_object_mut().expect("identity object");
+    if !args.reply_to.is_empty() {
+        obj.insert("replyTo".to_string(), email_addresses_value(&args.reply_to));
+    }
+    if !args.bcc.is_empty() {
+        obj.insert("bcc".to_string(), email_addresses_value(&args.bcc));
+    }
+    for (key, value) in [
+        ("textSignature", &args.text_signature),
+        ("htmlSignature", &args.html_signature),
+    ] {
+        if let Some(v) = value {
+            match read_text_arg(v) {
+                Ok(t) => obj.insert(key.to_string(), json!(t)),
+                Err(e) => return Envelope::err(command_name, account, e),
+            };
+        }
+    }
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    match backend.create_identity(identity).await {
+        Ok(created) => Envelope::ok(
+            command_name,
+            account,
+            json!({ "identity": created }),
+            Meta::default(),
+        ),
+        Err(e) => Envelope::err(command_name, account, e),
+    }
+}
+
+pub async fn identities_update(
+    account: Option<String>,
+    args: &IdentitiesUpdateArgs,
+) -> Envelope<Value> {
+    let command_name = "identities.update";
+
+    let mut patch = serde_json::Map::new();
+    if let Some(name) = &args.name {
+        patch.insert("name".to_string(), json!(name));
+    }
+    if args.clear_reply_to {
+        patch.insert("replyTo".to_string(), Value::Null);
+    } else if !args.reply_to.is_empty() {
+        patch.insert("replyTo".to_string(), email_addresses_value(&args.reply_to));
+    }
+    if args.clear_bcc {
+        patch.insert("bcc".to_string(), Value::Null);
+    } else if !args.bcc.is_empty() {
+        patch.insert("bcc".to_string(), email_addresses_value(&args.bcc));
+    }
+    for (key, value) in [
+        ("textSignature", &args.text_signature),
+        ("htmlSignature", &args.html_signature),
+    ] {
+        if let Some(v) = value {
+            match read_text_arg(v) {
+                Ok(t) => patch.insert(key.to_string(), json!(t)),
+                Err(e) => return Envelope::err(command_name, account, e),
+            };
+        }
+    }
+
+    if patch.is_empty() {
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::usage(
+                "nothing to update: pass --name, --reply-to, --bcc, --text-signature or --html-signature"
+                    .to_string(),
+            ),
         );
+    }
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let id = match find_identity_raw(&backend, &args.id).await {
+        Ok(i) => i
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let updated: Vec<String> = patch.keys().cloned().collect();
+    if let Err(e) = backend.update_identity(&id, Value::Object(patch)).await {
+        return Envelope::err(command_name, account, e);
+    }
+
+    let identity = match find_identity_raw(&backend, &id).await {
+        Ok(i) => i,
+        Err(e) => return Envelope::err(command_name, account, e),
     };
 
     Envelope::ok(
-        "identities.get",
+        command_name,
         account,
-        json!({
-            "identity": {"id": i.id(), "name": i.name(), "email": i.email()}
-        }),
+        json!({ "identity": identity, "updated": updated }),
         Meta::default(),
     )
 }
 
-pub async fn send(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
+pub async fn identities_delete(
+    account: Option<String>,
+    args: &IdentitiesDeleteArgs,
+    force: bool,
+) -> Envelope<Value> {
+    let command_name = "identities.delete";
+
+    if !force {
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::usage("identities delete is destructive; pass --force".to_string()),
+        );
+    }
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let identity = match find_identity_raw(&backend, &args.id).await {
+        Ok(i) => i,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let id = identity
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    if let Err(e) = backend.destroy_identity(&id).await {
+        return Envelope::err(command_name, account, e);
+    }
+
+    Envelope::ok(
+        command_name,
+        account,
+        json!({ "deleted": id, "email": identity.get("email") }),
+        Meta::default(),
+    )
+}
+
+pub async fn send(account: Option<String>, args: &SendArgs, dry_run: bool) -> Envelope<Value> {
+    if args.template.is_some() {
+        let env = send_merge(account, args, dry_run).await;
+        // `--ndjson` prints no final envelope (see main.rs), so a command-level error
+        // becomes one more line.
+        if let Some(e) = env.error.as_ref().filter(|_| args.ndjson) {
+            super::watch::json_line(&json!({"type": "error", "error": e}));
+        }
+        return env;
+    }
+    if args.eml.is_some() {
+        return send_eml(account, args).await;
+    }
+
     let backend = match Backend::connect(account.as_deref()).await {
         Ok(b) => b,
         Err(e) => return Envelope::err("send", account, e),
@@ -594,6 +1707,14 @@ pub async fn send(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
     let to = args.to.clone();
     let cc = args.cc.clone();
 
+    // Checked before any side effects: the draft is only created if it can be scheduled.
+    let envelope = match send_at_parameters(&backend, args.send_at.as_deref()) {
+        Ok(hold) => {
+            hold.map(|p| hold_envelope(&from_email, to.iter().chain(&cc).chain(&args.bcc), p))
+        }
+        Err(e) => return Envelope::err("send", account, e),
+    };
+
     let text = match &args.text {
         Some(v) => Some(match read_text_arg(v) {
             Ok(t) => t,
@@ -610,21 +1731,58 @@ pub async fn send(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
         None => None,
     };
 
-    if text.is_none() && html.is_none() && args.attach.is_empty() {
+    let (text, html) = match &args.markdown {
+        Some(v) => match read_text_arg(v) {
+            Ok(md) => {
+                let (t, h) = markdown::render(&md);
+                (Some(t), Some(h))
+            }
+            Err(e) => return Envelope::err("send", account, e),
+        },
+        None => (text, html),
+    };
+
+    if text.is_none() && html.is_none() && args.attach.is_empty() && args.attach_blob.is_empty() {
         return Envelope::err(
             "send",
             account,
             XinErrorOut::usage(
-                "missing message content: provide --text, --body-html, or --attach".to_string(),
+                "missing message content: provide --text, --body-html, --markdown, --attach or --attach-blob"
+                    .to_string(),
             ),
         );
     }
 
-    let uploaded = match upload_attachments(&backend, &args.attach).await {
+    let blob_refs = match attachment_blob_refs(&backend, &args.attach_blob).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err("send", account, e),
+    };
+
+    let (html, inline) = match inline_local_images(
+        html,
+        body_base_dir(args.body_html.as_deref().or(args.markdown.as_deref())),
+    ) {
         Ok(v) => v,
         Err(e) => return Envelope::err("send", account, e),
     };
 
+    let mut warnings: Vec<String> = Vec::new();
+    let (text, html) = if args.signature {
+        match with_signature(&backend, &identity_id, text, html, &mut warnings).await {
+            Ok(v) => v,
+            Err(e) => return Envelope::err("send", account, e),
+        }
+    } else {
+        (text, html)
+    };
+
+    let rest_size = message_size_estimate(text.as_deref(), html.as_deref(), &blob_refs);
+    let mut uploaded = match upload_attachments(&backend, &args.attach, inline, rest_size).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err("send", account, e),
+    };
+    uploaded.extend(blob_refs);
+
     let email = match backend
         .create_draft_email(
             &drafts_id,
@@ -633,7 +1791,7 @@ pub async fn send(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
             &to,
             &cc,
             &args.bcc,
-            Some(&args.subject),
+            args.subject.as_deref(),
             text.as_deref(),
             html.as_deref(),
             &uploaded,
@@ -655,10 +1813,21 @@ pub async fn send(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
         }
     };
 
-    let submission = match backend.submit_email(&email_id, &identity_id).await {
+    let (on_success, after_send) = after_send_plan(
+        backend.after_send(),
+        &mailboxes,
+        Some(&drafts_id),
+        &mut warnings,
+    );
+
+    let submission = match backend
+        .submit_email(&email_id, &identity_id, envelope.as_ref(), &on_success)
+        .await
+    {
         Ok(s) => s,
         Err(e) => return Envelope::err("send", account, e),
     };
+    let (submission_out, after_send_out) = submission_out(&submission, after_send, &mut warnings);
 
     let uploaded_out = uploaded
         .iter()
@@ -667,23 +1836,553 @@ pub async fn send(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
                 "blobId": u.blob_id,
                 "type": u.content_type,
                 "size": u.size,
+                "cid": u.cid,
             })
         })
         .collect::<Vec<_>>();
 
-    let data = json!({
+    let mut data = json!({
         "draft": {
             "emailId": email_id,
             "threadId": email.thread_id()
         },
-        "submission": {
-            "id": submission.id(),
-            "sendAt": to_rfc3339(submission.send_at())
-        },
+        "submission": submission_out,
+        "afterSend": after_send_out,
         "uploaded": uploaded_out
     });
+    if args.wait_delivery {
+        data["delivery"] =
+            wait_for_delivery(&backend, &submission.id, args.wait_timeout, &mut warnings).await;
+    }
+
+    let meta = Meta {
+        warnings: (!warnings.is_empty()).then_some(warnings),
+        ..Meta::default()
+    };
 
-    Envelope::ok("send", account, data, Meta::default())
+    Envelope::ok("send", account, data, meta)
+}
+
+/// `xin send --eml @file.eml`: upload the message, `Email/import` it into Drafts unchanged and
+/// submit it. The envelope comes from its headers unless `--mail-from` / `--rcpt-to` are given.
+async fn send_eml(account: Option<String>, args: &SendArgs) -> Envelope<Value> {
+    let command_name = "send";
+
+    let value = args.eml.as_deref().unwrap_or("");
+    let path = value.strip_prefix('@').unwrap_or(value);
+    // Only the header section is read here; the file is streamed when it is uploaded.
+    let read = fs::File::open(path).and_then(|f| {
+        let file_size = f.metadata()?.len();
+        Ok((eml::read_header(std::io::BufReader::new(f))?, file_size))
+    });
+    let (head, file_size) = match read {
+        Ok(v) => v,
+        Err(e) => {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::usage(format!("failed to read --eml {path}: {e}")),
+            );
+        }
+    };
+    let Some(fields) = eml::header_fields(&head) else {
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::usage(format!(
+                "--eml {path} is not an RFC 5322 message (no header fields)"
+            )),
+        );
+    };
+
+    let from = eml::addresses(&fields, "From");
+    let mail_from = match args.mail_from.clone().or_else(|| {
+        eml::addresses(&fields, "Sender")
+            .into_iter()
+            .chain(from.iter().cloned())
+            .next()
+    }) {
+        Some(a) => a,
+        None => {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::usage(
+                    "--eml: the message has no From address; pass --mail-from".to_string(),
+                ),
+            );
+        }
+    };
+    let bcc = eml::addresses(&fields, "Bcc");
+    let rcpt_to: Vec<String> = if args.rcpt_to.is_empty() {
+        eml::addresses(&fields, "To")
+            .into_iter()
+            .chain(eml::addresses(&fields, "Cc"))
+            .chain(bcc.iter().cloned())
+            .collect()
+    } else {
+        args.rcpt_to.clone()
+    };
+    if rcpt_to.is_empty() {
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::usage("--eml: no recipients in To/Cc/Bcc; pass --rcpt-to".to_string()),
+        );
+    }
+
+    // Bcc recipients only go in the envelope; the header would show them to everyone.
+    let without_bcc = fields
+        .iter()
+        .any(|(n, _)| n.eq_ignore_ascii_case("Bcc"))
+        .then(|| eml::remove_field(&head, "Bcc"));
+    let size = match &without_bcc {
+        Some(h) => file_size - head.len() as u64 + h.len() as u64,
+        None => file_size,
+    };
+
+    let mut warnings: Vec<String> = Vec::new();
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    // The message is uploaded as is: one upload, and the whole message for SIZE.
+    let limits = backend.upload_limits();
+    if let Err(e) = preflight_upload_size(limits, &format!("--eml {path}"), size)
+        .and_then(|_| preflight_upload(limits, &[], size as usize))
+    {
+        return Envelope::err(command_name, account, e);
+    }
+
+    let mailboxes = match backend.list_mailboxes().await {
+        Ok(m) => m,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let drafts_id = match find_drafts_mailbox_id(&mailboxes) {
+        Ok(id) => id,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let identities = match backend.list_identities().await {
+        Ok(i) => i,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    // Without --identity: the identity the message is From.
+    let candidates: Vec<(&'static str, String)> =
+        from.iter().map(|a| ("from", a.clone())).collect();
+    let (identity_id, _, identity_email, identity_out) =
+        match select_identity_matching(&identities, args.identity.as_deref(), &candidates) {
+            Ok(v) => v,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+    if !from.iter().any(|a| a.eq_ignore_ascii_case(&identity_email)) {
+        warnings.push(format!(
+            "From ({}) does not match identity {identity_email}; the server may reject the submission (forbiddenFrom)",
+            from.join(", ")
+        ));
+    }
+
+    // Checked before any side effects: the email is only imported if it can be scheduled.
+    let envelope = match send_at_parameters(&backend, args.send_at.as_deref()) {
+        Ok(hold) => hold_envelope(&mail_from, &rcpt_to, hold.unwrap_or(Value::Null)),
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let upload_path = match &without_bcc {
+        Some(h) => match copy_with_header(Path::new(path), head.len() as u64, h) {
+            Ok(p) => p,
+            Err(e) => {
+                return Envelope::err(
+                    command_name,
+                    account,
+                    XinErrorOut::usage(format!("failed to copy --eml {path}: {e}")),
+                );
+            }
+        },
+        None => PathBuf::from(path),
+    };
+    let uploaded = backend
+        .upload_files(&[UploadFile {
+            path: &upload_path,
+            size,
+            content_type: "message/rfc822",
+            name: None,
+        }])
+        .await;
+    if without_bcc.is_some() {
+        let _ = fs::remove_file(&upload_path);
+    }
+    let blob = match uploaded {
+        Ok(mut b) => b.remove(0),
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let imported = match backend.import_email(&blob.blob_id, &drafts_id).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let Some(email_id) = imported.get("id").and_then(|v| v.as_str()) else {
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::config("Email/import did not return email id".to_string()),
+        );
+    };
+
+    let (on_success, after_send) = after_send_plan(
+        backend.after_send(),
+        &mailboxes,
+        Some(&drafts_id),
+        &mut warnings,
+    );
+
+    let submission = match backend
+        .submit_email(email_id, &identity_id, Some(&envelope), &on_success)
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let (submission_out, after_send_out) = submission_out(&submission, after_send, &mut warnings);
+
+    let mut data = json!({
+        "draft": {
+            "emailId": email_id,
+            "threadId": imported.get("threadId")
+        },
+        "submission": submission_out,
+        "afterSend": after_send_out,
+        "identity": identity_out,
+        "envelope": envelope,
+        "imported": {"blobId": blob.blob_id, "size": size}
+    });
+    if args.wait_delivery {
+        data["delivery"] =
+            wait_for_delivery(&backend, &submission.id, args.wait_timeout, &mut warnings).await;
+    }
+
+    let meta = Meta {
+        warnings: (!warnings.is_empty()).then_some(warnings),
+        ..Meta::default()
+    };
+    Envelope::ok(command_name, account, data, meta)
+}
+
+/// Mail merge: `xin send --template @tmpl --data @rows.csv|.jsonl`, one draft (and
+/// submission) per row. Every row is rendered before anything is created.
+async fn send_merge(account: Option<String>, args: &SendArgs, dry_run: bool) -> Envelope<Value> {
+    let command_name = "send";
+
+    let template = match args
+        .template
+        .as_deref()
+        .map(read_text_arg)
+        .transpose()
+        .and_then(|src| merge::parse_template(src.as_deref().unwrap_or("")))
+    {
+        Ok(t) => t,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let data = args.data.as_deref().unwrap_or("");
+    let data_path = data.strip_prefix('@').unwrap_or(data);
+    let rows = match fs::read_to_string(data_path)
+        .map_err(|e| XinErrorOut::usage(format!("failed to read data file {data_path}: {e}")))
+        .and_then(|src| merge::parse_rows(data_path, &src))
+    {
+        Ok(r) => r,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let selection = match args
+        .rows
+        .as_deref()
+        .map(|s| merge::parse_row_selection(s, rows.len()))
+        .transpose()
+    {
+        Ok(s) => s,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let rendered: Vec<(usize, Result<merge::Rendered, XinErrorOut>)> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (i + 1, row))
+        .filter(|(n, _)| selection.as_ref().is_none_or(|s| s.contains(n)))
+        .map(|(n, row)| {
+            let rendered = merge::render(&template, row).map(|mut m| {
+                m.cc.extend(args.cc.iter().cloned());
+                m.bcc.extend(args.bcc.iter().cloned());
+                m
+            });
+            (n, rendered)
+        })
+        .collect();
+
+    if dry_run {
+        let results: Vec<Value> = rendered
+            .iter()
+            .map(|(n, r)| match r {
+                Ok(m) => json!({
+                    "row": n,
+                    "ok": true,
+                    "to": m.to,
+                    "cc": m.cc,
+                    "bcc": m.bcc,
+                    "subject": m.subject,
+                    "text": m.text,
+                    "html": m.html
+                }),
+                Err(e) => json!({"row": n, "ok": false, "error": e}),
+            })
+            .inspect(|r| {
+                if args.ndjson {
+                    super::watch::json_line(r);
+                }
+            })
+            .collect();
+        return merge_envelope(account, results, true, args.drafts_only, Vec::new());
+    }
+
+    if let Some((n, Err(e))) = rendered.iter().find(|(_, r)| r.is_err()) {
+        let failed = rendered.iter().filter(|(_, r)| r.is_err()).count();
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::usage(format!(
+                "{failed} of {} rows failed to render (row {n}: {}); nothing was sent, see --dry-run",
+                rendered.len(),
+                e.message
+            )),
+        );
+    }
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let mailboxes = match backend.list_mailboxes().await {
+        Ok(m) => m,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let drafts_id = match find_drafts_mailbox_id(&mailboxes) {
+        Ok(id) => id,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let identities = match backend.list_identities().await {
+        Ok(i) => i,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let (identity_id, from_name, from_email) =
+        match resolve_identity(&identities, args.identity.as_deref()) {
+            Ok(v) => v,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+
+    let hold = match send_at_parameters(&backend, args.send_at.as_deref()) {
+        Ok(h) => h,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let mut warnings: Vec<String> = Vec::new();
+    let signature = if args.signature {
+        match identity_signature(&backend, &identity_id).await {
+            Ok((t, h)) if t.trim().is_empty() && h.trim().is_empty() => {
+                warnings.push(format!(
+                    "--signature: identity {identity_id} has no signature"
+                ));
+                None
+            }
+            Ok(sig) => Some(sig),
+            Err(e) => return Envelope::err(command_name, account, e),
+        }
+    } else {
+        None
+    };
+
+    // Shared by every row: uploaded once, referenced by blobId.
+    let blob_refs = match attachment_blob_refs(&backend, &args.attach_blob).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    // Checked against the largest rendered row.
+    let rest_size = rendered
+        .iter()
+        .filter_map(|(_, r)| r.as_ref().ok())
+        .map(|m| message_size_estimate(m.text.as_deref(), m.html.as_deref(), &blob_refs))
+        .max()
+        .unwrap_or(0);
+    let mut uploaded = match upload_attachments(&backend, &args.attach, Vec::new(), rest_size).await
+    {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    uploaded.extend(blob_refs);
+
+    let (on_success, after_send) = after_send_plan(
+        backend.after_send(),
+        &mailboxes,
+        Some(&drafts_id),
+        &mut warnings,
+    );
+
+    let mut results: Vec<Value> = Vec::with_capacity(rendered.len());
+    for (i, (n, message)) in rendered.into_iter().enumerate() {
+        let Ok(mut message) = message else {
+            continue;
+        };
+        if i > 0 && !args.drafts_only && args.delay_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(args.delay_ms)).await;
+        }
+
+        if let Some((text, html)) = signature.as_ref().and_then(|(t, h)| {
+            quote::append_signature(message.text.clone(), message.html.clone(), t, h)
+        }) {
+            (message.text, message.html) = (text, html);
+        }
+
+        let mut result = json!({
+            "row": n,
+            "ok": true,
+            "to": message.to,
+            "subject": message.subject
+        });
+
+        let email = match backend
+            .create_draft_email(
+                &drafts_id,
+                from_name.clone(),
+                from_email.clone(),
+                &message.to,
+                &message.cc,
+                &message.bcc,
+                Some(&message.subject),
+                message.text.as_deref(),
+                message.html.as_deref(),
+                &uploaded,
+            )
+            .await
+            .and_then(|email| {
+                email
+                    .id()
+                    .map(str::to_string)
+                    .map(|id| (id, email))
+                    .ok_or_else(|| {
+                        XinErrorOut::config("Email/set did not return email id".to_string())
+                    })
+            }) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                result["ok"] = json!(false);
+                result["error"] = json!(e);
+                None
+            }
+        };
+
+        if let Some((email_id, email)) = email {
+            result["draft"] = json!({"emailId": email_id, "threadId": email.thread_id()});
+
+            if !args.drafts_only {
+                let envelope = hold.clone().map(|p| {
+                    hold_envelope(
+                        &from_email,
+                        message.to.iter().chain(&message.cc).chain(&message.bcc),
+                        p,
+                    )
+                });
+                match backend
+                    .submit_email(&email_id, &identity_id, envelope.as_ref(), &on_success)
+                    .await
+                {
+                    Ok(submission) => {
+                        let mut row_warnings = Vec::new();
+                        let (submission_out, after_send_out) =
+                            submission_out(&submission, after_send.clone(), &mut row_warnings);
+                        warnings.extend(row_warnings.into_iter().map(|w| format!("row {n}: {w}")));
+                        result["submission"] = submission_out;
+                        result["afterSend"] = after_send_out;
+                    }
+                    Err(e) => {
+                        // The draft stays; `xin drafts send <emailId>` or a retry of the row.
+                        result["ok"] = json!(false);
+                        result["error"] = json!(e);
+                    }
+                }
+            }
+        }
+
+        if args.ndjson {
+            super::watch::json_line(&result);
+        }
+        results.push(result);
+    }
+
+    merge_envelope(account, results, false, args.drafts_only, warnings)
+}
+
+fn merge_envelope(
+    account: Option<String>,
+    results: Vec<Value>,
+    dry_run: bool,
+    drafts_only: bool,
+    mut warnings: Vec<String>,
+) -> Envelope<Value> {
+    let failed: Vec<usize> = results
+        .iter()
+        .filter(|r| r.get("ok").and_then(|v| v.as_bool()) != Some(true))
+        .filter_map(|r| r.get("row").and_then(|v| v.as_u64()))
+        .map(|n| n as usize)
+        .collect();
+    let retry_rows = (!failed.is_empty()).then(|| merge::format_row_selection(&failed));
+    let failure = retry_rows.as_ref().map(|rows| {
+        format!(
+            "{} of {} rows failed; retry them with --rows {rows}",
+            failed.len(),
+            results.len()
+        )
+    });
+    if let Some(message) = &failure {
+        warnings.push(message.clone());
+    }
+
+    let data = json!({
+        "dryRun": dry_run,
+        "draftsOnly": drafts_only,
+        "summary": {
+            "total": results.len(),
+            "succeeded": results.len() - failed.len(),
+            "failed": failed.len()
+        },
+        "retryRows": retry_rows,
+        "results": results
+    });
+    let meta = Meta {
+        warnings: (!warnings.is_empty()).then_some(warnings),
+        ..Meta::default()
+    };
+    // Failed rows fail the command (exit 1), but the per-row results are still reported.
+    match failure {
+        None => Envelope::ok("send", account, data, meta),
+        Some(message) => Envelope {
+            data: Some(data),
+            meta,
+            ..Envelope::err(
+                "send",
+                account,
+                XinErrorOut {
+                    kind: "xinMergeFailed".to_string(),
+                    message,
+                    http: None,
+                    jmap: None,
+                },
+            )
+        },
+    }
 }
 
 pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value> {
@@ -703,36 +2402,46 @@ pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value>
         Err(e) => return Envelope::err("reply", account, e),
     };
 
-    // Resolve sending identity (needed for EmailSubmission and for reply-all self exclusion).
     let identities = match backend.list_identities().await {
         Ok(i) => i,
         Err(e) => return Envelope::err("reply", account, e),
     };
 
-    let (identity_id, from_name, from_email) =
-        match resolve_identity(&identities, args.identity.as_deref()) {
-            Ok(v) => v,
-            Err(e) => return Envelope::err("reply", account, e),
-        };
-
-    // Fetch original email by emailId.
-    let original = match backend
-        .get_email(
-            &args.email_id,
-            Some(vec![
-                jmap_client::email::Property::Id,
-                jmap_client::email::Property::ThreadId,
-                jmap_client::email::Property::MessageId,
-                jmap_client::email::Property::References,
-                jmap_client::email::Property::From,
-                jmap_client::email::Property::ReplyTo,
-                jmap_client::email::Property::To,
-                jmap_client::email::Property::Cc,
-                jmap_client::email::Property::Subject,
-            ]),
-        )
-        .await
-    {
+    // Fetch original email by emailId (with its bodies when quoting).
+    let original = if args.quote {
+        backend
+            .get_email_full(
+                &args.email_id,
+                QUOTE_MAX_BODY_BYTES,
+                vec![
+                    jmap_client::email::Property::MessageId,
+                    jmap_client::email::Property::References,
+                    jmap_client::email::Property::ReplyTo,
+                    jmap_client::email::Property::SentAt,
+                    delivered_to_property(),
+                ],
+            )
+            .await
+    } else {
+        backend
+            .get_email(
+                &args.email_id,
+                Some(vec![
+                    jmap_client::email::Property::Id,
+                    jmap_client::email::Property::ThreadId,
+                    jmap_client::email::Property::MessageId,
+                    jmap_client::email::Property::References,
+                    jmap_client::email::Property::From,
+                    jmap_client::email::Property::ReplyTo,
+                    jmap_client::email::Property::To,
+                    jmap_client::email::Property::Cc,
+                    jmap_client::email::Property::Subject,
+                    delivered_to_property(),
+                ]),
+            )
+            .await
+    };
+    let original = match original {
         Ok(v) => v,
         Err(e) => return Envelope::err("reply", account, e),
     };
@@ -745,6 +2454,13 @@ pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value>
         );
     };
 
+    // Resolve sending identity (needed for EmailSubmission and for reply-all self exclusion).
+    let (identity_id, from_name, from_email, identity_out) =
+        match select_identity(&identities, args.identity.as_deref(), Some(&original)) {
+            Ok(v) => v,
+            Err(e) => return Envelope::err("reply", account, e),
+        };
+
     let headers = match build_reply_headers(&original) {
         Ok(h) => h,
         Err(e) => return Envelope::err("reply", account, e),
@@ -761,6 +2477,13 @@ pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value>
         );
     }
 
+    let envelope = match send_at_parameters(&backend, args.send_at.as_deref()) {
+        Ok(hold) => {
+            hold.map(|p| hold_envelope(&from_email, to.iter().chain(&cc).chain(&args.bcc), p))
+        }
+        Err(e) => return Envelope::err("reply", account, e),
+    };
+
     let subject = args
         .subject
         .clone()
@@ -782,23 +2505,59 @@ pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value>
         None => None,
     };
 
-    if text.is_none() && html.is_none() && args.attach.is_empty() {
+    if text.is_none()
+        && html.is_none()
+        && args.attach.is_empty()
+        && args.attach_blob.is_empty()
+        && !args.quote
+    {
         return Envelope::err(
             "reply",
             account,
             XinErrorOut::usage(
-                "missing message content: provide --text, --body-html, or --attach".to_string(),
+                "missing message content: provide --text, --body-html, --attach, --attach-blob or --quote"
+                    .to_string(),
             ),
         );
     }
 
+    let blob_refs = match attachment_blob_refs(&backend, &args.attach_blob).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err("reply", account, e),
+    };
+
+    // Before quoting: only the reply's own images are local files.
+    let (html, inline) = match inline_local_images(html, body_base_dir(args.body_html.as_deref())) {
+        Ok(v) => v,
+        Err(e) => return Envelope::err("reply", account, e),
+    };
+
+    let mut warnings: Vec<String> = Vec::new();
+    let (text, html) = if args.signature {
+        match with_signature(&backend, &identity_id, text, html, &mut warnings).await {
+            Ok(v) => v,
+            Err(e) => return Envelope::err("reply", account, e),
+        }
+    } else {
+        (text, html)
+    };
+    let (text, html) = if args.quote {
+        // An invalid configured zone only matters to query dates; quote in UTC then.
+        let tz = backend.timezone().unwrap_or(chrono_tz::UTC);
+        quoted_reply_bodies(&original, text, html, tz, &mut warnings)
+    } else {
+        (text, html)
+    };
+
     // Merge explicit BCC.
     let bcc = args.bcc.clone();
 
-    let uploaded = match upload_attachments(&backend, &args.attach).await {
+    let rest_size = message_size_estimate(text.as_deref(), html.as_deref(), &blob_refs);
+    let mut uploaded = match upload_attachments(&backend, &args.attach, inline, rest_size).await {
         Ok(v) => v,
         Err(e) => return Envelope::err("reply", account, e),
     };
+    uploaded.extend(blob_refs);
 
     fn is_fastmail_invalid_reply_headers(e: &XinErrorOut) -> bool {
         e.kind == "jmapRequestError"
@@ -867,10 +2626,21 @@ pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value>
         }
     };
 
-    let submission = match backend.submit_email(&email_id, &identity_id).await {
+    let (on_success, after_send) = after_send_plan(
+        backend.after_send(),
+        &mailboxes,
+        Some(&drafts_id),
+        &mut warnings,
+    );
+
+    let submission = match backend
+        .submit_email(&email_id, &identity_id, envelope.as_ref(), &on_success)
+        .await
+    {
         Ok(s) => s,
         Err(e) => return Envelope::err("reply", account, e),
     };
+    let (submission_out, after_send_out) = submission_out(&submission, after_send, &mut warnings);
 
     let uploaded_out = uploaded
         .iter()
@@ -879,23 +2649,234 @@ pub async fn reply(account: Option<String>, args: &ReplyArgs) -> Envelope<Value>
                 "blobId": u.blob_id,
                 "type": u.content_type,
                 "size": u.size,
+                "cid": u.cid,
             })
         })
         .collect::<Vec<_>>();
 
-    let data = json!({
+    let mut data = json!({
         "draft": {
             "emailId": email_id,
             "threadId": email.thread_id()
         },
-        "submission": {
-            "id": submission.id(),
-            "sendAt": to_rfc3339(submission.send_at())
+        "submission": submission_out,
+        "afterSend": after_send_out,
+        "uploaded": uploaded_out,
+        "identity": identity_out
+    });
+    if args.wait_delivery {
+        data["delivery"] =
+            wait_for_delivery(&backend, &submission.id, args.wait_timeout, &mut warnings).await;
+    }
+
+    let meta = Meta {
+        warnings: (!warnings.is_empty()).then_some(warnings),
+        ..Meta::default()
+    };
+
+    Envelope::ok("reply", account, data, meta)
+}
+
+pub async fn forward(account: Option<String>, args: &ForwardArgs) -> Envelope<Value> {
+    let command_name = "forward";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let mailboxes = match backend.list_mailboxes().await {
+        Ok(m) => m,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let drafts_id = match find_drafts_mailbox_id(&mailboxes) {
+        Ok(id) => id,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let identities = match backend.list_identities().await {
+        Ok(i) => i,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let original = match backend
+        .get_email_full(
+            &args.email_id,
+            QUOTE_MAX_BODY_BYTES,
+            vec![
+                jmap_client::email::Property::SentAt,
+                jmap_client::email::Property::BlobId,
+                jmap_client::email::Property::Size,
+                delivered_to_property(),
+            ],
+        )
+        .await
+    {
+        Ok(Some(e)) => e,
+        Ok(None) => {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::usage(format!("email not found: {}", args.email_id)),
+            );
+        }
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let (identity_id, from_name, from_email, identity_out) =
+        match select_identity(&identities, args.identity.as_deref(), Some(&original)) {
+            Ok(v) => v,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+
+    let envelope = match send_at_parameters(&backend, args.send_at.as_deref()) {
+        Ok(hold) => hold.map(|p| {
+            hold_envelope(
+                &from_email,
+                args.to.iter().chain(&args.cc).chain(&args.bcc),
+                p,
+            )
+        }),
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let note_text = match args.text.as_deref().map(read_text_arg).transpose() {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let note_html = match args.body_html.as_deref().map(read_text_arg).transpose() {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let (note_html, inline) =
+        match inline_local_images(note_html, body_base_dir(args.body_html.as_deref())) {
+            Ok(v) => v,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+
+    let mut warnings: Vec<String> = Vec::new();
+    let subject = args
+        .subject
+        .clone()
+        .unwrap_or_else(|| quote::forward_subject(original.subject()));
+
+    let (mode, text, html, forwarded) = if args.as_attachment {
+        let Some(blob_id) = original.blob_id() else {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::config("Email/get did not return the email blobId".to_string()),
+            );
+        };
+        let message = UploadedBlob {
+            blob_id: blob_id.to_string(),
+            content_type: "message/rfc822".to_string(),
+            size: Some(original.size()),
+            name: Some(quote::eml_file_name(original.subject())),
+            cid: None,
+        };
+        // An attachment-only draft gets an empty text body (see `build_email_body`).
+        ("attachment", note_text, note_html, vec![message])
+    } else {
+        let tz = backend.timezone().unwrap_or(chrono_tz::UTC);
+        let (text, html) =
+            forward_inline_bodies(&original, note_text, note_html, tz, &mut warnings);
+        let forwarded = original_attachment_blobs(&original, html.as_deref());
+        ("inline", text, html, forwarded)
+    };
+
+    let rest_size = message_size_estimate(text.as_deref(), html.as_deref(), &forwarded);
+    let uploaded = match upload_attachments(&backend, &args.attach, inline, rest_size).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let attachments: Vec<UploadedBlob> = forwarded.iter().chain(&uploaded).cloned().collect();
+
+    let email = match backend
+        .create_draft_email(
+            &drafts_id,
+            from_name,
+            from_email,
+            &args.to,
+            &args.cc,
+            &args.bcc,
+            Some(&subject),
+            text.as_deref(),
+            html.as_deref(),
+            &attachments,
+        )
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let email_id = match email.id() {
+        Some(id) => id.to_string(),
+        None => {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::config("Email/set did not return email id".to_string()),
+            );
+        }
+    };
+
+    let (on_success, after_send) = after_send_plan(
+        backend.after_send(),
+        &mailboxes,
+        Some(&drafts_id),
+        &mut warnings,
+    );
+
+    let submission = match backend
+        .submit_email(&email_id, &identity_id, envelope.as_ref(), &on_success)
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let (submission_out, after_send_out) = submission_out(&submission, after_send, &mut warnings);
+
+    let uploaded_out = uploaded
+        .iter()
+        .map(|u| {
+            json!({
+                "blobId": u.blob_id,
+                "type": u.content_type,
+                "size": u.size,
+                "cid": u.cid,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut data = json!({
+        "draft": {
+            "emailId": email_id,
+            "threadId": email.thread_id()
         },
-        "uploaded": uploaded_out
+        "submission": submission_out,
+        "afterSend": after_send_out,
+        "uploaded": uploaded_out,
+        "identity": identity_out,
+        "forwarded": {
+            "emailId": args.email_id,
+            "mode": mode,
+            "blobIds": forwarded.iter().map(|b| b.blob_id.as_str()).collect::<Vec<_>>()
+        }
     });
+    if args.wait_delivery {
+        data["delivery"] =
+            wait_for_delivery(&backend, &submission.id, args.wait_timeout, &mut warnings).await;
+    }
+
+    let meta = Meta {
+        warnings: (!warnings.is_empty()).then_some(warnings),
+        ..Meta::default()
+    };
 
-    Envelope::ok("reply", account, data, Meta::default())
+    Envelope::ok(command_name, account, data, meta)
 }
 
 pub async fn drafts_list(account: Option<String>, args: &DraftsListArgs) -> Envelope<Value> {
@@ -924,7 +2905,9 @@ pub async fn drafts_list(account: Option<String>, args: &DraftsListArgs) -> Enve
         oldest: false,
         filter_json: Some(filter_json),
         collapse_threads: Some(false),
-        sort: crate::cli::SortKey::ReceivedAt,
+        sort: Vec::new(),
+        fail_on_drift: false,
+        explain: false,
     };
 
     crate::commands::read::search("drafts.list", account, &search_args).await
@@ -1051,12 +3034,27 @@ pub async fn drafts_create(account: Option<String>, args: &DraftsCreateArgs) ->
         None => None,
     };
 
-    if body_text.is_none() && body_html.is_none() && args.attach.is_empty() {
+    let (body_text, body_html) = match &args.markdown {
+        Some(v) => match read_text_arg(v) {
+            Ok(md) => {
+                let (t, h) = markdown::render(&md);
+                (Some(t), Some(h))
+            }
+            Err(e) => return Envelope::err(command_name, account, e),
+        },
+        None => (body_text, body_html),
+    };
+
+    if body_text.is_none()
+        && body_html.is_none()
+        && args.attach.is_empty()
+        && args.attach_blob.is_empty()
+    {
         return Envelope::err(
             command_name,
             account,
             XinErrorOut::usage(
-                "missing draft content: provide --body/--body-file, --body-html, or --attach"
+                "missing draft content: provide --body/--body-file, --body-html, --markdown, --attach or --attach-blob"
                     .to_string(),
             ),
         );
@@ -1077,16 +3075,40 @@ pub async fn drafts_create(account: Option<String>, args: &DraftsCreateArgs) ->
         Err(e) => return Envelope::err(command_name, account, e),
     };
 
-    let (_identity_id, from_name, from_email) =
+    let (identity_id, from_name, from_email) =
         match resolve_identity(&identities, args.identity.as_deref()) {
             Ok(v) => v,
             Err(e) => return Envelope::err(command_name, account, e),
         };
 
-    let uploaded = match upload_attachments(&backend, &args.attach).await {
+    let (body_html, inline) = match inline_local_images(
+        body_html,
+        body_base_dir(args.body_html.as_deref().or(args.markdown.as_deref())),
+    ) {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let mut warnings: Vec<String> = Vec::new();
+    let (body_text, body_html) = if args.signature {
+        match with_signature(&backend, &identity_id, body_text, body_html, &mut warnings).await {
+            Ok(v) => v,
+            Err(e) => return Envelope::err(command_name, account, e),
+        }
+    } else {
+        (body_text, body_html)
+    };
+
+    let blob_refs = match attachment_blob_refs(&backend, &args.attach_blob).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let rest_size = message_size_estimate(body_text.as_deref(), body_html.as_deref(), &blob_refs);
+    let mut uploaded = match upload_attachments(&backend, &args.attach, inline, rest_size).await {
         Ok(v) => v,
         Err(e) => return Envelope::err(command_name, account, e),
     };
+    uploaded.extend(blob_refs);
 
     let email = match backend
         .create_draft_email(
@@ -1125,6 +3147,7 @@ pub async fn drafts_create(account: Option<String>, args: &DraftsCreateArgs) ->
                 "blobId": u.blob_id,
                 "type": u.content_type,
                 "size": u.size,
+                "cid": u.cid,
             })
         })
         .collect::<Vec<_>>();
@@ -1136,7 +3159,10 @@ pub async fn drafts_create(account: Option<String>, args: &DraftsCreateArgs) ->
             "draft": { "emailId": email_id, "threadId": email.thread_id() },
             "uploaded": uploaded_out
         }),
-        Meta::default(),
+        Meta {
+            warnings: (!warnings.is_empty()).then_some(warnings),
+            ..Meta::default()
+        },
     )
 }
 
@@ -1273,22 +3299,25 @@ pub async fn drafts_rewrite(
         );
     }
 
-    if args.clear_attachments && (!args.attach.is_empty() || args.replace_attachments) {
+    let adds_attachments = !args.attach.is_empty() || !args.attach_blob.is_empty();
+    if args.clear_attachments && (adds_attachments || args.replace_attachments) {
         return Envelope::err(
             command_name,
             account,
             XinErrorOut::usage(
-                "--clear-attachments cannot be combined with --attach/--replace-attachments"
+                "--clear-attachments cannot be combined with --attach/--attach-blob/--replace-attachments"
                     .to_string(),
             ),
         );
     }
 
-    if args.replace_attachments && args.attach.is_empty() {
+    if args.replace_attachments && !adds_attachments {
         return Envelope::err(
             command_name,
             account,
-            XinErrorOut::usage("--replace-attachments requires at least one --attach".to_string()),
+            XinErrorOut::usage(
+                "--replace-attachments requires at least one --attach or --attach-blob".to_string(),
+            ),
         );
     }
 
@@ -1353,8 +3382,9 @@ pub async fn drafts_rewrite(
                 existing_attachments.push(UploadedBlob {
                     blob_id: blob_id.to_string(),
                     content_type: ct.to_string(),
-                    size: p.size(),
+                    size: Some(p.size()),
                     name: p.name().map(|n| n.to_string()),
+                    cid: None,
                 });
             }
         }
@@ -1399,10 +3429,26 @@ pub async fn drafts_rewrite(
     let final_text: Option<String> = body_text.or(existing_text);
     let final_html: Option<String> = body_html.or(existing_html);
 
-    let uploaded_new = match upload_attachments(&backend, &args.attach).await {
+    let blob_refs = match attachment_blob_refs(&backend, &args.attach_blob).await {
         Ok(v) => v,
         Err(e) => return Envelope::err(command_name, account, e),
     };
+    let kept_attachments: &[UploadedBlob] = if args.clear_attachments || args.replace_attachments {
+        &[]
+    } else {
+        &existing_attachments
+    };
+    let rest_size = message_size_estimate(final_text.as_deref(), final_html.as_deref(), &blob_refs)
+        + kept_attachments
+            .iter()
+            .map(|b| encoded_part_size(b.size.unwrap_or(0)))
+            .sum::<usize>();
+    let mut uploaded_new =
+        match upload_attachments(&backend, &args.attach, Vec::new(), rest_size).await {
+            Ok(v) => v,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+    uploaded_new.extend(blob_refs);
 
     let attachments_for_new_draft: Vec<UploadedBlob> = if args.clear_attachments {
         Vec::new()
@@ -1509,6 +3555,7 @@ pub async fn drafts_rewrite(
                 "blobId": u.blob_id,
                 "type": u.content_type,
                 "size": u.size,
+                "cid": u.cid,
             })
         })
         .collect::<Vec<_>>();
@@ -1545,41 +3592,103 @@ pub async fn drafts_send(account: Option<String>, args: &DraftsSendArgs) -> Enve
         Err(e) => return Envelope::err(command_name, account, e),
     };
 
-    let (identity_id, _from_name, _from_email) =
+    let (identity_id, _from_name, from_email) =
         match resolve_identity(&identities, args.identity.as_deref()) {
             Ok(v) => v,
             Err(e) => return Envelope::err(command_name, account, e),
         };
 
-    let submission = match backend
-        .submit_email(&args.draft_email_id, &identity_id)
-        .await
-    {
-        Ok(s) => s,
+    let hold = match send_at_parameters(&backend, args.send_at.as_deref()) {
+        Ok(h) => h,
         Err(e) => return Envelope::err(command_name, account, e),
     };
 
-    let thread_id = backend
+    // Before submitting: with `afterSend: destroy` the draft is gone afterwards.
+    let draft = backend
         .get_email(
             &args.draft_email_id,
             Some(vec![
                 jmap_client::email::Property::Id,
                 jmap_client::email::Property::ThreadId,
+                jmap_client::email::Property::To,
+                jmap_client::email::Property::Cc,
+                jmap_client::email::Property::Bcc,
             ]),
         )
         .await
         .ok()
-        .flatten()
+        .flatten();
+    let thread_id = draft
+        .as_ref()
         .and_then(|e| e.thread_id().map(|s| s.to_string()));
 
+    let envelope = match (hold, &draft) {
+        (None, _) => None,
+        (Some(p), Some(d)) => {
+            let recipients: Vec<String> = [d.to(), d.cc(), d.bcc()]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|a| a.email().to_string())
+                .collect();
+            Some(hold_envelope(&from_email, &recipients, p))
+        }
+        (Some(_), None) => {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::usage(format!("draft not found: {}", args.draft_email_id)),
+            );
+        }
+    };
+
+    let policy = backend.after_send();
+    let mailboxes = if matches!(policy, AfterSend::Move | AfterSend::Copy) {
+        match backend.list_mailboxes().await {
+            Ok(m) => m,
+            Err(e) => return Envelope::err(command_name, account, e),
+        }
+    } else {
+        Vec::new()
+    };
+    let drafts_id = find_drafts_mailbox_id(&mailboxes).ok();
+
+    let mut warnings: Vec<String> = Vec::new();
+    let (on_success, after_send) =
+        after_send_plan(policy, &mailboxes, drafts_id.as_deref(), &mut warnings);
+
+    let submission = match backend
+        .submit_email(
+            &args.draft_email_id,
+            &identity_id,
+            envelope.as_ref(),
+            &on_success,
+        )
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+    let (submission_out, after_send_out) = submission_out(&submission, after_send, &mut warnings);
+
+    let mut data = json!({
+        "draft": { "emailId": args.draft_email_id, "threadId": thread_id },
+        "submission": submission_out,
+        "afterSend": after_send_out
+    });
+    if args.wait_delivery {
+        data["delivery"] =
+            wait_for_delivery(&backend, &submission.id, args.wait_timeout, &mut warnings).await;
+    }
+
     Envelope::ok(
         command_name,
         account,
-        json!({
-            "draft": { "emailId": args.draft_email_id, "threadId": thread_id },
-            "submission": { "id": submission.id(), "sendAt": to_rfc3339(submission.send_at()) }
-        }),
-        Meta::default(),
+        data,
+        Meta {
+            warnings: (!warnings.is_empty()).then_some(warnings),
+            ..Meta::default()
+        },
     )
 }
 
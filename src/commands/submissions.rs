@@ -0,0 +1,408 @@
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+use crate::app_config::AfterSend;
+use crate::backend::{Backend, OnSuccess};
+use crate::cli::{
+    SubmissionsCancelArgs, SubmissionsChangesArgs, SubmissionsGetArgs, SubmissionsListArgs,
+};
+use crate::error::XinErrorOut;
+use crate::output::{Envelope, Meta};
+
+use super::send::{find_drafts_mailbox_id, find_sent_mailbox_id};
+
+pub async fn list(account: Option<String>, args: &SubmissionsListArgs) -> Envelope<Value> {
+    let command_name = "submissions.list";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let filter = args.pending.then(|| json!({"undoStatus": "pending"}));
+    let items = match backend
+        .submissions_list(filter, args.max.unwrap_or(20))
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    Envelope::ok(
+        command_name,
+        account,
+        json!({"items": items}),
+        Meta::default(),
+    )
+}
+
+pub async fn get(account: Option<String>, args: &SubmissionsGetArgs) -> Envelope<Value> {
+    let command_name = "submissions.get";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let (list, not_found) = match backend.submissions_get(&args.submission_ids).await {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    Envelope::ok(
+        command_name,
+        account,
+        json!({"submissions": list, "notFound": not_found}),
+        Meta::default(),
+    )
+}
+
+/// Undo what `afterSend` did when the submission was created, so the email is a draft again.
+fn restore_plan(
+    policy: AfterSend,
+    mailboxes: &[jmap_client::mailbox::Mailbox],
+) -> Result<OnSuccess, XinErrorOut> {
+    let mut patch = serde_json::Map::new();
+    match policy {
+        AfterSend::Destroy | AfterSend::None => return Ok(OnSuccess::Nothing),
+        AfterSend::Keep => {}
+        AfterSend::Move | AfterSend::Copy => {
+            let drafts = find_drafts_mailbox_id(mailboxes)?;
+            if let Some(sent) = find_sent_mailbox_id(mailboxes).filter(|s| *s != drafts) {
+                patch.insert(format!("mailboxIds/{sent}"), Value::Null);
+            }
+            patch.insert(format!("mailboxIds/{drafts}"), json!(true));
+        }
+    }
+    patch.insert("keywords/$draft".to_string(), json!(true));
+    Ok(OnSuccess::Update(Value::Object(patch)))
+}
+
+pub async fn cancel(account: Option<String>, args: &SubmissionsCancelArgs) -> Envelope<Value> {
+    let command_name = "submissions.cancel";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let submission = match backend
+        .submissions_get(std::slice::from_ref(&args.submission_id))
+        .await
+    {
+        Ok((mut list, _)) if !list.is_empty() => list.swap_remove(0),
+        Ok(_) => {
+            return Envelope::err(
+                command_name,
+                account,
+                XinErrorOut::usage(format!("submission not found: {}", args.submission_id)),
+            );
+        }
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let undo_status = submission
+        .get("undoStatus")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    if undo_status != "pending" {
+        return Envelope::err(
+            command_name,
+            account,
+            XinErrorOut::usage(format!(
+                "submission {} is {undo_status}; only pending submissions can be cancelled",
+                args.submission_id
+            )),
+        );
+    }
+
+    let email_id = submission
+        .get("emailId")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let policy = backend.after_send();
+    let mailboxes = if matches!(policy, AfterSend::Move | AfterSend::Copy) {
+        match backend.list_mailboxes().await {
+            Ok(m) => m,
+            Err(e) => return Envelope::err(command_name, account, e),
+        }
+    } else {
+        Vec::new()
+    };
+    let restore = match restore_plan(policy, &mailboxes) {
+        Ok(r) => r,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let email_error = match backend
+        .cancel_submission(&args.submission_id, &email_id, &restore)
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let mut warnings = Vec::new();
+    if let Some(err) = &email_error {
+        warnings.push(format!(
+            "cancelled, but moving the email back to Drafts failed: {}",
+            err.get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or("unknown")
+        ));
+    }
+
+    let restored = !matches!(restore, OnSuccess::Nothing) && email_error.is_none();
+
+    Envelope::ok(
+        command_name,
+        account,
+        json!({
+            "submission": {
+                "id": args.submission_id,
+                "emailId": email_id,
+                "undoStatus": "canceled"
+            },
+            "draft": {
+                "emailId": email_id,
+                "restored": restored,
+                "error": email_error
+            }
+        }),
+        Meta {
+            warnings: (!warnings.is_empty()).then_some(warnings),
+            ..Meta::default()
+        },
+    )
+}
+
+pub async fn changes(account: Option<String>, args: &SubmissionsChangesArgs) -> Envelope<Value> {
+    let command_name = "submissions.changes";
+
+    let backend = match Backend::connect(account.as_deref()).await {
+        Ok(b) => b,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let Some(since) = &args.since else {
+        let state = match backend.object_state("EmailSubmission").await {
+            Ok(s) => s,
+            Err(e) => return Envelope::err(command_name, account, e),
+        };
+        return Envelope::ok(
+            command_name,
+            account,
+            json!({
+                "sinceState": state,
+                "newState": state,
+                "hasMoreChanges": false,
+                "changes": {"created": [], "updated": [], "destroyed": []},
+                "submissions": []
+            }),
+            Meta::default(),
+        );
+    };
+
+    let changes = match backend
+        .object_changes("EmailSubmission", since, args.max)
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => return Envelope::err(command_name, account, e),
+    };
+
+    let ids = |key: &str| -> Vec<String> {
+        changes
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let (created, updated, destroyed) = (ids("created"), ids("updated"), ids("destroyed"));
+
+    let to_get: Vec<String> = created.iter().chain(&updated).cloned().collect();
+    let submissions = if to_get.is_empty() {
+        Vec::new()
+    } else {
+        match backend.submissions_get(&to_get).await {
+            Ok((list, _)) => list,
+            Err(e) => return Envelope::err(command_name, account, e),
+        }
+    };
+
+    Envelope::ok(
+        command_name,
+        account,
+        json!({
+            "sinceState": since,
+            "newState": changes.get("newState"),
+            "hasMoreChanges": changes.get("hasMoreChanges").and_then(|v| v.as_bool()).unwrap_or(false),
+            "changes": {"created": created, "updated": updated, "destroyed": destroyed},
+            "submissions": submissions
+        }),
+        Meta::default(),
+    )
+}
+
+/// Summarize an `EmailSubmission`'s `deliveryStatus` (RFC 8621 §7).
+///
+/// `state` is `pending`, `canceled`, `queued`, `delivered`, `failed`, `partial` or
+/// `unknown` (no per-recipient status, or servers that relay without DSN). It is final
+/// once the submission is released and no recipient is still `queued`.
+pub(crate) fn delivery_summary(submission: &Value) -> Value {
+    let undo_status = submission
+        .get("undoStatus")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    let recipients: Vec<Value> = submission
+        .get("deliveryStatus")
+        .and_then(|v| v.as_object())
+        .map(|m| {
+            m.iter()
+                .map(|(email, status)| {
+                    json!({
+                        "email": email,
+                        "delivered": status.get("delivered"),
+                        "displayed": status.get("displayed"),
+                        "smtpReply": status.get("smtpReply")
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let count = |value: &str| {
+        recipients
+            .iter()
+            .filter(|r| r.get("delivered").and_then(|v| v.as_str()) == Some(value))
+            .count()
+    };
+    let (queued, yes, no) = (count("queued"), count("yes"), count("no"));
+
+    let (state, is_final) = match undo_status {
+        "pending" => ("pending", false),
+        "canceled" => ("canceled", true),
+        _ if queued > 0 => ("queued", false),
+        _ if no > 0 && no == recipients.len() => ("failed", true),
+        _ if no > 0 => ("partial", true),
+        _ if yes > 0 && yes == recipients.len() => ("delivered", true),
+        _ => ("unknown", true),
+    };
+
+    json!({
+        "state": state,
+        "final": is_final,
+        "recipients": recipients,
+        "dsnBlobIds": submission.get("dsnBlobIds").cloned().unwrap_or_else(|| json!([])),
+        "mdnBlobIds": submission.get("mdnBlobIds").cloned().unwrap_or_else(|| json!([]))
+    })
+}
+
+/// `--wait-delivery`: poll `EmailSubmission/get` until the delivery status is final or
+/// `timeout_secs` have passed. The message is already sent, so polling failures only
+/// add a warning (and return `null`).
+pub(crate) async fn wait_for_delivery(
+    backend: &Backend,
+    submission_id: &str,
+    timeout_secs: u64,
+    warnings: &mut Vec<String>,
+) -> Value {
+    let started = Instant::now();
+    let timeout = Duration::from_secs(timeout_secs);
+    let mut interval = Duration::from_secs(1);
+
+    loop {
+        let submission = match backend.submissions_get(&[submission_id.to_string()]).await {
+            Ok((list, _)) => list.into_iter().next(),
+            Err(e) => {
+                warnings.push(format!(
+                    "sent, but polling the delivery status failed: {}",
+                    e.message
+                ));
+                return Value::Null;
+            }
+        };
+        let Some(submission) = submission else {
+            warnings.push(format!(
+                "sent, but submission {submission_id} is gone; no delivery status"
+            ));
+            return Value::Null;
+        };
+
+        let mut delivery = delivery_summary(&submission);
+        let elapsed = started.elapsed();
+        let is_final = delivery.get("final").and_then(|v| v.as_bool()) == Some(true);
+
+        if is_final || elapsed >= timeout {
+            let state = delivery
+                .get("state")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown")
+                .to_string();
+            if !is_final {
+                warnings.push(format!(
+                    "delivery status is still {state} after {timeout_secs}s"
+                ));
+            } else if state == "failed" || state == "partial" {
+                warnings.push(format!(
+                    "delivery {state}: see delivery.recipients and dsnBlobIds"
+                ));
+            }
+            let obj = delivery.as_object_mut().expect("delivery object");
+            obj.insert("timedOut".to_string(), json!(!is_final));
+            obj.insert("waitedSecs".to_string(), json!(elapsed.as_secs()));
+            return delivery;
+        }
+
+        tokio::time::sleep(interval.min(timeout - elapsed)).await;
+        interval = (interval * 2).min(Duration::from_secs(10));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delivery_summary_states() {
+        let sub = |undo: &str, status: Value| {
+            delivery_summary(&json!({"undoStatus": undo, "deliveryStatus": status}))
+        };
+        let rcpt = |delivered: &str| json!({"delivered": delivered, "displayed": "unknown", "smtpReply": "250 OK"});
+
+        assert_eq!(sub("pending", Value::Null)["state"], "pending");
+        assert_eq!(sub("pending", Value::Null)["final"], false);
+        assert_eq!(sub("final", Value::Null)["state"], "unknown");
+        assert_eq!(sub("final", Value::Null)["final"], true);
+        assert_eq!(
+            sub("final", json!({"a@x": rcpt("queued"), "b@x": rcpt("yes")}))["state"],
+            "queued"
+        );
+        assert_eq!(
+            sub("final", json!({"a@x": rcpt("yes"), "b@x": rcpt("yes")}))["state"],
+            "delivered"
+        );
+        assert_eq!(
+            sub("final", json!({"a@x": rcpt("no"), "b@x": rcpt("yes")}))["state"],
+            "partial"
+        );
+        assert_eq!(sub("final", json!({"a@x": rcpt("no")}))["state"], "failed");
+        assert_eq!(
+            sub("final", json!({"a@x": rcpt("yes"), "b@x": rcpt("unknown")}))["state"],
+            "unknown"
+        );
+
+        let out = sub("final", json!({"a@x": rcpt("yes")}));
+        assert_eq!(out["recipients"][0]["email"], "a@x");
+        assert_eq!(out["recipients"][0]["smtpReply"], "250 OK");
+        assert_eq!(out["dsnBlobIds"], json!([]));
+    }
+}
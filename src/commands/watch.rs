@@ -1,14 +1,20 @@
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::PathBuf;
 
 use serde_json::{Value, json};
 
 use crate::backend::Backend;
-use crate::cli::WatchArgs;
+use crate::cli::{ChangeType, WatchArgs, WatchTransport};
 use crate::error::XinErrorOut;
 use crate::output::{Envelope, Meta};
+use crate::push::PushChannel;
+use crate::sugar;
 
-use super::history::{PageToken, decode_page_token, encode_page_token};
+use super::history::{
+    PageToken, TypeChanges, change_type_name, current_states, decode_page_token, encode_page_token,
+    fetch_changes, is_email_only, jmap_data_type, selected_types,
+};
 
 fn read_checkpoint(path: &PathBuf) -> Option<String> {
     let s = std::fs::read_to_string(path).ok()?;
@@ -35,7 +41,7 @@ fn write_checkpoint(path: &PathBuf, token: &str) -> Result<(), XinErrorOut> {
     Ok(())
 }
 
-fn json_line(v: &Value) {
+pub(super) fn json_line(v: &Value) {
     let mut out = std::io::stdout().lock();
     // If serialization fails, we intentionally panic: this is programmer error.
     let s = serde_json::to_string(v).expect("json serialize");
@@ -80,11 +86,151 @@ where
     sleeper(dur).await
 }
 
+enum Wake {
+    Timer,
+    /// A push notification arrived (`None`) or the push connection failed (`Some(err)`).
+    Push(Option<XinErrorOut>),
+    Stop,
+}
+
+/// Ping interval requested from EventSource servers (seconds).
+const EVENTSOURCE_PING_SECS: u64 = 30;
+
+/// Quiet time after which a WebSocket gets a Ping, and the wait for its Pong (seconds).
+const WEBSOCKET_PING_SECS: u64 = 30;
+
+/// Upper bound for the push reconnect backoff.
+const RECONNECT_BACKOFF_MAX_MS: u64 = 5 * 60 * 1000;
+
+/// Delay before the next push reconnect attempt: doubles per consecutive failure.
+fn reconnect_backoff_ms(interval_ms: u64, failures: u32) -> u64 {
+    let factor = 1u64.checked_shl(failures.min(16)).unwrap_or(u64::MAX);
+    interval_ms
+        .max(1000)
+        .saturating_mul(factor)
+        .min(RECONNECT_BACKOFF_MAX_MS)
+}
+
+async fn connect_push(
+    backend: &Backend,
+    transport: WatchTransport,
+    data_types: &[&str],
+) -> Option<PushChannel> {
+    // Connection failures are not fatal: the caller keeps polling.
+    match transport {
+        WatchTransport::Poll => None,
+        WatchTransport::Websocket => backend
+            .connect_websocket_push(data_types, WEBSOCKET_PING_SECS)
+            .await
+            .ok()
+            .flatten()
+            .map(|p| PushChannel::WebSocket(Box::new(p))),
+        WatchTransport::Eventsource => backend
+            .connect_event_source_push(data_types, EVENTSOURCE_PING_SECS)
+            .await
+            .ok()
+            .flatten()
+            .map(|p| PushChannel::EventSource(Box::new(p))),
+    }
+}
+
+fn push_available(backend: &Backend, transport: WatchTransport) -> bool {
+    match transport {
+        WatchTransport::Poll => false,
+        WatchTransport::Websocket => backend.websocket_push_url().is_some(),
+        WatchTransport::Eventsource => backend.event_source_url().is_some(),
+    }
+}
+
+/// The `stopped` event (Ctrl-C).
+fn emit_stopped(plain: bool, emit_json: fn(&Value)) {
+    if plain {
+        plain_line("STOPPED\treason=ctrl_c");
+    } else {
+        emit_json(&json!({"type":"stopped","reason":"ctrl_c"}));
+    }
+}
+
+/// Email keeps its original event shape: `tick` + one `email.change` per id (+ `email.hydrated`).
+fn emit_email_changes(changes: &TypeChanges, plain: bool, emit_json: fn(&Value)) {
+    let new_state = &changes.new_state;
+
+    if plain {
+        plain_line(&format!(
+            "TICK\tsinceState={}\tnewState={}\tcreated={}\tupdated={}\tdestroyed={}\thasMoreChanges={}",
+            changes.since_state,
+            new_state,
+            changes.created.len(),
+            changes.updated.len(),
+            changes.destroyed.len(),
+            changes.has_more
+        ));
+
+        for id in &changes.created {
+            plain_line(&format!("CREATED\t{}\tnewState={}", id, new_state));
+        }
+        for id in &changes.updated {
+            plain_line(&format!("UPDATED\t{}\tnewState={}", id, new_state));
+        }
+        for id in &changes.destroyed {
+            plain_line(&format!("DESTROYED\t{}\tnewState={}", id, new_state));
+        }
+
+        if let Some((created_emails, updated_emails)) = &changes.hydrated {
+            plain_line(&format!(
+                "HYDRATED\tcreated={}\tupdated={}",
+                created_emails.len(),
+                updated_emails.len()
+            ));
+        }
+        return;
+    }
+
+    emit_json(&json!({
+        "type": "tick",
+        "sinceState": changes.since_state,
+        "newState": new_state,
+        "hasMoreChanges": changes.has_more,
+        "counts": {
+            "created": changes.created.len(),
+            "updated": changes.updated.len(),
+            "destroyed": changes.destroyed.len()
+        }
+    }));
+
+    for (change_type, ids) in [
+        ("created", &changes.created),
+        ("updated", &changes.updated),
+        ("destroyed", &changes.destroyed),
+    ] {
+        for id in ids {
+            emit_json(&json!({
+                "type": "email.change",
+                "changeType": change_type,
+                "id": id,
+                "newState": new_state
+            }));
+        }
+    }
+
+    if let Some((created_emails, updated_emails)) = &changes.hydrated {
+        // Optional hydrated summaries (agent can ignore).
+        emit_json(&json!({
+            "type": "email.hydrated",
+            "newState": new_state,
+            "hydrated": {
+                "created": crate::schema::email_summary_items(created_emails),
+                "updated": crate::schema::email_summary_items(updated_emails)
+            }
+        }));
+    }
+}
+
 pub async fn watch(account: Option<String>, args: &WatchArgs, plain: bool) -> Envelope<Value> {
     let command_name = "watch";
 
     // Emitter: JSON (default) or plain text.
-    let emit_json = if args.pretty { pretty_line } else { json_line };
+    let emit_json: fn(&Value) = if args.pretty { pretty_line } else { json_line };
     let emit_plain = |line: &str| plain_line(line);
 
     let emit_error = |e: &XinErrorOut| {
@@ -109,6 +255,18 @@ pub async fn watch(account: Option<String>, args: &WatchArgs, plain: bool) -> En
         }
     };
 
+    // Optional query: only matching created/updated emails are reported.
+    let query_filter = match args.query.as_deref().map(str::trim) {
+        Some(q) if !q.is_empty() => match sugar::compile_search_filter(q, &backend).await {
+            Ok(f) => Some(f),
+            Err(e) => {
+                emit_error(&e);
+                return Envelope::err(command_name, account, e);
+            }
+        },
+        _ => None,
+    };
+
     // Resolve start token priority:
     // 1) --page
     // 2) --checkpoint (if exists)
@@ -125,8 +283,9 @@ pub async fn watch(account: Option<String>, args: &WatchArgs, plain: bool) -> En
     }
 
     let max_changes_default = 100;
+    let requested = (!args.types.is_empty()).then(|| selected_types(&args.types));
 
-    let (mut since_state, used_max) = match &page_token {
+    let (mut states, used_max) = match &page_token {
         Some(token) => {
             let t = match decode_page_token(token) {
                 Ok(t) => t,
@@ -135,185 +294,198 @@ pub async fn watch(account: Option<String>, args: &WatchArgs, plain: bool) -> En
                     return Envelope::err(command_name, account, e);
                 }
             };
-
-            if let Some(max) = args.max {
-                if max != t.max_changes {
-                    let e = XinErrorOut::usage("page token does not match args".to_string());
+            let mut states = match t.states() {
+                Ok(s) => s,
+                Err(e) => {
                     emit_error(&e);
                     return Envelope::err(command_name, account, e);
                 }
+            };
+
+            let mismatch = args.max.is_some_and(|max| max != t.max_changes)
+                || args.since.as_ref().is_some_and(|since| {
+                    states.len() != 1 || states.values().next() != Some(since)
+                });
+            if mismatch {
+                let e = XinErrorOut::usage("page token does not match args".to_string());
+                emit_error(&e);
+                return Envelope::err(command_name, account, e);
             }
 
-            if let Some(since) = &args.since {
-                if since != &t.since_state {
+            let retyped = requested
+                .as_ref()
+                .filter(|types| !states.keys().eq(types.iter()));
+            if let Some(types) = retyped {
+                // An explicit --page must match; a checkpoint follows --types (new types
+                // start from their current state, dropped types are forgotten).
+                if args.page.is_some() {
                     let e = XinErrorOut::usage("page token does not match args".to_string());
                     emit_error(&e);
                     return Envelope::err(command_name, account, e);
                 }
+
+                states.retain(|t, _| types.contains(t));
+                let missing: Vec<ChangeType> = types
+                    .iter()
+                    .copied()
+                    .filter(|t| !states.contains_key(t))
+                    .collect();
+                match current_states(&backend, &missing).await {
+                    Ok(s) => states.extend(s),
+                    Err(e) => {
+                        emit_error(&e);
+                        return Envelope::err(command_name, account, e);
+                    }
+                }
             }
 
-            (t.since_state, t.max_changes)
+            (states, t.max_changes)
         }
         None => {
-            let since = if let Some(s) = &args.since {
-                s.clone()
-            } else {
-                match backend.email_state().await {
+            let types = requested.unwrap_or_else(|| vec![ChangeType::Email]);
+            let states = match &args.since {
+                Some(_) if types.len() > 1 => {
+                    let e = XinErrorOut::usage(
+                        "--since tracks a single type; use --page/--checkpoint to resume multiple --types"
+                            .to_string(),
+                    );
+                    emit_error(&e);
+                    return Envelope::err(command_name, account, e);
+                }
+                Some(s) => BTreeMap::from([(types[0], s.clone())]),
+                None => match current_states(&backend, &types).await {
                     Ok(s) => s,
                     Err(e) => {
                         emit_error(&e);
                         return Envelope::err(command_name, account, e);
                     }
-                }
+                },
             };
 
-            (since, args.max.unwrap_or(max_changes_default))
+            (states, args.max.unwrap_or(max_changes_default))
         }
     };
 
-    if since_state.trim().is_empty() {
+    if states.values().any(|s| s.trim().is_empty()) {
         let e = XinErrorOut::usage("missing --since (or --page/--checkpoint)".to_string());
         emit_error(&e);
         return Envelope::err(command_name, account, e);
     }
 
+    let types: Vec<ChangeType> = states.keys().copied().collect();
+    let type_names: Vec<&str> = types.iter().map(|t| change_type_name(*t)).collect();
+    let data_types: Vec<&str> = types.iter().map(|t| jmap_data_type(*t)).collect();
+
+    // Push transport (optional). When the server does not advertise the requested push
+    // mechanism, or the connection cannot be established, we keep polling.
+    let mut push = connect_push(&backend, args.transport, &data_types).await;
+    let transport_name = push.as_ref().map_or("poll", |p| p.transport_name());
+
+    // Reconnect backoff state (consecutive push failures, earliest next attempt).
+    let mut push_failures: u32 = 0;
+    let mut reconnect_at = std::time::Instant::now();
+
     // Initial ready event (useful for agents).
+    let email_state = states.get(&ChangeType::Email).cloned().unwrap_or_default();
     if plain {
         emit_plain(&format!(
-            "READY\tsinceState={}\tmaxChanges={}",
-            since_state, used_max
+            "READY\tsinceState={}\tmaxChanges={}\ttransport={}\ttypes={}",
+            email_state,
+            used_max,
+            transport_name,
+            type_names.join(",")
         ));
     } else {
-        emit_json(&json!({
+        let mut ready = json!({
             "type": "ready",
-            "sinceState": since_state,
+            "sinceState": email_state,
             "maxChanges": used_max,
-        }));
+            "transport": transport_name,
+            "types": type_names,
+        });
+        if let Some(q) = &args.query {
+            ready
+                .as_object_mut()
+                .expect("ready object")
+                .insert("query".to_string(), json!(q));
+        }
+        if !is_email_only(&types) {
+            ready.as_object_mut().expect("ready object").insert(
+                "sinceStates".to_string(),
+                json!(
+                    states
+                        .iter()
+                        .map(|(t, s)| (change_type_name(*t), s.as_str()))
+                        .collect::<BTreeMap<_, _>>()
+                ),
+            );
+        }
+        emit_json(&ready);
     }
 
     // (no RNG dependency; jitter derived from system time)
 
     loop {
-        let (mut resp, hydrated_created, hydrated_updated) = if args.hydrate {
-            match backend
-                .email_changes_hydrate(&since_state, Some(used_max))
-                .await
-            {
-                Ok((r, created, updated)) => (r, Some(created), Some(updated)),
-                Err(e) => {
-                    emit_error(&e);
-                    return Envelope::err(command_name, account, e);
-                }
-            }
-        } else {
-            match backend.email_changes(&since_state, Some(used_max)).await {
-                Ok(r) => (r, None, None),
-                Err(e) => {
-                    emit_error(&e);
-                    return Envelope::err(command_name, account, e);
-                }
-            }
-        };
+        let mut has_more = false;
 
-        let has_more = resp.has_more_changes();
-        let new_state = resp.take_new_state();
-
-        let created = resp.take_created();
-        let updated = resp.take_updated();
-        let destroyed = resp.take_destroyed();
-
-        // Emit events.
-        if !created.is_empty() || !updated.is_empty() || !destroyed.is_empty() {
-            if plain {
-                emit_plain(&format!(
-                    "TICK\tsinceState={}\tnewState={}\tcreated={}\tupdated={}\tdestroyed={}\thasMoreChanges={}",
-                    since_state,
-                    new_state,
-                    created.len(),
-                    updated.len(),
-                    destroyed.len(),
-                    has_more
-                ));
-
-                for id in &created {
-                    emit_plain(&format!("CREATED\t{}\tnewState={}", id, new_state));
-                }
-                for id in &updated {
-                    emit_plain(&format!("UPDATED\t{}\tnewState={}", id, new_state));
-                }
-                for id in &destroyed {
-                    emit_plain(&format!("DESTROYED\t{}\tnewState={}", id, new_state));
+        for t in &types {
+            let since_state = states[t].clone();
+            let mut changes =
+                match fetch_changes(&backend, *t, &since_state, used_max, args.hydrate).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        emit_error(&e);
+                        return Envelope::err(command_name, account, e);
+                    }
+                };
+
+            has_more |= changes.has_more;
+
+            if let Some(filter) = query_filter.as_ref().filter(|_| *t == ChangeType::Email) {
+                let changed: Vec<String> = changes
+                    .created
+                    .iter()
+                    .chain(&changes.updated)
+                    .cloned()
+                    .collect();
+                match backend.email_ids_matching(filter, &changed).await {
+                    Ok(keep) => changes.retain_ids(&keep),
+                    Err(e) => {
+                        emit_error(&e);
+                        return Envelope::err(command_name, account, e);
+                    }
                 }
+            }
 
-                if let (Some(created_emails), Some(updated_emails)) =
-                    (hydrated_created.as_ref(), hydrated_updated.as_ref())
-                {
+            if !changes.is_empty() {
+                if *t == ChangeType::Email {
+                    emit_email_changes(&changes, plain, emit_json);
+                } else if plain {
                     emit_plain(&format!(
-                        "HYDRATED\tcreated={}\tupdated={}",
-                        created_emails.len(),
-                        updated_emails.len()
+                        "CHANGES\ttype={}\tsinceState={}\tnewState={}\tcreated={}\tupdated={}\tdestroyed={}\thasMoreChanges={}",
+                        change_type_name(*t),
+                        changes.since_state,
+                        changes.new_state,
+                        changes.created.join(","),
+                        changes.updated.join(","),
+                        changes.destroyed.join(","),
+                        changes.has_more
                     ));
-                }
-            } else {
-                emit_json(&json!({
-                    "type": "tick",
-                    "sinceState": since_state,
-                    "newState": new_state,
-                    "hasMoreChanges": has_more,
-                    "counts": {
-                        "created": created.len(),
-                        "updated": updated.len(),
-                        "destroyed": destroyed.len()
-                    }
-                }));
-
-                for id in created {
-                    emit_json(&json!({
-                        "type": "email.change",
-                        "changeType": "created",
-                        "id": id,
-                        "newState": new_state
-                    }));
-                }
-                for id in updated {
-                    emit_json(&json!({
-                        "type": "email.change",
-                        "changeType": "updated",
-                        "id": id,
-                        "newState": new_state
-                    }));
-                }
-                for id in destroyed {
-                    emit_json(&json!({
-                        "type": "email.change",
-                        "changeType": "destroyed",
-                        "id": id,
-                        "newState": new_state
-                    }));
-                }
-
-                if let (Some(created_emails), Some(updated_emails)) =
-                    (hydrated_created, hydrated_updated)
-                {
-                    // Optional hydrated summaries (agent can ignore).
-                    emit_json(&json!({
-                        "type": "email.hydrated",
-                        "newState": new_state,
-                        "hydrated": {
-                            "created": crate::schema::email_summary_items(&created_emails),
-                            "updated": crate::schema::email_summary_items(&updated_emails)
-                        }
-                    }));
+                } else {
+                    let mut ev = changes.to_json();
+                    ev.as_object_mut().expect("changes object").insert(
+                        "type".to_string(),
+                        json!(format!("{}.changes", change_type_name(*t))),
+                    );
+                    emit_json(&ev);
                 }
             }
+
+            states.insert(*t, changes.new_state);
         }
 
         // Advance checkpoint.
-        since_state = new_state.clone();
-        let next_token = encode_page_token(&PageToken {
-            since_state: since_state.clone(),
-            max_changes: used_max,
-        });
+        let next_token = encode_page_token(&PageToken::from_states(&states, used_max));
 
         if let Some(path) = &args.checkpoint {
             if let Err(e) = write_checkpoint(path, &next_token) {
@@ -331,24 +503,98 @@ pub async fn watch(account: Option<String>, args: &WatchArgs, plain: bool) -> En
             continue;
         }
 
-        // Sleep before next poll.
+        // Wait before next poll: a push notification or the poll timer, whichever comes
+        // first, so a silently dead push connection cannot stop the polling.
         let wait = compute_wait_ms(args.interval_ms, args.jitter_ms, now_nanos());
+        let dur = std::time::Duration::from_millis(wait);
 
         // Allow Ctrl-C to exit quickly.
-        let dur = std::time::Duration::from_millis(wait);
-        tokio::select! {
-            _ = sleep_with(dur, tokio::time::sleep) => {},
-            _ = tokio::signal::ctrl_c() => {
+        let known: Vec<(&str, &str)> = types
+            .iter()
+            .map(|t| (jmap_data_type(*t), states[t].as_str()))
+            .collect();
+        let woke = match push.as_mut() {
+            Some(p) => tokio::select! {
+                r = p.wait_for_change(&known) => Wake::Push(r.err()),
+                _ = sleep_with(dur, tokio::time::sleep) => Wake::Timer,
+                _ = tokio::signal::ctrl_c() => Wake::Stop,
+            },
+            None => tokio::select! {
+                _ = sleep_with(dur, tokio::time::sleep) => Wake::Timer,
+                _ = tokio::signal::ctrl_c() => Wake::Stop,
+            },
+        };
+
+        match woke {
+            Wake::Stop => {
+                emit_stopped(plain, emit_json);
+                break;
+            }
+            Wake::Push(None) => {
+                push_failures = 0;
+            }
+            Wake::Push(Some(e)) => {
+                // Connection lost: poll meanwhile, and reconnect with backoff.
+                push = None;
+                push_failures = push_failures.saturating_add(1);
+                reconnect_at = std::time::Instant::now()
+                    + std::time::Duration::from_millis(reconnect_backoff_ms(
+                        args.interval_ms,
+                        push_failures - 1,
+                    ));
                 if plain {
-                    emit_plain("STOPPED\treason=ctrl_c");
+                    emit_plain(&format!("TRANSPORT\ttransport=poll\treason={}", e.message));
                 } else {
-                    emit_json(&json!({"type":"stopped","reason":"ctrl_c"}));
+                    emit_json(&json!({
+                        "type": "transport",
+                        "transport": "poll",
+                        "reason": e.message
+                    }));
+                }
+                let stopped = tokio::select! {
+                    _ = sleep_with(dur, tokio::time::sleep) => false,
+                    _ = tokio::signal::ctrl_c() => true,
+                };
+                if stopped {
+                    emit_stopped(plain, emit_json);
+                    break;
+                }
+            }
+            Wake::Timer => {
+                if push.is_none()
+                    && push_available(&backend, args.transport)
+                    && std::time::Instant::now() >= reconnect_at
+                {
+                    push = connect_push(&backend, args.transport, &data_types).await;
+                    match push.as_ref() {
+                        Some(p) => {
+                            if plain {
+                                emit_plain(&format!("TRANSPORT\ttransport={}", p.transport_name()));
+                            } else {
+                                emit_json(&json!({
+                                    "type": "transport",
+                                    "transport": p.transport_name()
+                                }));
+                            }
+                        }
+                        None => {
+                            push_failures = push_failures.saturating_add(1);
+                            reconnect_at = std::time::Instant::now()
+                                + std::time::Duration::from_millis(reconnect_backoff_ms(
+                                    args.interval_ms,
+                                    push_failures - 1,
+                                ));
+                        }
+                    }
                 }
-                break;
             }
         }
     }
 
+    if let Some(p) = push {
+        p.close().await;
+    }
+
     Envelope::ok(command_name, account, json!({"ok": true}), Meta::default())
 }
 
@@ -356,6 +602,21 @@ pub async fn watch(account: Option<String>, args: &WatchArgs, plain: bool) -> En
 mod tests {
     use super::*;
 
+    #[test]
+    fn reconnect_backoff_doubles_and_is_capped() {
+        assert_eq!(reconnect_backoff_ms(8000, 0), 8000);
+        assert_eq!(reconnect_backoff_ms(8000, 1), 16000);
+        assert_eq!(reconnect_backoff_ms(8000, 2), 32000);
+        assert_eq!(reconnect_backoff_ms(8000, 10), RECONNECT_BACKOFF_MAX_MS);
+        assert_eq!(
+            reconnect_backoff_ms(8000, u32::MAX),
+            RECONNECT_BACKOFF_MAX_MS
+        );
+
+        // Very small poll intervals still back off from at least one second.
+        assert_eq!(reconnect_backoff_ms(10, 0), 1000);
+    }
+
     #[test]
     fn compute_wait_ms_respects_interval_and_jitter_bounds() {
         let interval = 8000;
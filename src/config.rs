@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
 use std::fs;
+use std::sync::OnceLock;
 
 use crate::error::XinErrorOut;
+use chrono_tz::Tz;
 use jmap_client::client::Credentials;
 use std::fmt;
 
@@ -39,6 +42,16 @@ pub struct RuntimeConfig {
 
     /// Optional comma-separated redirect hosts allowlist (Fastmail may redirect session URL).
     pub follow_redirect_hosts: Vec<String>,
+
+    /// Timezone for bare dates in query sugar. Kept unresolved on error: only commands that
+    /// compile dates fail on an invalid setting.
+    pub timezone: Result<Tz, XinErrorOut>,
+
+    /// Saved queries for `@name` (global, overridden by the account's own).
+    pub saved_queries: BTreeMap<String, String>,
+
+    /// What sending does to the sent email.
+    pub after_send: app_config::AfterSend,
 }
 
 impl RuntimeConfig {
@@ -136,6 +149,61 @@ fn parse_auth_from_global_env() -> Result<Option<AuthConfig>, XinErrorOut> {
     Ok(None)
 }
 
+static TIMEZONE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Global `--tz` flag; takes precedence over `XIN_TZ` and the account config.
+pub fn set_timezone_override(tz: Option<String>) {
+    if let Some(tz) = tz {
+        let _ = TIMEZONE_OVERRIDE.set(tz);
+    }
+}
+
+fn parse_timezone(name: &str, source: &str) -> Result<Tz, XinErrorOut> {
+    let msg =
+        format!("invalid timezone '{name}' ({source}); use an IANA name like Asia/Tokyo or UTC");
+    name.trim().parse::<Tz>().map_err(|_| {
+        if source == "--tz" {
+            XinErrorOut::usage(msg)
+        } else {
+            XinErrorOut::config(msg)
+        }
+    })
+}
+
+/// Precedence: `--tz` -> `XIN_TZ` -> account `timezone` -> UTC.
+fn resolve_timezone(acct: Option<&app_config::AccountConfig>) -> Result<Tz, XinErrorOut> {
+    if let Some(tz) = TIMEZONE_OVERRIDE.get() {
+        return parse_timezone(tz, "--tz");
+    }
+    if let Some(tz) = std::env::var("XIN_TZ")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+    {
+        return parse_timezone(&tz, "XIN_TZ");
+    }
+    match acct.and_then(|a| a.timezone.as_deref()) {
+        Some(tz) => parse_timezone(tz, "account config"),
+        None => Ok(Tz::UTC),
+    }
+}
+
+/// Precedence: `XIN_AFTER_SEND` -> account `afterSend` -> move.
+fn resolve_after_send(
+    acct: Option<&app_config::AccountConfig>,
+) -> Result<app_config::AfterSend, XinErrorOut> {
+    if let Some(v) = std::env::var("XIN_AFTER_SEND")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+    {
+        return app_config::AfterSend::parse(&v).ok_or_else(|| {
+            XinErrorOut::config(format!(
+                "invalid XIN_AFTER_SEND '{v}' (expected move, copy, keep, destroy or none)"
+            ))
+        });
+    }
+    Ok(acct.and_then(|a| a.after_send).unwrap_or_default())
+}
+
 fn read_text_file_trimmed(path: &str, what: &str) -> Result<String, XinErrorOut> {
     let text = fs::read_to_string(path)
         .map_err(|e| XinErrorOut::config(format!("failed to read {what} file: {e}")))?;
@@ -308,11 +376,22 @@ pub fn resolve_runtime_config(
         }
     };
 
+    let timezone = resolve_timezone(acct_cfg.as_ref());
+    let after_send = resolve_after_send(acct_cfg.as_ref())?;
+
+    let mut saved_queries = cfg.as_ref().map(|c| c.queries.clone()).unwrap_or_default();
+    if let Some(acct) = acct_cfg.as_ref() {
+        saved_queries.extend(acct.queries.clone());
+    }
+
     Ok(ResolvedRuntimeConfig {
         config: RuntimeConfig {
             base_url,
             auth,
             follow_redirect_hosts,
+            timezone,
+            saved_queries,
+            after_send,
         },
         account: account_name,
     })
@@ -404,4 +483,44 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn timezone_from_env_and_invalid_names() {
+        with_env(
+            &[
+                ("XIN_BASE_URL", Some("https://example.com")),
+                ("XIN_TOKEN", Some("t")),
+                ("XIN_TOKEN_FILE", None),
+                ("XIN_BASIC_USER", None),
+                ("XIN_BASIC_PASS", None),
+                ("XIN_BASIC_PASS_FILE", None),
+                ("XIN_CONFIG_PATH", Some("/nonexistent/xin/config.json")),
+                ("XIN_TZ", Some("Asia/Tokyo")),
+            ],
+            || {
+                let cfg = RuntimeConfig::from_env().expect("config");
+                assert_eq!(cfg.timezone.expect("tz"), chrono_tz::Asia::Tokyo);
+            },
+        );
+
+        with_env(
+            &[
+                ("XIN_BASE_URL", Some("https://example.com")),
+                ("XIN_TOKEN", Some("t")),
+                ("XIN_TOKEN_FILE", None),
+                ("XIN_BASIC_USER", None),
+                ("XIN_BASIC_PASS", None),
+                ("XIN_BASIC_PASS_FILE", None),
+                ("XIN_CONFIG_PATH", Some("/nonexistent/xin/config.json")),
+                ("XIN_TZ", Some("Mars/Olympus")),
+            ],
+            || {
+                // Not fatal for the config itself; only date compilation reports it.
+                let cfg = RuntimeConfig::from_env().expect("config");
+                let err = cfg.timezone.expect_err("invalid tz");
+                assert_eq!(err.kind, "xinConfigError");
+                assert!(err.message.contains("XIN_TZ"));
+            },
+        );
+    }
 }
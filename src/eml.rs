@@ -0,0 +1,208 @@
+//! Header fields of a pre-built RFC 5322 message (`xin send --eml`), used to derive the
+//! SMTP envelope.
+
+use std::io::BufRead;
+
+/// The header section read from the start of a message, through the blank line that ends
+/// it (or the whole message when it has no body), so the body is never read into memory.
+pub fn read_header(mut reader: impl BufRead) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    loop {
+        let start = head.len();
+        if reader.read_until(b'\n', &mut head)? == 0 {
+            return Ok(head);
+        }
+        if matches!(&head[start..], b"\r\n" | b"\n") {
+            return Ok(head);
+        }
+    }
+}
+
+/// The header section as unfolded `(name, value)` pairs, in order. `None` when `raw` has no
+/// header/body separator or no header field.
+pub fn header_fields(raw: &[u8]) -> Option<Vec<(String, String)>> {
+    let end = find(raw, b"\r\n\r\n")
+        .into_iter()
+        .chain(find(raw, b"\n\n"))
+        .min()
+        // A message may be headers only.
+        .unwrap_or(raw.len());
+    let head = String::from_utf8_lossy(&raw[..end]);
+
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            // Folded continuation of the previous field.
+            let (_, value) = fields.last_mut()?;
+            value.push(' ');
+            value.push_str(line.trim());
+            continue;
+        }
+        let (name, value) = line.split_once(':')?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        fields.push((name.to_string(), value.trim().to_string()));
+    }
+    (!fields.is_empty()).then_some(fields)
+}
+
+/// Addresses of every `name` field (case-insensitive), in order.
+pub fn addresses(fields: &[(String, String)], name: &str) -> Vec<String> {
+    fields
+        .iter()
+        .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+        .flat_map(|(_, v)| address_list(v))
+        .collect()
+}
+
+/// The addr-specs of an address list: `"Doe, Jane" <j@x>, k@y (Kay), team: a@z, b@z;`.
+pub fn address_list(value: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    // Text of the current mailbox outside quotes and comments, and its `<...>` part.
+    let mut bare = String::new();
+    let mut angle: Option<String> = None;
+    let mut in_angle = false;
+    let mut in_quote = false;
+    let mut comment_depth = 0usize;
+    let mut chars = value.chars();
+
+    let mut finish = |bare: &mut String, angle: &mut Option<String>| {
+        let addr = match angle.take() {
+            Some(a) => a.trim().to_string(),
+            None => bare.trim().to_string(),
+        };
+        bare.clear();
+        if addr.contains('@') {
+            out.push(addr);
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if in_quote {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_quote = false,
+                _ => {}
+            }
+            continue;
+        }
+        if comment_depth > 0 {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '(' => comment_depth += 1,
+                ')' => comment_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        if in_angle {
+            match c {
+                '>' => in_angle = false,
+                _ => angle.get_or_insert_with(String::new).push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quote = true,
+            '(' => comment_depth = 1,
+            '<' => {
+                in_angle = true;
+                angle = Some(String::new());
+            }
+            // Group name: what came before is a display name, not an address.
+            ':' => bare.clear(),
+            ',' | ';' => finish(&mut bare, &mut angle),
+            _ => bare.push(c),
+        }
+    }
+    finish(&mut bare, &mut angle);
+    out
+}
+
+/// `raw` without any `name` field (case-insensitive), folded continuation lines included.
+/// The other fields and the body are copied byte for byte.
+pub fn remove_field(raw: &[u8], name: &str) -> Vec<u8> {
+    // End of the header section, after the last field's line break.
+    let end = find(raw, b"\r\n\r\n")
+        .map(|i| i + 2)
+        .into_iter()
+        .chain(find(raw, b"\n\n").map(|i| i + 1))
+        .min()
+        .unwrap_or(raw.len());
+
+    let mut out = Vec::with_capacity(raw.len());
+    let mut skip = false;
+    for line in raw[..end].split_inclusive(|&b| b == b'\n') {
+        if !line.starts_with(b" ") && !line.starts_with(b"\t") {
+            skip = line
+                .split(|&b| b == b':')
+                .next()
+                .is_some_and(|n| n.trim_ascii().eq_ignore_ascii_case(name.as_bytes()));
+        }
+        if !skip {
+            out.extend_from_slice(line);
+        }
+    }
+    out.extend_from_slice(&raw[end..]);
+    out
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_folded_headers() {
+        let raw = b"From: Me <me@example.com>\r\nTo: a@example.com,\r\n\t\"Doe, Jane\" <jane@example.com>\r\nSubject: Hi\r\n\r\nBody: not a header\r\n";
+        let fields = header_fields(raw).expect("headers");
+        assert_eq!(fields.len(), 3);
+        assert_eq!(
+            addresses(&fields, "to"),
+            vec!["a@example.com", "jane@example.com"]
+        );
+        assert_eq!(addresses(&fields, "FROM"), vec!["me@example.com"]);
+
+        assert!(header_fields(b"not a message").is_none());
+        assert!(header_fields(b"\r\n\r\nbody").is_none());
+    }
+
+    #[test]
+    fn address_lists_with_groups_and_comments() {
+        assert_eq!(
+            address_list(
+                "k@y.example (Kay, K.), team: a@z.example, \"B\" <b@z.example>;, undisclosed-recipients:;"
+            ),
+            vec!["k@y.example", "a@z.example", "b@z.example"]
+        );
+    }
+
+    #[test]
+    fn reads_only_the_header_section() {
+        let raw = b"From: a@x\r\nTo: b@x\r\n\r\nbody\r\n\r\nmore\r\n";
+        assert_eq!(
+            read_header(&raw[..]).expect("read"),
+            b"From: a@x\r\nTo: b@x\r\n\r\n"
+        );
+        assert_eq!(read_header(&b"To: b@x\n"[..]).expect("read"), b"To: b@x\n");
+    }
+
+    #[test]
+    fn removes_folded_field() {
+        let raw = b"From: a@x\r\nBcc: b@x,\r\n\tc@x\r\nSubject: Hi\r\nbcc: d@x\r\n\r\nBcc: in the body\r\n";
+        assert_eq!(
+            remove_field(raw, "Bcc"),
+            b"From: a@x\r\nSubject: Hi\r\n\r\nBcc: in the body\r\n"
+        );
+        assert_eq!(remove_field(b"To: a@x\nBcc: b@x\n", "Bcc"), b"To: a@x\n");
+    }
+}
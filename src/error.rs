@@ -20,6 +20,27 @@ impl XinErrorOut {
         }
     }
 
+    /// The server does not offer what the command needs (e.g. a missing capability).
+    pub fn not_supported(message: impl Into<String>) -> Self {
+        Self {
+            kind: "xinNotSupported".to_string(),
+            message: message.into(),
+            http: None,
+            jmap: None,
+        }
+    }
+
+    /// Content over a server limit (`maxSizeUpload`, SMTP `SIZE`); the message names the
+    /// limit and the sizes.
+    pub fn too_large(message: impl Into<String>) -> Self {
+        Self {
+            kind: "xinTooLarge".to_string(),
+            message: message.into(),
+            http: None,
+            jmap: None,
+        }
+    }
+
     pub fn config(message: impl Into<String>) -> Self {
         Self {
             kind: "xinConfigError".to_string(),
@@ -0,0 +1,107 @@
+//! Safe local file names for exported attachments.
+
+use std::collections::HashSet;
+
+/// Longest file name we write, in bytes (most filesystems allow 255).
+const MAX_NAME_BYTES: usize = 200;
+
+/// Windows device names, which cannot be used as a file stem.
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A file name that is safe on common filesystems: no path separators, control or
+/// reserved characters, no leading dots, no Windows device names, at most
+/// `MAX_NAME_BYTES` (keeping the extension). Empty names become `fallback`.
+pub fn sanitize(name: &str, fallback: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_whitespace() => ' ',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_start_matches('.')
+        .trim_end_matches(['.', ' '])
+        .to_string();
+    if cleaned.is_empty() {
+        return fallback.to_string();
+    }
+
+    let (stem, ext) = split_ext(&cleaned);
+    let reserved = RESERVED.contains(&stem.to_ascii_uppercase().as_str());
+    let stem = if reserved {
+        format!("_{stem}")
+    } else {
+        stem.to_string()
+    };
+
+    let ext = truncate(ext, 20);
+    let stem = truncate(&stem, MAX_NAME_BYTES - ext.len());
+    format!("{stem}{ext}")
+}
+
+/// `name`, or `name (2)`, `name (3)`... before the extension: the first not in `taken`
+/// (compared case-insensitively, for case-insensitive filesystems).
+pub fn unique(name: &str, taken: &HashSet<String>) -> String {
+    let free = |n: &str| !taken.contains(&n.to_lowercase());
+    if free(name) {
+        return name.to_string();
+    }
+    let (stem, ext) = split_ext(name);
+    (2..)
+        .map(|i| format!("{stem} ({i}){ext}"))
+        .find(|n| free(n))
+        .expect("unbounded")
+}
+
+/// `("report", ".pdf")`; dotfiles and names without a dot have no extension.
+fn split_ext(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(i) if i > 0 => name.split_at(i),
+        _ => (name, ""),
+    }
+}
+
+fn truncate(s: &str, max: usize) -> &str {
+    let mut end = s.len().min(max);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_names() {
+        assert_eq!(sanitize("../../etc/passwd", "a"), "_.._etc_passwd");
+        assert_eq!(sanitize("Invoice: March?.pdf", "a"), "Invoice_ March_.pdf");
+        assert_eq!(sanitize("  spaced \t name .pdf. ", "a"), "spaced name .pdf");
+        assert_eq!(sanitize("con.txt", "a"), "_con.txt");
+        assert_eq!(sanitize("...", "attachment"), "attachment");
+        let long = format!("{}.pdf", "é".repeat(150));
+        let s = sanitize(&long, "a");
+        assert!(s.len() <= MAX_NAME_BYTES && s.ends_with(".pdf"));
+    }
+
+    #[test]
+    fn unique_names() {
+        let taken: HashSet<String> = ["invoice.pdf", "invoice (2).pdf", ".env"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(unique("Invoice.pdf", &taken), "Invoice (3).pdf");
+        assert_eq!(unique("notes.txt", &taken), "notes.txt");
+        assert_eq!(unique(".env", &taken), ".env (2)");
+    }
+}
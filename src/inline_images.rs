@@ -0,0 +1,194 @@
+//! Local `<img src>` references in HTML bodies, embedded as `cid:` inline parts.
+
+use std::collections::HashMap;
+
+/// Byte range of each `<img>` `src` value (without quotes) in `html`.
+fn img_src_ranges(html: &str) -> Vec<(usize, usize)> {
+    let lower = html.to_ascii_lowercase();
+    let mut out = Vec::new();
+    let mut from = 0;
+
+    while let Some(i) = lower[from..].find("<img") {
+        let start = from + i;
+        let Some(len) = lower[start..].find('>') else {
+            break;
+        };
+        let end = start + len;
+        from = end;
+
+        // Only a real `<img` tag, not e.g. `<imgx`.
+        if !lower[start + 4..]
+            .starts_with(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        {
+            continue;
+        }
+
+        let tag = &lower[start..end];
+        let mut search = 0;
+        while let Some(j) = tag[search..].find("src") {
+            let at = search + j;
+            search = at + 3;
+            // Attribute name boundary: `data-src=` is not `src=`.
+            if !tag[..at].ends_with(|c: char| c.is_ascii_whitespace()) {
+                continue;
+            }
+            let rest = tag[at + 3..].trim_start();
+            let Some(rest) = rest.strip_prefix('=') else {
+                continue;
+            };
+            let value = rest.trim_start();
+            let value_at = end - value.len();
+            let (v_start, v_len) = match value.chars().next() {
+                Some(q @ ('"' | '\'')) => match value[1..].find(q) {
+                    Some(n) => (value_at + 1, n),
+                    None => break,
+                },
+                _ => {
+                    let len = match value.find(|c: char| c.is_ascii_whitespace()) {
+                        Some(n) => n,
+                        // `<img src=a.png/>`: the slash closes the tag.
+                        None => value.strip_suffix('/').unwrap_or(value).len(),
+                    };
+                    (value_at, len)
+                }
+            };
+            out.push((v_start, v_start + v_len));
+            break;
+        }
+    }
+    out
+}
+
+/// Whether an `<img src>` points at a local file: no URL scheme (or `file:`), not
+/// protocol-relative.
+pub fn is_local_src(src: &str) -> bool {
+    let src = src.trim();
+    if src.is_empty() || src.starts_with("//") || src.starts_with('#') {
+        return false;
+    }
+    if src.to_ascii_lowercase().starts_with("file:") {
+        return true;
+    }
+    match src.split_once(':') {
+        // Windows drive letters (`C:\...`) are paths, anything else with a scheme is a URL.
+        Some((scheme, _)) => scheme.len() == 1 && scheme.chars().all(|c| c.is_ascii_alphabetic()),
+        None => true,
+    }
+}
+
+/// The local image paths referenced by `<img src>`, in order of first appearance.
+pub fn local_image_srcs(html: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for (a, b) in img_src_ranges(html) {
+        let src = decode_attr(&html[a..b]);
+        if is_local_src(&src) && !out.contains(&src) {
+            out.push(src);
+        }
+    }
+    out
+}
+
+/// Replace each local `src` found in `cids` with `cid:<id>`.
+pub fn rewrite_srcs(html: &str, cids: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut last = 0;
+    for (a, b) in img_src_ranges(html) {
+        if let Some(cid) = cids.get(&decode_attr(&html[a..b])) {
+            out.push_str(&html[last..a]);
+            out.push_str("cid:");
+            out.push_str(cid);
+            last = b;
+        }
+    }
+    out.push_str(&html[last..]);
+    out
+}
+
+/// A file path for a local `src`: `file://` stripped, `%20` decoded.
+pub fn src_path(src: &str) -> String {
+    let s = src.trim();
+    let s = s
+        .strip_prefix("file://")
+        .or_else(|| s.strip_prefix("file:"))
+        .unwrap_or(s);
+    let s = s.split(['?', '#']).next().unwrap_or(s);
+    percent_decode(s)
+}
+
+/// A Content-ID for the `n`th image (`img1.logo.png@xin`).
+pub fn content_id(n: usize, file_name: &str) -> String {
+    let name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("img{n}.{name}@xin")
+}
+
+fn decode_attr(s: &str) -> String {
+    s.replace("&amp;", "&")
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        if let Some(b) = hex {
+            out.push(b);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_and_rewrites_local_images() {
+        let html = r#"<p><img src="./logo.png" alt=x><IMG data-src="a.png" SRC='img/chart%201.png'/><img src="https://x.org/a.png"><img src=cid:y><img src=./logo.png/></p>"#;
+        assert_eq!(
+            local_image_srcs(html),
+            vec!["./logo.png", "img/chart%201.png"]
+        );
+
+        let cids = HashMap::from([
+            ("./logo.png".to_string(), "img1.logo.png@xin".to_string()),
+            (
+                "img/chart%201.png".to_string(),
+                "img2.chart_1.png@xin".to_string(),
+            ),
+        ]);
+        assert_eq!(
+            rewrite_srcs(html, &cids),
+            r#"<p><img src="cid:img1.logo.png@xin" alt=x><IMG data-src="a.png" SRC='cid:img2.chart_1.png@xin'/><img src="https://x.org/a.png"><img src=cid:y><img src=cid:img1.logo.png@xin/></p>"#
+        );
+        assert_eq!(src_path("img/chart%201.png"), "img/chart 1.png");
+        assert_eq!(src_path("file:///tmp/a.png"), "/tmp/a.png");
+    }
+
+    #[test]
+    fn local_src_detection() {
+        assert!(is_local_src("logo.png"));
+        assert!(is_local_src("/abs/logo.png"));
+        assert!(is_local_src("file:///abs/logo.png"));
+        assert!(!is_local_src("data:image/png;base64,AAAA"));
+        assert!(!is_local_src("cid:abc"));
+        assert!(!is_local_src("//cdn.example.com/a.png"));
+        assert_eq!(content_id(2, "my logo.png"), "img2.my_logo.png@xin");
+    }
+}
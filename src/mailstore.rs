@@ -0,0 +1,155 @@
+//! Local mail containers for `xin export`: Maildir file names and flags, mboxrd framing
+//! and `.eml` file names.
+//!
+//! JMAP ids only use `A-Za-z0-9-_` (RFC 8620 §1.2), so they go into file names as-is and
+//! can be read back to skip messages already exported.
+
+use std::io::{self, BufRead, Write};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// JMAP keywords with a Maildir flag, in the (ASCII) order flags are written.
+const MAILDIR_FLAGS: &[(&str, char)] = &[
+    ("$draft", 'D'),
+    ("$flagged", 'F'),
+    ("$forwarded", 'P'),
+    ("$answered", 'R'),
+    ("$seen", 'S'),
+];
+
+/// Maildir flags for an Email `keywords` object; other keywords have no flag.
+pub fn maildir_flags(keywords: Option<&Value>) -> String {
+    let set = |kw: &str| {
+        keywords
+            .and_then(|k| k.get(kw))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    MAILDIR_FLAGS
+        .iter()
+        .filter(|(kw, _)| set(kw))
+        .map(|(_, flag)| *flag)
+        .collect()
+}
+
+/// Maildir base name (`<unix time>.<emailId>.xin`), unique per email.
+pub fn maildir_base(email_id: &str, received: Option<DateTime<Utc>>) -> String {
+    let secs = received.map_or(0, |d| d.timestamp());
+    format!("{secs}.{email_id}.xin")
+}
+
+/// The file name in `cur/`: base name plus `:2,<flags>`.
+pub fn maildir_name(base: &str, flags: &str) -> String {
+    format!("{base}:2,{flags}")
+}
+
+/// The email id of a file written by `maildir_name`.
+pub fn maildir_email_id(file_name: &str) -> Option<&str> {
+    let base = file_name.split(':').next()?;
+    let mut parts = base.split('.');
+    let (_, id, host) = (parts.next()?, parts.next()?, parts.next()?);
+    (host == "xin" && parts.next().is_none()).then_some(id)
+}
+
+/// `.eml` file name: `<receivedAt>-<emailId>.eml`, so a listing sorts by date.
+pub fn eml_name(email_id: &str, received: Option<DateTime<Utc>>) -> String {
+    let stamp = received.map_or_else(
+        || "00000000T000000Z".to_string(),
+        |d| d.format("%Y%m%dT%H%M%SZ").to_string(),
+    );
+    format!("{stamp}-{email_id}.eml")
+}
+
+/// The email id of a file written by `eml_name`.
+pub fn eml_email_id(file_name: &str) -> Option<&str> {
+    let (stamp, id) = file_name.strip_suffix(".eml")?.split_once('-')?;
+    (stamp.len() == 16 && !id.is_empty()).then_some(id)
+}
+
+/// The mbox `From ` separator line (without newline): sender, then the date in asctime
+/// format (UTC). Senders that cannot appear there become `MAILER-DAEMON`.
+pub fn mbox_from_line(sender: Option<&str>, received: Option<DateTime<Utc>>) -> String {
+    let sender = sender
+        .filter(|s| !s.is_empty() && !s.chars().any(|c| c.is_whitespace() || c.is_control()))
+        .unwrap_or("MAILER-DAEMON");
+    let date = received.unwrap_or(DateTime::UNIX_EPOCH);
+    format!("From {sender} {}", date.format("%a %b %e %H:%M:%S %Y"))
+}
+
+/// Copy one message into an mbox in mboxrd form: LF line endings, `>` added to lines
+/// matching `^>*From `, and a blank line after the message. The `From ` separator line is
+/// written by the caller.
+pub fn write_mboxrd(mut message: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if message.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+        let unquoted = line.iter().position(|&b| b != b'>').unwrap_or(line.len());
+        if line[unquoted..].starts_with(b"From ") {
+            out.write_all(b">")?;
+        }
+        out.write_all(&line)?;
+        out.write_all(b"\n")?;
+    }
+    out.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn maildir_names_and_flags() {
+        let keywords = json!({"$seen": true, "$answered": true, "$flagged": true, "custom": true});
+        let flags = maildir_flags(Some(&keywords));
+        assert_eq!(flags, "FRS");
+        assert_eq!(maildir_flags(None), "");
+
+        let received = DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let base = maildir_base("M1-a_b", Some(received));
+        let name = maildir_name(&base, &flags);
+        assert_eq!(name, "1772442000.M1-a_b.xin:2,FRS");
+        assert_eq!(maildir_email_id(&name), Some("M1-a_b"));
+        assert_eq!(maildir_email_id("1772442000.M1.host:2,S"), None);
+
+        let eml = eml_name("M1-a", Some(received));
+        assert_eq!(eml, "20260302T090000Z-M1-a.eml");
+        assert_eq!(eml_email_id(&eml), Some("M1-a"));
+        assert_eq!(eml_email_id("notes.eml"), None);
+    }
+
+    #[test]
+    fn mboxrd_framing() {
+        let received = DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            mbox_from_line(Some("alice@example.com"), Some(received)),
+            "From alice@example.com Mon Mar  2 09:00:00 2026"
+        );
+        assert_eq!(
+            mbox_from_line(Some("a b@example.com"), None),
+            "From MAILER-DAEMON Thu Jan  1 00:00:00 1970"
+        );
+
+        let message = b"Subject: hi\r\n\r\nFrom here\r\n>From there\r\nFromage\r\nend";
+        let mut out = Vec::new();
+        write_mboxrd(&message[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Subject: hi\n\n>From here\n>>From there\nFromage\nend\n\n"
+        );
+    }
+}
@@ -4,13 +4,23 @@ mod cli;
 mod commands;
 mod config;
 mod debug;
+mod eml;
 mod error;
+mod filename;
 mod headers;
+mod inline_images;
 mod jmap;
+mod mailstore;
+mod markdown;
+mod merge;
+mod mime;
 mod output;
 mod plain;
+mod push;
+mod quote;
 mod schema;
 mod sugar;
+mod transfer;
 
 use clap::Parser;
 
@@ -19,11 +29,14 @@ async fn main() {
     let cli = cli::Cli::parse();
 
     crate::debug::set_verbose(cli.verbose);
+    crate::config::set_timezone_override(cli.tz.clone());
 
     let env = commands::dispatch(&cli).await;
 
     // watch is primarily a streaming command; suppress the final envelope for stream-only consumers.
-    let suppress_envelope = matches!(cli.command, crate::cli::Command::Watch(ref args) if args.no_envelope || cli.plain);
+    // Mail merge with --ndjson has already streamed every row as a JSON line.
+    let suppress_envelope = matches!(cli.command, crate::cli::Command::Watch(ref args) if args.no_envelope || cli.plain)
+        || matches!(cli.command, crate::cli::Command::Send(ref args) if args.ndjson);
 
     if !suppress_envelope {
         if cli.plain {
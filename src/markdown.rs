@@ -0,0 +1,381 @@
+//! `--markdown` bodies: one Markdown source rendered to sanitized HTML and a plain-text
+//! alternative.
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd, html};
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH
+}
+
+/// Render Markdown to `(text, html)`.
+pub fn render(markdown: &str) -> (String, String) {
+    (to_text(markdown), to_html(markdown))
+}
+
+/// URL schemes kept in links and images; anything else (`javascript:`, `data:`, ...) is
+/// dropped. Scheme-less (relative, `#fragment`) URLs are kept.
+fn is_safe_url(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return true;
+    };
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !is_scheme {
+        // `:` after a path or query character, e.g. `docs/a:b`.
+        return true;
+    }
+    matches!(
+        scheme.to_ascii_lowercase().as_str(),
+        "http" | "https" | "mailto" | "tel" | "cid"
+    )
+}
+
+/// HTML with raw HTML escaped (it shows as text) and links/images with unsafe URLs removed.
+pub fn to_html(markdown: &str) -> String {
+    let mut events: Vec<Event> = Vec::new();
+    // Per open link/image: whether its tags were dropped.
+    let mut dropped: Vec<bool> = Vec::new();
+
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Html(s) | Event::InlineHtml(s) => events.push(Event::Text(s)),
+            Event::Start(Tag::Link { ref dest_url, .. }) => {
+                let drop = !is_safe_url(dest_url);
+                dropped.push(drop);
+                if !drop {
+                    events.push(event);
+                }
+            }
+            Event::Start(Tag::Image { ref dest_url, .. }) => {
+                let drop = !is_safe_url(dest_url);
+                dropped.push(drop);
+                if drop {
+                    // Keep the alt text.
+                    continue;
+                }
+                events.push(event);
+            }
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                if !dropped.pop().unwrap_or(false) {
+                    events.push(event);
+                }
+            }
+            _ => events.push(event),
+        }
+    }
+
+    let mut out = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+/// Readable plain text: emphasis markers dropped, `inline code` kept, links as
+/// `text (url)`, code blocks indented, tables as aligned `|` columns.
+pub fn to_text(markdown: &str) -> String {
+    let mut w = TextWriter::default();
+    for event in Parser::new_ext(markdown, options()) {
+        w.event(event);
+    }
+    let mut out = w.out.trim_end().to_string();
+    out.push('\n');
+    out
+}
+
+#[derive(Default)]
+struct TextWriter {
+    out: String,
+    /// Line prefixes of the open blocks (`> ` for quotes, indentation for list items/code).
+    prefixes: Vec<String>,
+    /// Marker (`- ` / `1. `) replacing the last prefix on the next line.
+    marker: Option<String>,
+    at_line_start: bool,
+    pending_blank: bool,
+    /// Next number of each open list (`None` for bullets).
+    lists: Vec<Option<u64>>,
+    /// Per open link/image: destination and where its text starts.
+    links: Vec<(String, usize)>,
+    heading_start: usize,
+    table: Option<Table>,
+}
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: Option<String>,
+}
+
+impl TextWriter {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(s) | Event::Html(s) | Event::InlineHtml(s) => self.write(&s),
+            Event::Code(s) => {
+                self.write("`");
+                self.write(&s);
+                self.write("`");
+            }
+            Event::SoftBreak | Event::HardBreak => self.write("\n"),
+            Event::Rule => {
+                self.block_start();
+                self.write("----------");
+                self.block_end();
+            }
+            Event::TaskListMarker(done) => self.write(if done { "[x] " } else { "[ ] " }),
+            Event::FootnoteReference(s) => self.write(&format!("[{s}]")),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.block_start(),
+            Tag::Heading { level, .. } => {
+                self.block_start();
+                self.heading_start = self.out.len();
+                if level > HeadingLevel::H2 {
+                    self.write(&format!("{} ", "#".repeat(level as usize)));
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.block_start();
+                self.prefixes.push("> ".to_string());
+            }
+            Tag::CodeBlock(_) => {
+                self.block_start();
+                self.prefixes.push("    ".to_string());
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block_start();
+                } else {
+                    self.newline();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.newline();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.prefixes.push(" ".repeat(marker.len()));
+                self.marker = Some(marker);
+            }
+            Tag::Table(_) => {
+                self.block_start();
+                self.table = Some(Table::default());
+            }
+            Tag::TableCell => {
+                if let Some(t) = self.table.as_mut() {
+                    t.cell = Some(String::new());
+                }
+            }
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                let at = self.buffer().len();
+                self.links.push((dest_url.to_string(), at));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.block_end(),
+            TagEnd::Heading(level) => {
+                if level <= HeadingLevel::H2 {
+                    let line = &self.out[self.heading_start..];
+                    let width = line.rsplit('\n').next().unwrap_or("").chars().count();
+                    let ch = if level == HeadingLevel::H1 { "=" } else { "-" };
+                    self.write(&format!("\n{}", ch.repeat(width.max(3))));
+                }
+                self.block_end();
+            }
+            TagEnd::BlockQuote(_) | TagEnd::CodeBlock => {
+                self.prefixes.pop();
+                self.block_end();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block_end();
+                }
+            }
+            TagEnd::Item => {
+                self.newline();
+                self.prefixes.pop();
+                self.marker = None;
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(t) = self.table.as_mut() {
+                    let row = std::mem::take(&mut t.row);
+                    t.rows.push(row);
+                }
+            }
+            TagEnd::TableCell => {
+                if let Some(t) = self.table.as_mut() {
+                    let cell = t.cell.take().unwrap_or_default();
+                    t.row.push(cell.trim().to_string());
+                }
+            }
+            TagEnd::Table => {
+                if let Some(t) = self.table.take() {
+                    let lines = table_lines(&t.rows);
+                    self.write(&lines.join("\n"));
+                }
+                self.block_end();
+            }
+            TagEnd::Link | TagEnd::Image => {
+                if let Some((dest, at)) = self.links.pop() {
+                    let text = self.buffer().get(at..).unwrap_or("").to_string();
+                    let shown = dest.strip_prefix("mailto:").unwrap_or(&dest);
+                    if !dest.is_empty() && is_safe_url(&dest) && text.trim() != shown {
+                        self.write(&format!(" ({dest})"));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Where inline text currently goes: the open table cell, or the output.
+    fn buffer(&self) -> &str {
+        match self.table.as_ref().and_then(|t| t.cell.as_deref()) {
+            Some(cell) => cell,
+            None => &self.out,
+        }
+    }
+
+    fn write(&mut self, s: &str) {
+        if let Some(cell) = self.table.as_mut().and_then(|t| t.cell.as_mut()) {
+            cell.push_str(&s.replace('\n', " "));
+            return;
+        }
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+                self.at_line_start = true;
+            }
+            if !line.is_empty() {
+                self.line_prefix();
+                self.out.push_str(line);
+            }
+        }
+    }
+
+    fn line_prefix(&mut self) {
+        if !self.at_line_start {
+            return;
+        }
+        self.at_line_start = false;
+        let n = self.prefixes.len();
+        match self.marker.take() {
+            Some(marker) => {
+                for p in &self.prefixes[..n - 1] {
+                    self.out.push_str(p);
+                }
+                self.out.push_str(&marker);
+            }
+            None => {
+                for p in &self.prefixes {
+                    self.out.push_str(p);
+                }
+            }
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.at_line_start = true;
+    }
+
+    /// Start a block, separated from the previous one by a blank line (`>` inside quotes).
+    fn block_start(&mut self) {
+        self.newline();
+        if self.pending_blank {
+            self.pending_blank = false;
+            let quotes = self.prefixes.iter().filter(|p| p.as_str() == "> ").count();
+            self.out.push_str(&">".repeat(quotes));
+            self.out.push('\n');
+        }
+    }
+
+    fn block_end(&mut self) {
+        self.newline();
+        self.pending_blank = !self.out.is_empty();
+    }
+}
+
+/// `| a | b |` rows padded to the widest cell, with a `|---|` rule under the header.
+fn table_lines(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .filter_map(|r| r.get(c))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let line = |row: &[String]| {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(c, w)| {
+                let cell = row.get(c).map(String::as_str).unwrap_or("");
+                let pad = w - cell.chars().count();
+                format!(" {cell}{} ", " ".repeat(pad))
+            })
+            .collect();
+        format!("|{}|", cells.join("|"))
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (i, row) in rows.iter().enumerate() {
+        lines.push(line(row));
+        if i == 0 {
+            let rule: Vec<String> = widths.iter().map(|w| "-".repeat(w + 2)).collect();
+            lines.push(format!("|{}|", rule.join("|")));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_keeps_code_tables_and_links_and_escapes_raw_html() {
+        let html = to_html(
+            "Run `xin send` <script>alert(1)</script> [docs](https://example.com) [x](javascript:alert(1))\n\n| a | b |\n|---|---|\n| 1 | 2 |\n",
+        );
+        assert!(html.contains("<code>xin send</code>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<a href=\"https://example.com\">docs</a>"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("<table>"));
+        assert!(html.contains("<td>2</td>"));
+    }
+
+    #[test]
+    fn text_is_readable() {
+        let md = "# Title\n\nSee [docs](https://example.com) and <https://x.org>, run `ls -l`.\n\n- one\n- **two**\n  1. nested\n\n> quoted\n> text\n\n```\ncode line\n```\n\n| Name | Qty |\n|------|----:|\n| apple | 3 |\n";
+        assert_eq!(
+            to_text(md),
+            "Title\n=====\n\nSee docs (https://example.com) and https://x.org, run `ls -l`.\n\n- one\n- two\n  1. nested\n\n> quoted\n> text\n\n    code line\n\n| Name  | Qty |\n|-------|-----|\n| apple | 3   |\n"
+        );
+    }
+}
@@ -0,0 +1,438 @@
+//! Mail merge (`xin send --template ... --data ...`): template parsing, `{{var}}`
+//! substitution and the CSV / JSON Lines row readers.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+use crate::error::XinErrorOut;
+use crate::{markdown, quote};
+
+/// One data row: column name → value.
+pub type Row = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+/// A message template: `To:` / `Cc:` / `Bcc:` / `Subject:` / `Format:` header lines, a
+/// blank line, then the body.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub to: String,
+    pub cc: Option<String>,
+    pub bcc: Option<String>,
+    pub subject: String,
+    pub format: BodyFormat,
+    pub body: String,
+}
+
+/// A template rendered for one row.
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub text: Option<String>,
+    pub html: Option<String>,
+}
+
+pub fn parse_template(src: &str) -> Result<Template, XinErrorOut> {
+    let src = src.strip_prefix('\u{feff}').unwrap_or(src);
+    let (head, body) = match src.find("\n\n").or_else(|| src.find("\r\n\r\n")) {
+        Some(i) => (&src[..i], src[i..].trim_start_matches(['\r', '\n'])),
+        None => (src, ""),
+    };
+
+    let (mut to, mut cc, mut bcc, mut subject, mut format) = (None, None, None, None, None);
+    for (n, line) in head.lines().enumerate() {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(XinErrorOut::usage(format!(
+                "template line {}: expected `Header: value` (headers end at the first blank line)",
+                n + 1
+            )));
+        };
+        let value = value.trim().to_string();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "to" => to = Some(value),
+            "cc" => cc = Some(value),
+            "bcc" => bcc = Some(value),
+            "subject" => subject = Some(value),
+            "format" => {
+                format = Some(match value.to_ascii_lowercase().as_str() {
+                    "text" => BodyFormat::Text,
+                    "markdown" | "md" => BodyFormat::Markdown,
+                    "html" => BodyFormat::Html,
+                    other => {
+                        return Err(XinErrorOut::usage(format!(
+                            "template Format: expected text, markdown or html, got {other}"
+                        )));
+                    }
+                })
+            }
+            other => {
+                return Err(XinErrorOut::usage(format!(
+                    "template line {}: unknown header {other} (use To, Cc, Bcc, Subject, Format)",
+                    n + 1
+                )));
+            }
+        }
+    }
+
+    let to = to
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| XinErrorOut::usage("template is missing a To: header".to_string()))?;
+    let subject = subject
+        .ok_or_else(|| XinErrorOut::usage("template is missing a Subject: header".to_string()))?;
+
+    Ok(Template {
+        to,
+        cc: cc.filter(|v| !v.is_empty()),
+        bcc: bcc.filter(|v| !v.is_empty()),
+        subject,
+        format: format.unwrap_or(BodyFormat::Text),
+        body: body.to_string(),
+    })
+}
+
+/// Replace every `{{ name }}` with the row's value; values are HTML-escaped when `html`.
+/// A name the row does not have is an error (an empty value is fine).
+pub fn substitute(s: &str, row: &Row, html: bool) -> Result<String, XinErrorOut> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            return Err(XinErrorOut::usage("unclosed `{{` in template".to_string()));
+        };
+        let name = rest[start + 2..start + end].trim();
+        let value = row
+            .get(name)
+            .ok_or_else(|| XinErrorOut::usage(format!("missing variable: {name}")))?;
+        if html {
+            out.push_str(&quote::escape_html(value));
+        } else {
+            out.push_str(value);
+        }
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+pub fn render(t: &Template, row: &Row) -> Result<Rendered, XinErrorOut> {
+    // Split the template value first so a display name with a comma stays one address; a
+    // bare `{{var}}` may still expand to a list.
+    let addresses = |v: Option<&String>| -> Result<Vec<String>, XinErrorOut> {
+        let mut out = Vec::new();
+        for part in split_addresses(v.map(String::as_str).unwrap_or("")) {
+            let value = substitute(&part, row, false)?;
+            if part.contains('<') {
+                out.push(value.trim().to_string());
+            } else {
+                out.extend(split_addresses(&value));
+            }
+        }
+        Ok(out)
+    };
+    let to = addresses(Some(&t.to))?;
+    if to.is_empty() {
+        return Err(XinErrorOut::usage(
+            "no recipients: To: is empty".to_string(),
+        ));
+    }
+
+    let subject = substitute(&t.subject, row, false)?
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (text, html) = match t.format {
+        BodyFormat::Text => (Some(substitute(&t.body, row, false)?), None),
+        BodyFormat::Markdown => {
+            let (text, html) = markdown::render(&substitute(&t.body, row, false)?);
+            (Some(text), Some(html))
+        }
+        BodyFormat::Html => (None, Some(substitute(&t.body, row, true)?)),
+    };
+
+    Ok(Rendered {
+        to,
+        cc: addresses(t.cc.as_ref())?,
+        bcc: addresses(t.bcc.as_ref())?,
+        subject,
+        text,
+        html,
+    })
+}
+
+/// Split a header value on commas outside quotes and `<...>`.
+pub fn split_addresses(s: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let (mut quoted, mut angle) = (false, false);
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' | ';' if !quoted && !angle => {
+                out.push(std::mem::take(&mut cur));
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(c);
+    }
+    out.push(cur);
+    out.into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+/// RFC 4180 CSV with a header row (quoted fields, `""` escapes, CRLF or LF).
+pub fn parse_csv(src: &str) -> Result<Vec<Row>, XinErrorOut> {
+    let src = src.strip_prefix('\u{feff}').unwrap_or(src);
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(XinErrorOut::usage(
+            "CSV: unterminated quoted field".to_string(),
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    // Blank lines carry no row.
+    records.retain(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+    let mut records = records.into_iter();
+    let header: Vec<String> = records
+        .next()
+        .ok_or_else(|| XinErrorOut::usage("CSV: missing header row".to_string()))?
+        .into_iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    records
+        .enumerate()
+        .map(|(i, r)| {
+            if r.len() != header.len() {
+                return Err(XinErrorOut::usage(format!(
+                    "CSV row {}: expected {} fields, got {}",
+                    i + 1,
+                    header.len(),
+                    r.len()
+                )));
+            }
+            Ok(header.iter().cloned().zip(r).collect())
+        })
+        .collect()
+}
+
+/// JSON Lines: one object per line; scalars become strings, `null` an empty string.
+pub fn parse_jsonl(src: &str) -> Result<Vec<Row>, XinErrorOut> {
+    src.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            let v: Value = serde_json::from_str(line)
+                .map_err(|e| XinErrorOut::usage(format!("JSONL line {}: {e}", n + 1)))?;
+            let Value::Object(obj) = v else {
+                return Err(XinErrorOut::usage(format!(
+                    "JSONL line {}: expected an object",
+                    n + 1
+                )));
+            };
+            Ok(obj
+                .into_iter()
+                .map(|(k, v)| {
+                    let s = match v {
+                        Value::String(s) => s,
+                        Value::Null => String::new(),
+                        other => other.to_string(),
+                    };
+                    (k, s)
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// `--data @rows.csv|@rows.jsonl|@rows.ndjson` by extension.
+pub fn parse_rows(path: &str, src: &str) -> Result<Vec<Row>, XinErrorOut> {
+    let lower = path.to_ascii_lowercase();
+    if lower.ends_with(".csv") {
+        parse_csv(src)
+    } else if lower.ends_with(".jsonl") || lower.ends_with(".ndjson") {
+        parse_jsonl(src)
+    } else {
+        Err(XinErrorOut::usage(format!(
+            "--data: unsupported file type {path} (use .csv, .jsonl or .ndjson)"
+        )))
+    }
+}
+
+/// `--rows 1,3,5-8` (1-based data rows); every row must exist in data with `total` rows.
+pub fn parse_row_selection(s: &str, total: usize) -> Result<BTreeSet<usize>, XinErrorOut> {
+    let bad = || XinErrorOut::usage(format!("--rows: expected e.g. 1,3,5-8, got {s}"));
+    let mut rows = BTreeSet::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (a, b) = part.split_once('-').unwrap_or((part, part));
+        let a: usize = a.trim().parse().map_err(|_| bad())?;
+        let b: usize = b.trim().parse().map_err(|_| bad())?;
+        if a == 0 || b < a {
+            return Err(bad());
+        }
+        if b > total {
+            return Err(XinErrorOut::usage(format!(
+                "--rows: row {b} is out of range (the data has {total} rows)"
+            )));
+        }
+        rows.extend(a..=b);
+    }
+    if rows.is_empty() {
+        return Err(bad());
+    }
+    Ok(rows)
+}
+
+/// Compact `--rows` value for the given row numbers (`1,3,5-8`).
+pub fn format_row_selection(rows: &[usize]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < rows.len() {
+        let start = rows[i];
+        let mut end = start;
+        while i + 1 < rows.len() && rows[i + 1] == end + 1 {
+            i += 1;
+            end = rows[i];
+        }
+        parts.push(if start == end {
+            start.to_string()
+        } else {
+            format!("{start}-{end}")
+        });
+        i += 1;
+    }
+    parts.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pairs: &[(&str, &str)]) -> Row {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_template_per_row() {
+        let t = parse_template(
+            "To: {{name}} <{{email}}>\nSubject: Invoice {{ id }}\nFormat: html\n\n<p>Hi {{name}}</p>\n",
+        )
+        .expect("template");
+        let r = render(
+            &t,
+            &row(&[
+                ("name", "Doe, A & B"),
+                ("email", "ab@example.com"),
+                ("id", "7"),
+            ]),
+        )
+        .expect("render");
+        assert_eq!(r.to, vec!["Doe, A & B <ab@example.com>"]);
+        assert_eq!(r.subject, "Invoice 7");
+        assert_eq!(r.html.as_deref(), Some("<p>Hi Doe, A &amp; B</p>\n"));
+        assert_eq!(r.text, None);
+
+        let err = render(&t, &row(&[("email", "x@example.com")])).expect_err("missing");
+        assert_eq!(err.message, "missing variable: name");
+    }
+
+    #[test]
+    fn template_requires_to_and_subject() {
+        assert!(parse_template("Subject: hi\n\nbody").is_err());
+        assert!(parse_template("To: a@example.com\n\nbody").is_err());
+        assert!(parse_template("To: a@example.com\nX-Foo: 1\nSubject: s\n\nbody").is_err());
+    }
+
+    #[test]
+    fn parses_csv_with_quotes() {
+        let rows = parse_csv(
+            "email,note\r\na@example.com,\"hello, \"\"you\"\"\nline two\"\r\n\r\nb@example.com,\n",
+        )
+        .expect("csv");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["note"], "hello, \"you\"\nline two");
+        assert_eq!(rows[1]["note"], "");
+
+        assert!(parse_csv("a,b\n1\n").is_err());
+    }
+
+    #[test]
+    fn parses_jsonl() {
+        let rows =
+            parse_jsonl("{\"email\":\"a@example.com\",\"n\":3,\"x\":null}\n\n").expect("jsonl");
+        assert_eq!(rows[0]["n"], "3");
+        assert_eq!(rows[0]["x"], "");
+        assert!(parse_jsonl("[1]").is_err());
+    }
+
+    #[test]
+    fn row_selection_round_trips() {
+        let rows = parse_row_selection("1, 3,5-8", 8).expect("rows");
+        let list: Vec<usize> = rows.into_iter().collect();
+        assert_eq!(list, vec![1, 3, 5, 6, 7, 8]);
+        assert_eq!(format_row_selection(&list), "1,3,5-8");
+        assert!(parse_row_selection("0", 8).is_err());
+        assert!(parse_row_selection("4-2", 8).is_err());
+
+        // A huge range is rejected before it is expanded.
+        let err = parse_row_selection("1-18446744073709551615", 8).expect_err("range");
+        assert!(err.message.contains("out of range (the data has 8 rows)"));
+    }
+
+    #[test]
+    fn splits_addresses_outside_quotes() {
+        assert_eq!(
+            split_addresses("\"Doe, Jane\" <j@example.com>, b@example.com;"),
+            vec!["\"Doe, Jane\" <j@example.com>", "b@example.com"]
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Attachment content types (extension table and magic-byte sniffing) and the
+//! `--attach path;type=...;name=...` syntax.
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::XinErrorOut;
+
+/// Content type for a file: its extension, else its leading bytes, else
+/// `application/octet-stream`.
+pub fn content_type(path: &str, bytes: &[u8]) -> &'static str {
+    extension_type(path)
+        .or_else(|| sniff(bytes))
+        .unwrap_or("application/octet-stream")
+}
+
+/// Content type by file extension (case-insensitive).
+pub fn extension_type(path: &str) -> Option<&'static str> {
+    let ext = std::path::Path::new(path)
+        .extension()?
+        .to_str()?
+        .to_ascii_lowercase();
+    let ty = match ext.as_str() {
+        // Text
+        "txt" | "text" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "tsv" => "text/tab-separated-values",
+        "ics" | "ifb" => "text/calendar",
+        "vcf" | "vcard" => "text/vcard",
+        "xml" => "application/xml",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "js" | "mjs" => "text/javascript",
+        "rtf" => "application/rtf",
+        // Mail
+        "eml" => "message/rfc822",
+        "mbox" => "application/mbox",
+        // Documents
+        "pdf" => "application/pdf",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "odp" => "application/vnd.oasis.opendocument.presentation",
+        "epub" => "application/epub+zip",
+        // Images
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/vnd.microsoft.icon",
+        "tif" | "tiff" => "image/tiff",
+        "heic" => "image/heic",
+        "avif" => "image/avif",
+        // Audio / video
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "flac" => "audio/flac",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        // Archives
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "bz2" => "application/x-bzip2",
+        "xz" => "application/x-xz",
+        "7z" => "application/x-7z-compressed",
+        "rar" => "application/vnd.rar",
+        "zst" => "application/zstd",
+        // Keys and certificates
+        "pgp" | "gpg" => "application/pgp-encrypted",
+        "asc" | "sig" => "application/pgp-signature",
+        "p7s" => "application/pkcs7-signature",
+        "pem" | "crt" | "cer" => "application/x-x509-ca-cert",
+        // Fonts
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// Content type from the leading bytes, for files without a known extension.
+pub fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"%PDF-", "application/pdf"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"II*\0", "image/tiff"),
+        (b"MM\0*", "image/tiff"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"PK\x05\x06", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\0", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (
+            b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+            "application/x-ole-storage",
+        ),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"{\\rtf", "application/rtf"),
+        (b"BEGIN:VCALENDAR", "text/calendar"),
+        (b"BEGIN:VCARD", "text/vcard"),
+        (b"-----BEGIN PGP MESSAGE", "application/pgp-encrypted"),
+        (b"-----BEGIN PGP SIGNATURE", "application/pgp-signature"),
+    ];
+    if let Some((_, ty)) = MAGIC.iter().find(|(m, _)| bytes.starts_with(m)) {
+        return Some(ty);
+    }
+
+    // `BM` alone also starts plain text: require a plausible file size (at least the two
+    // headers) and the zero reserved fields of the bitmap file header.
+    let bmp_size = bytes
+        .get(2..6)
+        .map(|s| u32::from_le_bytes([s[0], s[1], s[2], s[3]]));
+    if bytes.starts_with(b"BM")
+        && bmp_size.is_some_and(|size| size >= 26)
+        && bytes.get(6..10) == Some(&[0; 4][..])
+    {
+        return Some("image/bmp");
+    }
+
+    // RIFF and ISO-BMFF containers carry their type a few bytes in.
+    match (bytes.get(..4), bytes.get(8..12)) {
+        (Some(b"RIFF"), Some(b"WEBP")) => return Some("image/webp"),
+        (Some(b"RIFF"), Some(b"WAVE")) => return Some("audio/wav"),
+        (Some(b"RIFF"), Some(b"AVI ")) => return Some("video/x-msvideo"),
+        _ => {}
+    }
+    if bytes.get(4..8) == Some(b"ftyp") {
+        return match bytes.get(8..12) {
+            Some(b"heic" | b"heix") => Some("image/heic"),
+            Some(b"avif") => Some("image/avif"),
+            Some(b"qt  ") => Some("video/quicktime"),
+            Some(b"M4A ") => Some("audio/mp4"),
+            _ => Some("video/mp4"),
+        };
+    }
+
+    if looks_like_message(bytes) {
+        return Some("message/rfc822");
+    }
+    // UTF-8 without NULs; a character cut off at the end of a file head still counts.
+    let head = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let utf8 = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    (utf8 && !head.is_empty() && !head.contains(&0)).then_some("text/plain")
+}
+
+/// How many leading bytes `sniff` looks at.
+pub const SNIFF_LEN: usize = 4096;
+
+/// The size of the file at `path` and its first `SNIFF_LEN` bytes, enough for
+/// `content_type` without reading the whole file.
+pub fn file_head(path: &Path) -> std::io::Result<(u64, Vec<u8>)> {
+    let file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+    Ok((size, head))
+}
+
+/// An RFC 5322 message: starts with one of the usual header fields.
+fn looks_like_message(bytes: &[u8]) -> bool {
+    const FIELDS: &[&str] = &[
+        "return-path:",
+        "received:",
+        "from:",
+        "date:",
+        "message-id:",
+        "mime-version:",
+        "delivered-to:",
+    ];
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(64)]).to_ascii_lowercase();
+    FIELDS.iter().any(|f| head.starts_with(f))
+        && (bytes.windows(4).any(|w| w == b"\r\n\r\n") || bytes.windows(2).any(|w| w == b"\n\n"))
+}
+
+/// One `--attach` value: `path[;type=<content-type>][;name=<file name>]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachSpec {
+    pub path: String,
+    pub content_type: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Parse `--attach`. Only trailing `;type=` / `;name=` segments are options, so paths
+/// containing `;` still work.
+pub fn parse_attach_spec(value: &str) -> Result<AttachSpec, XinErrorOut> {
+    let mut path = value;
+    let mut content_type: Option<String> = None;
+    let mut name: Option<String> = None;
+
+    while let Some((rest, option)) = path.rsplit_once(';') {
+        let Some((key, val)) = option.split_once('=') else {
+            break;
+        };
+        let slot = match key.trim().to_ascii_lowercase().as_str() {
+            "type" => &mut content_type,
+            "name" => &mut name,
+            _ => break,
+        };
+        let val = val.trim();
+        if val.is_empty() {
+            return Err(XinErrorOut::usage(format!(
+                "--attach {value}: empty {} option",
+                key.trim()
+            )));
+        }
+        if slot.is_some() {
+            return Err(XinErrorOut::usage(format!(
+                "--attach {value}: {} given twice",
+                key.trim()
+            )));
+        }
+        *slot = Some(val.to_string());
+        path = rest;
+    }
+
+    if let Some(ty) = &content_type {
+        let valid = ty.split_once('/').is_some_and(|(a, b)| {
+            !a.is_empty() && !b.is_empty() && !ty.contains(char::is_whitespace)
+        });
+        if !valid {
+            return Err(XinErrorOut::usage(format!(
+                "--attach {value}: invalid type {ty:?} (expected e.g. text/calendar)"
+            )));
+        }
+    }
+    if path.is_empty() {
+        return Err(XinErrorOut::usage(format!(
+            "--attach {value}: missing file path"
+        )));
+    }
+
+    Ok(AttachSpec {
+        path: path.to_string(),
+        content_type,
+        name,
+    })
+}
+
+/// Bytes a part of `n` bytes takes in the message once base64-encoded (76-column lines).
+pub fn base64_size(n: usize) -> usize {
+    let encoded = n.div_ceil(3) * 4;
+    encoded + 2 * encoded.div_ceil(76)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_then_magic_bytes() {
+        assert_eq!(
+            content_type("report.DOCX", b"PK\x03\x04"),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(content_type("invite.ics", b""), "text/calendar");
+        assert_eq!(content_type("scan", b"%PDF-1.7\n"), "application/pdf");
+        assert_eq!(
+            content_type("photo.bin", b"RIFF\0\0\0\0WEBPVP8 "),
+            "image/webp"
+        );
+        assert_eq!(
+            content_type("fwd", b"From: a@example.com\r\nSubject: x\r\n\r\nhi"),
+            "message/rfc822"
+        );
+        assert_eq!(content_type("notes", b"plain words\n"), "text/plain");
+        assert_eq!(
+            content_type("pic", b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0\x28\0"),
+            "image/bmp"
+        );
+        assert_eq!(content_type("memo", b"BMW service notes\n"), "text/plain");
+        assert_eq!(
+            content_type("cut", "caf\u{e9}".as_bytes().split_last().unwrap().1),
+            "text/plain"
+        );
+        assert_eq!(
+            content_type("blob", b"\0\x01\x02"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn attach_spec_options() {
+        assert_eq!(
+            parse_attach_spec("./a;b.dat;type=text/calendar;name=Invite.ics").ok(),
+            Some(AttachSpec {
+                path: "./a;b.dat".to_string(),
+                content_type: Some("text/calendar".to_string()),
+                name: Some("Invite.ics".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_attach_spec("a.pdf")
+                .ok()
+                .map(|s| (s.path, s.content_type, s.name)),
+            Some(("a.pdf".to_string(), None, None))
+        );
+        assert!(parse_attach_spec("a.pdf;type=pdf").is_err());
+        assert!(parse_attach_spec(";name=x").is_err());
+        assert!(parse_attach_spec("a;name=x;name=y").is_err());
+    }
+
+    #[test]
+    fn base64_overhead() {
+        assert_eq!(base64_size(0), 0);
+        assert_eq!(base64_size(57), 78);
+        assert_eq!(base64_size(3 * 1024), 4096 + 2 * 54);
+    }
+}
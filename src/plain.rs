@@ -117,6 +117,34 @@ fn render_labels_list(env: &Envelope<Value>) -> String {
     lines.join("\n")
 }
 
+fn render_queries_list(env: &Envelope<Value>) -> String {
+    let Some(items) = env
+        .data
+        .as_ref()
+        .and_then(|d| d.pointer("/queries"))
+        .and_then(|x| x.as_array())
+    else {
+        return plain_ok_fallback(env);
+    };
+
+    items
+        .iter()
+        .map(|q| {
+            let field = |k: &str| q.get(k).and_then(|x| x.as_str()).unwrap_or("");
+            let scope = match q.get("account").and_then(|x| x.as_str()) {
+                Some(a) => format!("account:{a}"),
+                None => "global".to_string(),
+            };
+            tsv(&[
+                format!("@{}", field("name")),
+                scope,
+                sanitize_field(field("query")),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn render_thread_attachments(env: &Envelope<Value>) -> String {
     let data = match env.data.as_ref() {
         Some(d) => d,
@@ -169,6 +197,36 @@ fn render_history(env: &Envelope<Value>) -> String {
         None => return plain_ok_fallback(env),
     };
 
+    // Multi-type history (--types): one line per type.
+    if let Some(types) = data.get("types").and_then(|t| t.as_array()) {
+        let next_page = env.meta.next_page.clone().unwrap_or_default();
+        let mut lines: Vec<String> = types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .map(|t| {
+                let c = data
+                    .pointer(&format!("/changes/{t}"))
+                    .unwrap_or(&Value::Null);
+                let count = |k: &str| c.get(k).and_then(|x| x.as_array()).map_or(0, |a| a.len());
+                tsv(&[
+                    t.to_string(),
+                    format!(
+                        "{} -> {}",
+                        get_str(data, &format!("/sinceStates/{t}")).unwrap_or(""),
+                        get_str(data, &format!("/newStates/{t}")).unwrap_or("")
+                    ),
+                    format!("created={}", count("created")),
+                    format!("updated={}", count("updated")),
+                    format!("destroyed={}", count("destroyed")),
+                ])
+            })
+            .collect();
+        if !next_page.is_empty() {
+            lines.push(format!("nextPage={next_page}"));
+        }
+        return lines.join("\n");
+    }
+
     let since = get_str(data, "/sinceState").unwrap_or("");
     let new = get_str(data, "/newState").unwrap_or("");
 
@@ -312,6 +370,17 @@ pub fn render(env: &Envelope<Value>) -> String {
     }
 
     match env.command.as_str() {
+        "search" | "messages.search" | "inbox.next"
+            if env
+                .data
+                .as_ref()
+                .is_some_and(|d| d.get("explain").is_some()) =>
+        {
+            // `--explain` has no tabular form; print the explanation as JSON.
+            let explain = env.data.as_ref().and_then(|d| d.get("explain"));
+            serde_json::to_string_pretty(&explain).unwrap_or_default()
+        }
+
         "search" | "messages.search" | "drafts.list" => {
             if let Some(data) = env.data.as_ref() {
                 if let Some(items) = data.pointer("/items").and_then(|x| x.as_array()) {
@@ -323,6 +392,8 @@ pub fn render(env: &Envelope<Value>) -> String {
 
         "labels.list" | "mailboxes.list" => render_labels_list(env),
 
+        "queries.list" => render_queries_list(env),
+
         "thread.attachments" => render_thread_attachments(env),
 
         "attachment" => render_attachment(env),
@@ -0,0 +1,508 @@
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+use crate::error::XinErrorOut;
+
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// JMAP WebSocket push (RFC 8887).
+///
+/// We only use the push half of the protocol: after `WebSocketPushEnable`, the server sends
+/// `StateChange` objects whenever a subscribed data type changes. Requests still go over HTTP,
+/// so the `watch` loop keeps using `Email/changes` for the actual change sets.
+///
+/// A socket that goes quiet for the ping interval gets a Ping; if nothing (not even the Pong)
+/// arrives within another interval, the connection is treated as dead.
+pub struct WebSocketPush {
+    ws: WsStream,
+    account_id: String,
+    ping_interval: std::time::Duration,
+    /// When the last frame arrived (or the connection opened).
+    last_seen: tokio::time::Instant,
+    /// When the unanswered Ping was sent, if any.
+    ping_sent: Option<tokio::time::Instant>,
+}
+
+impl WebSocketPush {
+    pub async fn connect(
+        url: &str,
+        headers: &reqwest::header::HeaderMap,
+        account_id: &str,
+        data_types: &[&str],
+        ping_secs: u64,
+    ) -> Result<Self, XinErrorOut> {
+        let mut request = url.into_client_request().map_err(|e| XinErrorOut {
+            kind: "httpError".to_string(),
+            message: format!("invalid websocket url: {e}"),
+            http: None,
+            jmap: None,
+        })?;
+
+        // Reuse the HTTP client's headers (Authorization etc.) for the upgrade request.
+        for (name, value) in headers {
+            if let (Ok(n), Ok(v)) = (
+                tokio_tungstenite::tungstenite::http::HeaderName::from_bytes(
+                    name.as_str().as_bytes(),
+                ),
+                tokio_tungstenite::tungstenite::http::HeaderValue::from_bytes(value.as_bytes()),
+            ) {
+                request.headers_mut().insert(n, v);
+            }
+        }
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            tokio_tungstenite::tungstenite::http::HeaderValue::from_static("jmap"),
+        );
+
+        let (mut ws, _resp) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("websocket connect failed: {e}"),
+                http: None,
+                jmap: None,
+            })?;
+
+        let enable = json!({
+            "@type": "WebSocketPushEnable",
+            "dataTypes": data_types,
+        });
+
+        ws.send(Message::text(enable.to_string()))
+            .await
+            .map_err(|e| XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("websocket push enable failed: {e}"),
+                http: None,
+                jmap: None,
+            })?;
+
+        Ok(Self {
+            ws,
+            account_id: account_id.to_string(),
+            ping_interval: std::time::Duration::from_secs(ping_secs.max(1)),
+            last_seen: tokio::time::Instant::now(),
+            ping_sent: None,
+        })
+    }
+
+    /// Wait until the server reports a state that differs from the known one for any of the
+    /// `(data_type, since_state)` pairs.
+    ///
+    /// Returns the changed data type. Returns an error when the connection is closed, broken
+    /// or unresponsive; the caller is expected to fall back to polling (and reconnect later).
+    /// The ping state is kept across calls, so a caller may drop this future (e.g. to poll on
+    /// a timer) without resetting it.
+    pub async fn wait_for_change(&mut self, since: &[(&str, &str)]) -> Result<String, XinErrorOut> {
+        loop {
+            let deadline = self.ping_sent.unwrap_or(self.last_seen) + self.ping_interval;
+            let next = match tokio::time::timeout_at(deadline, self.ws.next()).await {
+                Ok(next) => next,
+                Err(_) if self.ping_sent.is_some() => {
+                    return Err(XinErrorOut {
+                        kind: "httpError".to_string(),
+                        message: "websocket ping timeout".to_string(),
+                        http: None,
+                        jmap: None,
+                    });
+                }
+                Err(_) => {
+                    self.ws
+                        .send(Message::Ping(Vec::new()))
+                        .await
+                        .map_err(|e| XinErrorOut {
+                            kind: "httpError".to_string(),
+                            message: format!("websocket ping failed: {e}"),
+                            http: None,
+                            jmap: None,
+                        })?;
+                    self.ping_sent = Some(tokio::time::Instant::now());
+                    continue;
+                }
+            };
+
+            let msg = match next {
+                Some(Ok(m)) => m,
+                Some(Err(e)) => {
+                    return Err(XinErrorOut {
+                        kind: "httpError".to_string(),
+                        message: format!("websocket read failed: {e}"),
+                        http: None,
+                        jmap: None,
+                    });
+                }
+                None => {
+                    return Err(XinErrorOut {
+                        kind: "httpError".to_string(),
+                        message: "websocket closed".to_string(),
+                        http: None,
+                        jmap: None,
+                    });
+                }
+            };
+
+            // Any frame shows the connection is alive.
+            self.last_seen = tokio::time::Instant::now();
+            self.ping_sent = None;
+
+            if msg.is_close() {
+                return Err(XinErrorOut {
+                    kind: "httpError".to_string(),
+                    message: "websocket closed by server".to_string(),
+                    http: None,
+                    jmap: None,
+                });
+            }
+
+            // Pings are answered by tungstenite itself; ignore Pongs and binary frames.
+            let Ok(text) = msg.to_text() else {
+                continue;
+            };
+            if text.is_empty() {
+                continue;
+            }
+
+            let Ok(v) = serde_json::from_str::<Value>(text) else {
+                continue;
+            };
+
+            if let Some(data_type) = changed_type(&v, &self.account_id, since) {
+                return Ok(data_type);
+            }
+        }
+    }
+
+    pub async fn close(mut self) {
+        let _ = self.ws.close(None).await;
+    }
+}
+
+/// JMAP push over EventSource (RFC 8620 §7.3).
+///
+/// The server keeps a `text/event-stream` response open and sends `state` events carrying a
+/// `StateChange` object, plus periodic `ping` events. If nothing (not even a ping) arrives within
+/// the ping timeout, the connection is treated as dead.
+pub struct EventSourcePush {
+    resp: reqwest::Response,
+    parser: SseParser,
+    account_id: String,
+    ping_timeout: std::time::Duration,
+    /// When the last chunk arrived (or the stream opened).
+    last_seen: tokio::time::Instant,
+}
+
+impl EventSourcePush {
+    pub async fn connect(
+        url_template: &str,
+        headers: &reqwest::header::HeaderMap,
+        account_id: &str,
+        data_types: &[&str],
+        ping_secs: u64,
+    ) -> Result<Self, XinErrorOut> {
+        let url = expand_event_source_url(url_template, &data_types.join(","), ping_secs);
+
+        // No overall timeout: the response body is a long-lived stream.
+        let http = reqwest::Client::builder()
+            .default_headers(headers.clone())
+            .connect_timeout(std::time::Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("eventsource client build failed: {e}"),
+                http: None,
+                jmap: None,
+            })?;
+
+        let resp = http
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("eventsource connect failed: {e}"),
+                http: None,
+                jmap: None,
+            })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(XinErrorOut {
+                kind: "httpError".to_string(),
+                message: format!("eventsource connect failed: HTTP {status}"),
+                http: Some(json!({"status": status.as_u16()})),
+                jmap: None,
+            });
+        }
+
+        Ok(Self {
+            resp,
+            parser: SseParser::default(),
+            account_id: account_id.to_string(),
+            // Allow a couple of missed pings before giving up on the connection.
+            ping_timeout: std::time::Duration::from_secs(ping_secs.max(1) * 3),
+            last_seen: tokio::time::Instant::now(),
+        })
+    }
+
+    /// Same contract as [`WebSocketPush::wait_for_change`].
+    pub async fn wait_for_change(&mut self, since: &[(&str, &str)]) -> Result<String, XinErrorOut> {
+        loop {
+            let deadline = self.last_seen + self.ping_timeout;
+            let chunk = match tokio::time::timeout_at(deadline, self.resp.chunk()).await {
+                Err(_) => {
+                    return Err(XinErrorOut {
+                        kind: "httpError".to_string(),
+                        message: "eventsource ping timeout".to_string(),
+                        http: None,
+                        jmap: None,
+                    });
+                }
+                Ok(Err(e)) => {
+                    return Err(XinErrorOut {
+                        kind: "httpError".to_string(),
+                        message: format!("eventsource read failed: {e}"),
+                        http: None,
+                        jmap: None,
+                    });
+                }
+                Ok(Ok(None)) => {
+                    return Err(XinErrorOut {
+                        kind: "httpError".to_string(),
+                        message: "eventsource closed".to_string(),
+                        http: None,
+                        jmap: None,
+                    });
+                }
+                Ok(Ok(Some(c))) => c,
+            };
+            self.last_seen = tokio::time::Instant::now();
+
+            for ev in self.parser.feed(&chunk) {
+                // `ping` events only keep the connection alive (they reset the timeout above).
+                if ev.event != "state" && ev.event != "message" {
+                    continue;
+                }
+                let Ok(v) = serde_json::from_str::<Value>(&ev.data) else {
+                    continue;
+                };
+                if let Some(data_type) = changed_type(&v, &self.account_id, since) {
+                    return Ok(data_type);
+                }
+            }
+        }
+    }
+}
+
+/// Expand the session's `eventSourceUrl` URI template (RFC 8620 §7.3).
+///
+/// We never ask the server to close after a state event; `watch` keeps one stream open.
+pub fn expand_event_source_url(template: &str, types: &str, ping_secs: u64) -> String {
+    template
+        .replace("{types}", types)
+        .replace("{closeafter}", "no")
+        .replace("{ping}", &ping_secs.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type; `message` when the server omits the `event:` field.
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental `text/event-stream` parser (WHATWG HTML §9.2.6, the subset JMAP needs).
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buf: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed raw bytes; returns the events completed by this chunk.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        self.buf.extend_from_slice(bytes);
+
+        let mut out = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let raw: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&raw[..raw.len() - 1]);
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+
+            if line.is_empty() {
+                // Blank line dispatches the pending event.
+                let event = self.event.take();
+                let data = std::mem::take(&mut self.data);
+                if !data.is_empty() || event.is_some() {
+                    out.push(SseEvent {
+                        event: event.unwrap_or_else(|| "message".to_string()),
+                        data: data.join("\n"),
+                    });
+                }
+                continue;
+            }
+
+            if line.starts_with(':') {
+                // Comment (often used as a keep-alive).
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((f, v)) => (f, v.strip_prefix(' ').unwrap_or(v)),
+                None => (line, ""),
+            };
+
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        out
+    }
+}
+
+/// A connected push channel, whichever transport it uses.
+pub enum PushChannel {
+    WebSocket(Box<WebSocketPush>),
+    EventSource(Box<EventSourcePush>),
+}
+
+impl PushChannel {
+    pub fn transport_name(&self) -> &'static str {
+        match self {
+            PushChannel::WebSocket(_) => "websocket",
+            PushChannel::EventSource(_) => "eventsource",
+        }
+    }
+
+    pub async fn wait_for_change(&mut self, since: &[(&str, &str)]) -> Result<String, XinErrorOut> {
+        match self {
+            PushChannel::WebSocket(p) => p.wait_for_change(since).await,
+            PushChannel::EventSource(p) => p.wait_for_change(since).await,
+        }
+    }
+
+    pub async fn close(self) {
+        match self {
+            PushChannel::WebSocket(p) => (*p).close().await,
+            // Dropping the response closes the HTTP stream.
+            PushChannel::EventSource(_) => {}
+        }
+    }
+}
+
+/// Extract the new state for (`account_id`, `data_type`) from a JMAP `StateChange` object.
+///
+/// RFC 8620 §7.1: `{"@type":"StateChange","changed":{"<accountId>":{"<Type>":"<state>"}}}`
+pub fn state_change_for(v: &Value, account_id: &str, data_type: &str) -> Option<String> {
+    if v.get("@type").and_then(|t| t.as_str()) != Some("StateChange") {
+        return None;
+    }
+
+    v.get("changed")
+        .and_then(|c| c.get(account_id))
+        .and_then(|a| a.get(data_type))
+        .and_then(|s| s.as_str())
+        .map(|s| s.to_string())
+}
+
+/// First data type in `since` whose pushed state differs from the known one.
+fn changed_type(v: &Value, account_id: &str, since: &[(&str, &str)]) -> Option<String> {
+    since.iter().find_map(|(data_type, since_state)| {
+        state_change_for(v, account_id, data_type)
+            .filter(|state| state != since_state)
+            .map(|_| data_type.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_change_for_extracts_account_and_type() {
+        let v = json!({
+            "@type": "StateChange",
+            "changed": {
+                "A": {"Email": "S2", "Mailbox": "M9"},
+                "B": {"Email": "X1"}
+            }
+        });
+
+        assert_eq!(state_change_for(&v, "A", "Email"), Some("S2".to_string()));
+        assert_eq!(state_change_for(&v, "A", "Mailbox"), Some("M9".to_string()));
+        assert_eq!(state_change_for(&v, "A", "Thread"), None);
+        assert_eq!(state_change_for(&v, "C", "Email"), None);
+
+        let not_push = json!({"@type": "Response", "methodResponses": []});
+        assert_eq!(state_change_for(&not_push, "A", "Email"), None);
+    }
+
+    #[test]
+    fn changed_type_ignores_known_states() {
+        let v = json!({
+            "@type": "StateChange",
+            "changed": {"A": {"Email": "S1", "Mailbox": "M2"}}
+        });
+
+        assert_eq!(changed_type(&v, "A", &[("Email", "S1")]), None);
+        assert_eq!(
+            changed_type(&v, "A", &[("Email", "S1"), ("Mailbox", "M1")]),
+            Some("Mailbox".to_string())
+        );
+        assert_eq!(changed_type(&v, "A", &[("Thread", "T1")]), None);
+    }
+
+    #[test]
+    fn sse_parser_handles_split_chunks_ping_and_comments() {
+        let mut p = SseParser::default();
+
+        let evs =
+            p.feed(b": keep-alive\r\nevent: ping\r\ndata: {\"interval\":30}\r\n\r\nevent: st");
+        assert_eq!(
+            evs,
+            vec![SseEvent {
+                event: "ping".to_string(),
+                data: "{\"interval\":30}".to_string()
+            }]
+        );
+
+        let evs = p.feed(b"ate\ndata: {\"a\":\ndata: 1}\n\ndata: plain\n\n");
+        assert_eq!(
+            evs,
+            vec![
+                SseEvent {
+                    event: "state".to_string(),
+                    data: "{\"a\":\n1}".to_string()
+                },
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "plain".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_event_source_url_fills_template() {
+        assert_eq!(
+            expand_event_source_url(
+                "https://x/es?types={types}&closeafter={closeafter}&ping={ping}",
+                "Email,Mailbox",
+                30
+            ),
+            "https://x/es?types=Email,Mailbox&closeafter=no&ping=30"
+        );
+        assert_eq!(
+            expand_event_source_url("https://x/es", "Email", 30),
+            "https://x/es"
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+/// Who/when line above a quoted reply: `On Tue, 3 Mar 2026 09:00 UTC, Alice <a@x> wrote:`,
+/// with the date in `tz`.
+pub fn attribution(sent_at: Option<i64>, from: Option<&str>, tz: Tz) -> String {
+    let who = from.unwrap_or("someone");
+    match sent_at.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)) {
+        Some(dt) => format!("On {}, {who} wrote:", format_date(dt, tz)),
+        None => format!("{who} wrote:"),
+    }
+}
+
+/// `Tue, 3 Mar 2026 18:05 JST`: the zone abbreviation follows the local time.
+pub fn format_date(dt: DateTime<Utc>, tz: Tz) -> String {
+    dt.with_timezone(&tz)
+        .format("%a, %-d %b %Y %H:%M %Z")
+        .to_string()
+}
+
+/// `Name <email>` or just `email`.
+pub fn format_address(name: Option<&str>, email: &str) -> String {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(n) => format!("{n} <{email}>"),
+        None => email.to_string(),
+    }
+}
+
+/// Prefix every line with `> ` (`>` for empty and already-quoted lines, so nesting stays `>>`).
+pub fn quote_text(text: &str) -> String {
+    text.trim_end()
+        .lines()
+        .map(|line| {
+            if line.is_empty() || line.starts_with('>') {
+                format!(">{line}")
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reply text: the caller's text, then the attribution and the quoted original.
+pub fn quoted_reply_text(reply: Option<&str>, attribution: &str, original: &str) -> String {
+    let quoted = format!("{attribution}\n{}\n", quote_text(original));
+    match reply.map(str::trim_end).filter(|r| !r.is_empty()) {
+        Some(r) => format!("{r}\n\n{quoted}"),
+        None => quoted,
+    }
+}
+
+/// Reply HTML: the caller's HTML, then the attribution and a `blockquote type="cite"`.
+pub fn quoted_reply_html(reply: Option<&str>, attribution: &str, original_html: &str) -> String {
+    format!(
+        "{}<div>{}</div>\n<blockquote type=\"cite\" style=\"margin:0 0 0 .8ex;border-left:1px solid #ccc;padding-left:1ex\">\n{}\n</blockquote>\n",
+        reply.map(|r| format!("{r}\n<br>\n")).unwrap_or_default(),
+        escape_html(attribution),
+        body_html(original_html)
+    )
+}
+
+/// The part of a full HTML document that can be nested in another body: the `<body>`
+/// content (or what follows `</head>`), without `<style>` / `<script>` elements.
+pub fn body_html(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let after_tag = |open: &str| {
+        let start = lower.find(open)?;
+        Some(start + lower[start..].find('>')? + 1)
+    };
+    let start = after_tag("<body")
+        .or_else(|| after_tag("</head"))
+        .unwrap_or(0);
+    let end = ["</body", "</html"]
+        .iter()
+        .filter_map(|close| lower[start..].find(close))
+        .min()
+        .map_or(html.len(), |i| start + i);
+
+    let mut out = String::with_capacity(end - start);
+    let mut pos = start;
+    while let Some((open, name)) = ["style", "script"]
+        .iter()
+        .filter_map(|name| Some((pos + lower[pos..end].find(&format!("<{name}"))?, *name)))
+        .min()
+    {
+        out.push_str(&html[pos..open]);
+        pos = lower[open..end]
+            .find(&format!("</{name}"))
+            .and_then(|c| Some(open + c + lower[open + c..end].find('>')? + 1))
+            .unwrap_or(end);
+    }
+    out.push_str(&html[pos..end]);
+    out.trim().to_string()
+}
+
+/// Header block of an inline forward, as `(label, value)` pairs.
+pub fn forward_header_lines(
+    from: Option<&str>,
+    sent_at: Option<i64>,
+    subject: Option<&str>,
+    to: &[String],
+    cc: &[String],
+    tz: Tz,
+) -> Vec<(&'static str, String)> {
+    let mut lines = vec![("From", from.unwrap_or("").to_string())];
+    if let Some(dt) = sent_at.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)) {
+        lines.push(("Date", format_date(dt, tz)));
+    }
+    lines.push(("Subject", subject.unwrap_or("").to_string()));
+    if !to.is_empty() {
+        lines.push(("To", to.join(", ")));
+    }
+    if !cc.is_empty() {
+        lines.push(("Cc", cc.join(", ")));
+    }
+    lines
+}
+
+const FORWARD_MARKER: &str = "---------- Forwarded message ---------";
+
+pub fn forward_text(note: Option<&str>, headers: &[(&str, String)], original: &str) -> String {
+    let mut out = String::new();
+    if let Some(n) = note.map(str::trim_end).filter(|n| !n.is_empty()) {
+        out.push_str(n);
+        out.push_str("\n\n");
+    }
+    out.push_str(FORWARD_MARKER);
+    out.push('\n');
+    for (label, value) in headers {
+        out.push_str(&format!("{label}: {value}\n"));
+    }
+    out.push('\n');
+    out.push_str(original.trim_end());
+    out.push('\n');
+    out
+}
+
+pub fn forward_html(note: Option<&str>, headers: &[(&str, String)], original_html: &str) -> String {
+    let header_html = headers
+        .iter()
+        .map(|(label, value)| format!("{label}: {}", escape_html(value)))
+        .collect::<Vec<_>>()
+        .join("<br>\n");
+    format!(
+        "{}<div>{FORWARD_MARKER}<br>\n{header_html}</div>\n<br>\n{}\n",
+        note.map(|n| format!("{n}\n<br>\n")).unwrap_or_default(),
+        body_html(original_html)
+    )
+}
+
+/// Plain text shown as HTML (for quoting a text-only original into an HTML body).
+pub fn text_to_html(text: &str) -> String {
+    format!(
+        "<div style=\"white-space:pre-wrap\">{}</div>",
+        escape_html(text.trim_end())
+    )
+}
+
+pub fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Rough HTML → text for quoting an HTML-only original: drops tags (and `<style>`/`<script>`
+/// content), turns block ends into newlines and decodes the common entities.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        if !tag.starts_with('/') && (name == "style" || name == "script") {
+            let close = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(i) => &rest[i..],
+                None => "",
+            };
+            continue;
+        }
+        if matches!(
+            name,
+            "br" | "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+        ) && (name == "br" || tag.starts_with('/'))
+        {
+            out.push('\n');
+        }
+    }
+    out.push_str(rest);
+
+    let decoded = out
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    // Collapse runs of blank lines left behind by nested blocks.
+    let mut lines: Vec<&str> = Vec::new();
+    for line in decoded.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// `Fwd: <subject>` unless it is already a forward.
+pub fn forward_subject(original: Option<&str>) -> String {
+    let s = original.unwrap_or("").trim();
+    let lower = s.to_lowercase();
+    if lower.starts_with("fwd:") || lower.starts_with("fw:") {
+        s.to_string()
+    } else if s.is_empty() {
+        "Fwd:".to_string()
+    } else {
+        format!("Fwd: {s}")
+    }
+}
+
+/// File name for a forwarded `message/rfc822` attachment.
+pub fn eml_file_name(subject: Option<&str>) -> String {
+    let base: String = subject
+        .unwrap_or("")
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .chars()
+        .take(80)
+        .collect();
+    if base.is_empty() {
+        "forwarded.eml".to_string()
+    } else {
+        format!("{base}.eml")
+    }
+}
+
+/// Append an identity signature after the `-- ` separator (RFC 3676 §4.3).
+///
+/// Each existing body gets its own kind of signature, or the other one converted; with no
+/// body at all, a text body holding just the signature is created. Returns `None` when both
+/// signatures are empty.
+pub fn append_signature(
+    text: Option<String>,
+    html: Option<String>,
+    text_signature: &str,
+    html_signature: &str,
+) -> Option<(Option<String>, Option<String>)> {
+    let text_sig = Some(text_signature.trim_end().to_string())
+        .filter(|s| !s.trim().is_empty())
+        .or_else(|| Some(html_to_text(html_signature)).filter(|s| !s.is_empty()))?;
+    let html_sig = Some(html_signature.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| text_to_html(&text_sig));
+
+    let text = match (text, &html) {
+        (Some(t), _) => Some(format!("{}\n\n-- \n{text_sig}\n", t.trim_end())),
+        (None, None) => Some(format!("-- \n{text_sig}\n")),
+        (None, Some(_)) => None,
+    };
+    let html = html.map(|h| {
+        format!(
+            "{}\n<br>\n<div class=\"signature\">-- <br>\n{html_sig}</div>\n",
+            h.trim_end()
+        )
+    });
+    Some((text, html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_text_and_nests_existing_quotes() {
+        assert_eq!(quote_text("hi\n\n> old\nbye\n"), "> hi\n>\n>> old\n> bye");
+
+        let out = quoted_reply_text(Some("Thanks!"), "Alice wrote:", "hi");
+        assert_eq!(out, "Thanks!\n\nAlice wrote:\n> hi\n");
+    }
+
+    #[test]
+    fn attribution_uses_configured_timezone() {
+        // 2026-03-03T09:05:00Z
+        let line = attribution(
+            Some(1_772_528_700),
+            Some("Alice <a@example.com>"),
+            chrono_tz::UTC,
+        );
+        assert_eq!(
+            line,
+            "On Tue, 3 Mar 2026 09:05 UTC, Alice <a@example.com> wrote:"
+        );
+        let line = attribution(Some(1_772_528_700), None, chrono_tz::Asia::Tokyo);
+        assert_eq!(line, "On Tue, 3 Mar 2026 18:05 JST, someone wrote:");
+        assert_eq!(attribution(None, None, chrono_tz::UTC), "someone wrote:");
+    }
+
+    #[test]
+    fn body_html_keeps_only_the_body_content() {
+        let html = "<!DOCTYPE html><html><head><style>p{color:red}</style><title>x</title></head><BODY class=\"m\"><p>Hi</p><script>alert(1)</script><Style>b{}</style><b>there</b></body></html>";
+        assert_eq!(body_html(html), "<p>Hi</p><b>there</b>");
+        assert_eq!(body_html("<p>fragment</p>\n"), "<p>fragment</p>");
+
+        let out = quoted_reply_html(None, "Alice wrote:", html);
+        assert!(!out.contains("<head>") && !out.contains("<body"));
+        assert!(out.contains("<p>Hi</p><b>there</b>"));
+    }
+
+    #[test]
+    fn html_to_text_drops_tags_and_styles() {
+        let html = "<html><head><style>p{color:red}</style></head><body><p>Hello &amp; welcome</p><div>line<br>two</div></body></html>";
+        assert_eq!(html_to_text(html), "Hello & welcome\nline\ntwo");
+    }
+
+    #[test]
+    fn forward_helpers() {
+        assert_eq!(forward_subject(Some("Hello")), "Fwd: Hello");
+        assert_eq!(forward_subject(Some("FW: Hello")), "FW: Hello");
+        assert_eq!(eml_file_name(Some("Re: a/b")), "Re_ a_b.eml");
+        assert_eq!(eml_file_name(None), "forwarded.eml");
+
+        let headers = forward_header_lines(
+            Some("a@example.com"),
+            None,
+            Some("Hello"),
+            &["b@example.com".to_string()],
+            &[],
+            chrono_tz::UTC,
+        );
+        let text = forward_text(Some("FYI"), &headers, "body\n");
+        assert_eq!(
+            text,
+            "FYI\n\n---------- Forwarded message ---------\nFrom: a@example.com\nSubject: Hello\nTo: b@example.com\n\nbody\n"
+        );
+    }
+
+    #[test]
+    fn signature_goes_after_the_separator() {
+        let (text, html) = append_signature(
+            Some("Hi\n".to_string()),
+            Some("<p>Hi</p>".to_string()),
+            "Alice\nACME",
+            "",
+        )
+        .expect("signature");
+        assert_eq!(text.as_deref(), Some("Hi\n\n-- \nAlice\nACME\n"));
+        assert!(
+            html.as_deref()
+                .unwrap_or("")
+                .contains("-- <br>\n<div style=\"white-space:pre-wrap\">Alice\nACME</div>")
+        );
+
+        let (text, html) = append_signature(None, None, "", "<b>Alice</b>").expect("signature");
+        assert_eq!(text.as_deref(), Some("-- \nAlice\n"));
+        assert_eq!(html, None);
+
+        assert!(append_signature(Some("Hi".to_string()), None, " ", "").is_none());
+    }
+}
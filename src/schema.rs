@@ -152,6 +152,19 @@ pub fn extract_attachments(email: &Email) -> Vec<Value> {
     out
 }
 
+/// An email in `Email/get` shape with what attachment export records (see
+/// `xin attachments export`).
+pub fn email_attachment_source(email: &Email) -> Value {
+    json!({
+        "id": email.id(),
+        "threadId": email.thread_id(),
+        "receivedAt": received_at_rfc3339(email),
+        "subject": email.subject(),
+        "from": email.from(),
+        "attachments": extract_attachments(email),
+    })
+}
+
 pub fn get_email_data(email: &Email, raw: Option<Value>) -> Value {
     // v0 metadata-first: keep a stable shape; fill more fields as READ expands.
     json!({
@@ -1,259 +1,510 @@
-use chrono::{NaiveDate, TimeZone, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
 use serde_json::{Value, json};
 
 use crate::backend::Backend;
 use crate::error::XinErrorOut;
 
-/// Compile xin's v0 sugar DSL into a JMAP Email/query filter JSON object.
+/// Compile xin's sugar DSL into a JMAP Email/query filter JSON object.
+///
+/// Grammar (precedence: NOT > AND > OR):
+///
+/// ```text
+/// query   := or_expr
+/// or_expr := and_expr (("OR" | "|") and_expr)*
+/// and_expr:= unary (["AND"] unary)*        AND is implicit by whitespace
+/// unary   := ("-" | "NOT") unary | primary
+/// primary := "(" or_expr ")" | "or:(" or_expr ")" | term
+/// ```
+///
+/// - `OR`, `AND`, `NOT` are keywords only in upper case; lower-case words are text terms.
+/// - `or:(a | b)` is kept as an alias for `(a | b)`.
+/// - If a term has no `key:` prefix, it is treated as `text:<term>`.
+/// - Values containing spaces or parentheses must be quoted (`subject:"a (b)"`).
+/// - `@name` expands a saved query from the config (`queries`) as a group, so it composes
+///   with other terms (`@invoices newer_than:30d`). Names are `[A-Za-z0-9_-]+`; other
+///   `@...` words (e.g. `@example.com`) stay text terms.
 ///
-/// Notes (v0):
-/// - AND is implicit by whitespace.
-/// - OR is only supported via `or:(a | b | ...)`.
-/// - NOT is supported via `-term` prefix.
-/// - Parentheses grouping is NOT supported.
-/// - If a token has no `key:` prefix, it is treated as `text:<token>`.
+/// Every node compiles 1:1 into a JMAP FilterOperator (`AND` / `OR` / `NOT`), or a
+/// FilterCondition for a single term.
 ///
 /// Mailbox resolution:
 /// - `in:<mailbox>` resolves by id, role, then (case-sensitive) name, then case-insensitive name.
 /// - Aliases: `spam` -> role `junk`, `bin` -> role `trash`.
+///
+/// Dates:
+/// - Bare dates (`after:2026-01-01`) and `today` / `yesterday` are midnight in the
+///   account timezone (`Backend::timezone`); the filter always carries UTC RFC3339 instants.
 pub async fn compile_search_filter(query: &str, backend: &Backend) -> Result<Value, XinErrorOut> {
+    explain_search_filter(query, backend)
+        .await
+        .map(|c| c.filter)
+}
+
+/// The filter of a command taking `[<query>]` or `--filter-json`: the JSON when given, else
+/// the compiled query, else `{}` (every email).
+pub async fn query_filter(
+    query: Option<&str>,
+    filter_json: Option<&str>,
+    backend: &Backend,
+) -> Result<Value, XinErrorOut> {
+    match filter_json {
+        Some(s) => crate::config::read_json_arg(s),
+        None => compile_search_filter(query.unwrap_or(""), backend).await,
+    }
+}
+
+/// A compiled sugar query plus the details `--explain` reports.
+pub struct CompiledQuery {
+    pub filter: Value,
+    /// One entry per `in:` term: the mailbox it resolved to and how it matched.
+    pub mailboxes: Vec<Value>,
+    /// Saved queries (`@name`) that were expanded, in first-use order.
+    pub saved_queries: Vec<Value>,
+}
+
+/// Like [`compile_search_filter`], but also reports how each `in:` term was resolved.
+pub async fn explain_search_filter(
+    query: &str,
+    backend: &Backend,
+) -> Result<CompiledQuery, XinErrorOut> {
     let query = query.trim();
     if query.is_empty() {
-        return Ok(json!({}));
+        return Ok(CompiledQuery {
+            filter: json!({}),
+            mailboxes: Vec::new(),
+            saved_queries: Vec::new(),
+        });
     }
 
-    let tokens = lex_tokens(query)?;
+    let (expr, used) = parse_query_with(query, backend.saved_queries(), Vec::new())?;
 
     // Only fetch mailboxes if we see any in:<...> term.
-    let needs_mailboxes = tokens.iter().any(|t| match t {
-        Token::Term { key: Some(k), .. } => k == "in",
-        Token::OrGroup(terms) => terms
-            .iter()
-            .any(|tt| matches!(tt.key.as_deref(), Some("in"))),
-        _ => false,
-    });
-
-    let mailboxes = if needs_mailboxes {
+    let mailboxes = if expr.has_key("in") {
         Some(backend.list_mailboxes().await?)
     } else {
         None
     };
 
-    let mut compiled: Vec<Value> = Vec::new();
-
-    for t in tokens {
-        match t {
-            Token::Term {
-                negated,
-                key,
-                value,
-            } => {
-                let cond = compile_one_term(&key, &value, mailboxes.as_deref())?;
-                compiled.push(if negated { not(cond) } else { cond });
-            }
-            Token::OrGroup(terms) => {
-                let mut ors: Vec<Value> = Vec::new();
-                for tt in terms {
-                    let cond = compile_one_term(&tt.key, &tt.value, mailboxes.as_deref())?;
-                    ors.push(if tt.negated { not(cond) } else { cond });
-                }
-                compiled.push(op("OR", ors));
+    let ctx = Ctx {
+        mailboxes: mailboxes.as_deref(),
+        tz: backend.timezone(),
+        now: Utc::now(),
+    };
+    let filter = compile_expr(&expr, &ctx)?;
+
+    let mut resolved: Vec<Value> = Vec::new();
+    if let Some(mbxs) = mailboxes.as_deref() {
+        let mut terms: Vec<(&str, usize)> = Vec::new();
+        expr.collect_values("in", &mut terms);
+        for (value, pos) in terms {
+            // compile_expr already failed for unknown mailboxes.
+            if let Some((m, matched_by)) = resolve_mailbox(value, mbxs) {
+                resolved.push(json!({
+                    "term": format!("in:{value}"),
+                    "position": pos,
+                    "mailboxId": m.id(),
+                    "name": m.name(),
+                    "matchedBy": matched_by
+                }));
             }
         }
     }
 
-    Ok(and_all(compiled))
+    let mut saved_queries: Vec<Value> = Vec::new();
+    for (name, text) in used {
+        if !saved_queries.iter().any(|q| q["name"] == name) {
+            saved_queries.push(json!({"name": name, "query": text}));
+        }
+    }
+
+    Ok(CompiledQuery {
+        filter,
+        mailboxes: resolved,
+        saved_queries,
+    })
 }
 
-#[derive(Debug, Clone)]
-enum Token {
+/// Parsed sugar query.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
     Term {
-        negated: bool,
-        key: Option<String>,
+        key: String,
         value: String,
+        /// 1-based character position in the query (for error messages).
+        pos: usize,
     },
-    OrGroup(Vec<TermToken>),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    fn has_key(&self, k: &str) -> bool {
+        match self {
+            Expr::Term { key, .. } => key == k,
+            Expr::Not(e) => e.has_key(k),
+            Expr::And(es) | Expr::Or(es) => es.iter().any(|e| e.has_key(k)),
+        }
+    }
+
+    /// Report every term at `pos` (used for terms expanded from a saved query).
+    fn at(self, pos: usize) -> Expr {
+        match self {
+            Expr::Term { key, value, .. } => Expr::Term { key, value, pos },
+            Expr::Not(e) => Expr::Not(Box::new(e.at(pos))),
+            Expr::And(es) => Expr::And(es.into_iter().map(|e| e.at(pos)).collect()),
+            Expr::Or(es) => Expr::Or(es.into_iter().map(|e| e.at(pos)).collect()),
+        }
+    }
+
+    /// Values (and positions) of every `k:` term, in query order.
+    fn collect_values<'a>(&'a self, k: &str, out: &mut Vec<(&'a str, usize)>) {
+        match self {
+            Expr::Term { key, value, pos } if key == k => out.push((value.as_str(), *pos)),
+            Expr::Term { .. } => {}
+            Expr::Not(e) => e.collect_values(k, out),
+            Expr::And(es) | Expr::Or(es) => es.iter().for_each(|e| e.collect_values(k, out)),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-struct TermToken {
-    negated: bool,
-    key: Option<String>,
-    value: String,
+/// What term compilation needs besides the term itself.
+struct Ctx<'a> {
+    mailboxes: Option<&'a [jmap_client::mailbox::Mailbox]>,
+    /// Zone for bare dates and `today` / `yesterday`; an invalid setting only fails queries
+    /// that use them.
+    tz: Result<Tz, XinErrorOut>,
+    /// Reference instant for relative dates.
+    now: DateTime<Utc>,
 }
 
-fn lex_tokens(input: &str) -> Result<Vec<Token>, XinErrorOut> {
-    // Split on whitespace, but keep quoted segments intact.
-    let raw = split_ws_quoted(input)?;
+fn compile_expr(expr: &Expr, ctx: &Ctx) -> Result<Value, XinErrorOut> {
+    match expr {
+        Expr::Term { key, value, pos } => compile_one_term(key, value, ctx).map_err(|mut e| {
+            e.message = format!("{} (at position {pos})", e.message);
+            e
+        }),
+        Expr::Not(e) => Ok(not(compile_expr(e, ctx)?)),
+        Expr::And(es) => Ok(op(
+            "AND",
+            es.iter()
+                .map(|e| compile_expr(e, ctx))
+                .collect::<Result<_, _>>()?,
+        )),
+        Expr::Or(es) => Ok(op(
+            "OR",
+            es.iter()
+                .map(|e| compile_expr(e, ctx))
+                .collect::<Result<_, _>>()?,
+        )),
+    }
+}
 
-    let mut out: Vec<Token> = Vec::new();
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    LParen,
+    RParen,
+    Or,
+    And,
+    Not,
+    Term(String),
+}
 
-    for tok in raw {
-        if tok.starts_with("or:(") {
-            // v0: or:(a | b | c)
-            if !tok.ends_with(')') {
-                return Err(XinErrorOut::usage(
-                    "or:(...) group must be a single token; quote the full query".to_string(),
-                ));
-            }
-            let inner = tok
-                .strip_prefix("or:(")
-                .and_then(|s| s.strip_suffix(')'))
-                .unwrap_or("");
+/// A token and its 1-based character position.
+type Spanned = (Tok, usize);
 
-            if inner.contains("or:(") {
-                return Err(XinErrorOut::usage(
-                    "nested or:(...) is not supported in v0; use `--filter-json` for nested boolean logic".to_string(),
-                ));
-            }
+fn parse_error(pos: usize, msg: &str) -> XinErrorOut {
+    XinErrorOut::usage(format!(
+        "query parse error at position {pos}: {msg}; quote values with spaces/parentheses, or use `--filter-json` (inline JSON or @file)"
+    ))
+}
+
+fn lex(input: &str) -> Result<Vec<Spanned>, XinErrorOut> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out: Vec<Spanned> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let pos = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
 
-            let parts = split_or_terms(inner)?;
-            let mut terms: Vec<TermToken> = Vec::new();
-            for p in parts {
-                let (negated, key, value) = parse_simple_term(&p)?;
-                terms.push(TermToken {
-                    negated,
-                    key,
-                    value,
-                });
+        match c {
+            '(' => {
+                out.push((Tok::LParen, pos));
+                i += 1;
+                continue;
             }
-            if terms.is_empty() {
-                return Err(XinErrorOut::usage("or:(...) group is empty".to_string()));
+            ')' => {
+                out.push((Tok::RParen, pos));
+                i += 1;
+                continue;
             }
-            out.push(Token::OrGroup(terms));
+            '|' => {
+                out.push((Tok::Or, pos));
+                i += 1;
+                continue;
+            }
+            '-' => {
+                // Prefix NOT must be attached to what it negates.
+                match chars.get(i + 1) {
+                    Some(n) if !n.is_whitespace() && *n != ')' && *n != '|' => {
+                        out.push((Tok::Not, pos));
+                        i += 1;
+                        continue;
+                    }
+                    _ => return Err(parse_error(pos, "`-` must be followed by a term or group")),
+                }
+            }
+            _ => {}
+        }
+
+        // Term: read until whitespace or a structural character (outside quotes).
+        let mut term = String::new();
+        let mut in_quotes = false;
+        while i < chars.len() {
+            let ch = chars[i];
+            if ch == '"' {
+                in_quotes = !in_quotes;
+            } else if !in_quotes && (ch.is_whitespace() || ch == ')' || ch == '|') {
+                break;
+            } else if !in_quotes && ch == '(' {
+                if term.eq_ignore_ascii_case("or:") {
+                    // Legacy `or:(...)` group opener.
+                    break;
+                }
+                return Err(parse_error(i + 1, "unexpected `(` inside a term"));
+            }
+            term.push(ch);
+            i += 1;
+        }
+
+        if in_quotes {
+            return Err(parse_error(pos, "unterminated quote"));
+        }
+
+        if term.eq_ignore_ascii_case("or:") && chars.get(i) == Some(&'(') {
+            out.push((Tok::LParen, pos));
+            i += 1;
             continue;
         }
 
-        let (negated, key, value) = parse_simple_term(&tok)?;
-        out.push(Token::Term {
-            negated,
-            key,
-            value,
-        });
+        let tok = match term.as_str() {
+            "OR" => Tok::Or,
+            "AND" => Tok::And,
+            "NOT" => Tok::Not,
+            _ => Tok::Term(term),
+        };
+        out.push((tok, pos));
     }
 
     Ok(out)
 }
 
-fn parse_simple_term(token: &str) -> Result<(bool, Option<String>, String), XinErrorOut> {
-    let mut s = token.trim();
-    if s.is_empty() {
-        return Err(XinErrorOut::usage("empty token".to_string()));
-    }
+struct Parser<'a> {
+    toks: Vec<Spanned>,
+    i: usize,
+    /// Position just past the end of the input (for "unexpected end" errors).
+    end: usize,
+    /// Saved queries available to `@name`.
+    saved: &'a BTreeMap<String, String>,
+    /// Saved queries being expanded (cycle detection).
+    stack: Vec<String>,
+    /// Saved queries expanded so far: `(name, query)`.
+    used: Vec<(String, String)>,
+}
 
-    let mut negated = false;
-    if let Some(rest) = s.strip_prefix('-') {
-        negated = true;
-        s = rest;
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.i).map(|(t, _)| t)
     }
 
-    // No parentheses grouping in v0.
-    if token.trim_start().starts_with("-(") {
-        return Err(XinErrorOut::usage(
-            "group negation `-(...)` is not supported in v0; negate individual terms (e.g. `-from:alice -subject:foo`) or use `--filter-json` (inline JSON or @file). Example: --filter-json '{\"operator\":\"NOT\",\"conditions\":[{\"from\":\"alice\"}]}'".to_string(),
-        ));
+    fn pos(&self) -> usize {
+        self.toks.get(self.i).map(|(_, p)| *p).unwrap_or(self.end)
     }
 
-    if s.starts_with('(') || s.ends_with(')') {
-        return Err(XinErrorOut::usage(
-            "parentheses grouping is not supported in v0; use `or:(a|b|...)`, `-term`, or `--filter-json` (inline JSON or @file) for complex filters. Example: --filter-json '{\"operator\":\"AND\",\"conditions\":[{\"from\":\"alice\"},{\"subject\":\"foo\"}]}'".to_string(),
-        ));
+    fn parse_or(&mut self) -> Result<Expr, XinErrorOut> {
+        let mut items = vec![self.parse_and()?];
+        while self.peek() == Some(&Tok::Or) {
+            self.i += 1;
+            items.push(self.parse_and()?);
+        }
+        Ok(flatten(items, true))
     }
 
-    if let Some((k, v)) = s.split_once(':') {
-        let key = k.trim().to_lowercase();
-        let value = unquote(v.trim());
-        Ok((negated, Some(key), value))
-    } else {
-        // Bare term: map to text search.
-        Ok((negated, Some("text".to_string()), unquote(s)))
+    fn parse_and(&mut self) -> Result<Expr, XinErrorOut> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Tok::And) => {
+                    self.i += 1;
+                    items.push(self.parse_unary()?);
+                }
+                Some(Tok::Term(_)) | Some(Tok::Not) | Some(Tok::LParen) => {
+                    items.push(self.parse_unary()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(flatten(items, false))
     }
-}
 
-fn unquote(s: &str) -> String {
-    if let Some(inner) = s.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
-        inner.to_string()
-    } else {
-        s.to_string()
+    fn parse_unary(&mut self) -> Result<Expr, XinErrorOut> {
+        if self.peek() == Some(&Tok::Not) {
+            self.i += 1;
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
     }
-}
 
-fn split_ws_quoted(input: &str) -> Result<Vec<String>, XinErrorOut> {
-    let mut out: Vec<String> = Vec::new();
-    let mut cur = String::new();
-    let mut in_quotes = false;
-
-    for ch in input.chars() {
-        match ch {
-            '"' => {
-                in_quotes = !in_quotes;
-                cur.push(ch);
+    fn parse_primary(&mut self) -> Result<Expr, XinErrorOut> {
+        let pos = self.pos();
+        match self.toks.get(self.i).map(|(t, _)| t.clone()) {
+            Some(Tok::LParen) => {
+                self.i += 1;
+                if self.peek() == Some(&Tok::RParen) {
+                    return Err(parse_error(pos, "empty group"));
+                }
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&Tok::RParen) {
+                    return Err(parse_error(pos, "missing `)` for this `(`"));
+                }
+                self.i += 1;
+                Ok(inner)
             }
-            c if c.is_whitespace() && !in_quotes => {
-                if !cur.is_empty() {
-                    out.push(cur);
-                    cur = String::new();
+            Some(Tok::Term(t)) => {
+                self.i += 1;
+                if let Some(name) = saved_query_ref(&t) {
+                    return self.expand_saved(name, pos);
                 }
+                let (key, value) = parse_simple_term(&t);
+                Ok(Expr::Term { key, value, pos })
             }
-            _ => cur.push(ch),
+            Some(Tok::RParen) => Err(parse_error(pos, "unexpected `)`")),
+            Some(Tok::Or) => Err(parse_error(pos, "expected a term before OR")),
+            Some(Tok::And) => Err(parse_error(pos, "expected a term before AND")),
+            Some(Tok::Not) => unreachable!("handled by parse_unary"),
+            None => Err(parse_error(pos, "unexpected end of query")),
         }
     }
 
-    if in_quotes {
-        return Err(XinErrorOut::usage("unterminated quote".to_string()));
-    }
+    fn expand_saved(&mut self, name: &str, pos: usize) -> Result<Expr, XinErrorOut> {
+        if self.stack.iter().any(|n| n == name) {
+            let chain: Vec<String> = self.stack.iter().map(|n| format!("@{n}")).collect();
+            return Err(XinErrorOut::usage(format!(
+                "saved query @{name} refers to itself ({} -> @{name}) (at position {pos})",
+                chain.join(" -> ")
+            )));
+        }
+        let text = self.saved.get(name).ok_or_else(|| {
+            XinErrorOut::usage(format!(
+                "unknown saved query @{name} (at position {pos}); see `xin queries list`"
+            ))
+        })?;
+
+        let mut stack = self.stack.clone();
+        stack.push(name.to_string());
+        let (expr, used) = parse_query_with(text, self.saved, stack).map_err(|mut e| {
+            e.message = format!("in saved query @{name}: {}", e.message);
+            e
+        })?;
 
-    if !cur.is_empty() {
-        out.push(cur);
+        self.used.push((name.to_string(), text.clone()));
+        self.used.extend(used);
+        Ok(expr.at(pos))
     }
+}
 
-    Ok(out)
+/// `@name` -> `name` when the token is a saved query reference.
+fn saved_query_ref(token: &str) -> Option<&str> {
+    token.strip_prefix('@').filter(|name| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
 }
 
-fn split_or_terms(inner: &str) -> Result<Vec<String>, XinErrorOut> {
-    // Split on `|`, but allow quoted strings.
-    let mut out: Vec<String> = Vec::new();
-    let mut cur = String::new();
-    let mut in_quotes = false;
+/// Merge same-operator children (`a OR (b OR c)` -> `OR[a,b,c]`) and unwrap single items.
+fn flatten(items: Vec<Expr>, is_or: bool) -> Expr {
+    if items.len() == 1 {
+        return items.into_iter().next().expect("one item");
+    }
 
-    for ch in inner.chars() {
-        match ch {
-            '"' => {
-                in_quotes = !in_quotes;
-                cur.push(ch);
-            }
-            '|' if !in_quotes => {
-                let s = cur.trim();
-                if !s.is_empty() {
-                    out.push(s.to_string());
-                }
-                cur.clear();
-            }
-            _ => cur.push(ch),
+    let mut out: Vec<Expr> = Vec::new();
+    for e in items {
+        match e {
+            Expr::Or(inner) if is_or => out.extend(inner),
+            Expr::And(inner) if !is_or => out.extend(inner),
+            e => out.push(e),
         }
     }
 
-    if in_quotes {
-        return Err(XinErrorOut::usage(
-            "unterminated quote in or:(...)".to_string(),
-        ));
-    }
+    if is_or { Expr::Or(out) } else { Expr::And(out) }
+}
 
-    let s = cur.trim();
-    if !s.is_empty() {
-        out.push(s.to_string());
+#[cfg(test)]
+fn parse_query(input: &str) -> Result<Expr, XinErrorOut> {
+    parse_query_with(input, &BTreeMap::new(), Vec::new()).map(|(e, _)| e)
+}
+
+/// Parse `input`, expanding `@name` from `saved`; also returns the expanded saved queries.
+fn parse_query_with(
+    input: &str,
+    saved: &BTreeMap<String, String>,
+    stack: Vec<String>,
+) -> Result<(Expr, Vec<(String, String)>), XinErrorOut> {
+    let toks = lex(input)?;
+    let mut p = Parser {
+        toks,
+        i: 0,
+        end: input.chars().count() + 1,
+        saved,
+        stack,
+        used: Vec::new(),
+    };
+
+    let expr = p.parse_or()?;
+    if p.i < p.toks.len() {
+        let msg = match p.peek() {
+            Some(Tok::RParen) => "unmatched `)`",
+            _ => "unexpected token",
+        };
+        return Err(parse_error(p.pos(), msg));
     }
+    Ok((expr, p.used))
+}
 
-    Ok(out)
+/// Check that a query parses and its `@name` references resolve (for `xin queries set`).
+pub fn validate_query(query: &str, saved: &BTreeMap<String, String>) -> Result<(), XinErrorOut> {
+    parse_query_with(query, saved, Vec::new()).map(|_| ())
 }
 
-fn compile_one_term(
-    key: &Option<String>,
-    value: &str,
-    mailboxes: Option<&[jmap_client::mailbox::Mailbox]>,
-) -> Result<Value, XinErrorOut> {
-    let key = key.as_deref().unwrap_or("text");
+fn parse_simple_term(token: &str) -> (String, String) {
+    if let Some((k, v)) = token.split_once(':') {
+        (k.trim().to_lowercase(), unquote(v.trim()))
+    } else {
+        // Bare term: map to text search.
+        ("text".to_string(), unquote(token))
+    }
+}
 
+fn unquote(s: &str) -> String {
+    if let Some(inner) = s.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        inner.to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+fn compile_one_term(key: &str, value: &str, ctx: &Ctx) -> Result<Value, XinErrorOut> {
     match key {
         // Addressing
         "from" | "to" | "cc" | "bcc" => Ok(json!({ key: value })),
@@ -263,7 +514,7 @@ fn compile_one_term(
 
         // Mailbox
         "in" => {
-            let Some(mbxs) = mailboxes else {
+            let Some(mbxs) = ctx.mailboxes else {
                 return Err(XinErrorOut::usage(
                     "in:<mailbox> requires mailbox listing (internal error)".to_string(),
                 ));
@@ -277,7 +528,45 @@ fn compile_one_term(
         "has" if value == "attachment" => Ok(json!({"hasAttachment": true})),
         "hasattachment" => Ok(json!({"hasAttachment": parse_bool(value, "hasAttachment")?})),
 
+        // Size (bytes; K/M/G suffixes are powers of 1024)
+        "larger" => Ok(json!({"minSize": parse_size(value, "larger")?})),
+        "smaller" => Ok(json!({"maxSize": parse_size(value, "smaller")?})),
+
+        // Headers: `header:<Name>` (present) or `header:<Name>:<text>` (contains)
+        "header" => {
+            let (name, text) = match value.split_once(':') {
+                Some((n, t)) => (n.trim(), Some(unquote(t.trim()))),
+                None => (value.trim(), None),
+            };
+            if name.is_empty() {
+                return Err(XinErrorOut::usage(
+                    "header: requires a header name (header:<Name>[:<text>])".to_string(),
+                ));
+            }
+            match text {
+                Some(t) => Ok(json!({"header": [name, t]})),
+                None => Ok(json!({"header": [name]})),
+            }
+        }
+
         // Keywords/state
+        "keyword" => Ok(json!({"hasKeyword": parse_keyword(value, "keyword")?})),
+        "thread-has" => Ok(json!({
+            "someInThreadHaveKeyword": parse_keyword(value, "thread-has")?
+        })),
+        "thread-all" => Ok(json!({
+            "allInThreadHaveKeyword": parse_keyword(value, "thread-all")?
+        })),
+        "is" => match value.to_lowercase().as_str() {
+            "answered" | "replied" => Ok(json!({"hasKeyword": "$answered"})),
+            "draft" => Ok(json!({"hasKeyword": "$draft"})),
+            "flagged" | "starred" => Ok(json!({"hasKeyword": "$flagged"})),
+            "seen" | "read" => Ok(json!({"hasKeyword": "$seen"})),
+            "unseen" | "unread" => Ok(json!({"notKeyword": "$seen"})),
+            other => Err(XinErrorOut::usage(format!(
+                "unsupported is:{other} (expected answered|draft|flagged|seen|unread)"
+            ))),
+        },
         "seen" => {
             let b = parse_bool(value, "seen")?;
             if b {
@@ -296,8 +585,10 @@ fn compile_one_term(
         }
 
         // Time (receivedAt)
-        "after" => Ok(json!({"after": parse_date(value, "after")?})),
-        "before" => Ok(json!({"before": parse_date(value, "before")?})),
+        "after" => Ok(json!({"after": parse_date(value, "after", ctx)?})),
+        "before" => Ok(json!({"before": parse_date(value, "before", ctx)?})),
+        "newer_than" => Ok(json!({"after": parse_relative(value, key, ctx.now)?})),
+        "older_than" => Ok(json!({"before": parse_relative(value, key, ctx.now)?})),
 
         other => Err(XinErrorOut::usage(format!("unsupported term: {other}"))),
     }
@@ -311,14 +602,53 @@ fn parse_bool(value: &str, label: &str) -> Result<bool, XinErrorOut> {
     }
 }
 
-fn parse_date(value: &str, label: &str) -> Result<String, XinErrorOut> {
-    // v0: accept YYYY-MM-DD (interpreted as 00:00:00Z) or RFC3339.
-    if let Ok(d) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
-        let dt = Utc.from_utc_datetime(
-            &d.and_hms_opt(0, 0, 0)
-                .ok_or_else(|| XinErrorOut::usage(format!("invalid date for {label}")))?,
-        );
-        return Ok(dt.to_rfc3339());
+fn parse_size(value: &str, label: &str) -> Result<u64, XinErrorOut> {
+    let invalid = || XinErrorOut::usage(format!("{label} must be a size like 500K, 5M or 1G"));
+    let v = value.trim().to_uppercase();
+    let v = v.strip_suffix('B').unwrap_or(&v);
+    let (digits, mult) = match v.chars().last() {
+        Some('K') => (&v[..v.len() - 1], 1024),
+        Some('M') => (&v[..v.len() - 1], 1024 * 1024),
+        Some('G') => (&v[..v.len() - 1], 1024 * 1024 * 1024),
+        _ => (v, 1),
+    };
+    let n: u64 = digits.trim().parse().map_err(|_| invalid())?;
+    n.checked_mul(mult).ok_or_else(invalid)
+}
+
+fn parse_keyword(value: &str, label: &str) -> Result<String, XinErrorOut> {
+    let kw = value.trim();
+    // RFC 8621 keywords: printable ASCII, no spaces or `( ) { ] % * " \`.
+    let valid = !kw.is_empty()
+        && kw.chars().all(|c| {
+            c.is_ascii_graphic() && !matches!(c, '(' | ')' | '{' | ']' | '%' | '*' | '"' | '\\')
+        });
+    if !valid {
+        return Err(XinErrorOut::usage(format!(
+            "{label} requires a JMAP keyword (e.g. $flagged, $label)"
+        )));
+    }
+    Ok(kw.to_lowercase())
+}
+
+fn parse_date(value: &str, label: &str, ctx: &Ctx) -> Result<String, XinErrorOut> {
+    // YYYY-MM-DD / today / yesterday (midnight in ctx.tz), RFC3339, or a relative age (`7d`).
+    let today = || -> Result<NaiveDate, XinErrorOut> {
+        Ok(ctx.now.with_timezone(&ctx.tz.clone()?).date_naive())
+    };
+    let day = match value.to_lowercase().as_str() {
+        "today" => Some(today()?),
+        "yesterday" => today()?.pred_opt(),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+    };
+    if let Some(d) = day {
+        return local_midnight(d, ctx.tz.clone()?)
+            .map(|dt| dt.to_rfc3339())
+            .ok_or_else(|| XinErrorOut::usage(format!("invalid date for {label}")));
+    }
+
+    if let Some(age) = parse_age(value) {
+        return relative_to(age, label, ctx.now);
     }
 
     chrono::DateTime::parse_from_rfc3339(value)
@@ -326,19 +656,75 @@ fn parse_date(value: &str, label: &str) -> Result<String, XinErrorOut> {
         .map_err(|e| XinErrorOut::usage(format!("invalid {label} date: {e}")))
 }
 
+/// Start of `day` in `tz`, as a UTC instant. When midnight falls in a DST gap, the day
+/// starts at the first valid local time after it.
+fn local_midnight(day: NaiveDate, tz: Tz) -> Option<DateTime<Utc>> {
+    (0..=2).find_map(|h| {
+        let local = day.and_hms_opt(h, 0, 0)?;
+        tz.from_local_datetime(&local)
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    })
+}
+
+/// `newer_than:` / `older_than:` only accept relative ages.
+fn parse_relative(value: &str, label: &str, now: DateTime<Utc>) -> Result<String, XinErrorOut> {
+    let age = parse_age(value).ok_or_else(|| {
+        XinErrorOut::usage(format!(
+            "{label} must be a relative age like 12h, 7d, 2w, 3m or 1y"
+        ))
+    })?;
+    relative_to(age, label, now)
+}
+
+/// Parse `<n><unit>` with unit h (hours), d (days), w (weeks), m (30 days), y (365 days).
+fn parse_age(value: &str) -> Option<(i64, char)> {
+    let v = value.trim().to_lowercase();
+    let unit = v.chars().last()?;
+    if !matches!(unit, 'h' | 'd' | 'w' | 'm' | 'y') {
+        return None;
+    }
+    let n: i64 = v[..v.len() - 1].parse().ok()?;
+    (n >= 0).then_some((n, unit))
+}
+
+fn relative_to(
+    (n, unit): (i64, char),
+    label: &str,
+    now: DateTime<Utc>,
+) -> Result<String, XinErrorOut> {
+    let hours = match unit {
+        'h' => Some(n),
+        'd' => n.checked_mul(24),
+        'w' => n.checked_mul(24 * 7),
+        'm' => n.checked_mul(24 * 30),
+        _ => n.checked_mul(24 * 365),
+    };
+    let dt = hours
+        .and_then(Duration::try_hours)
+        .and_then(|d| now.checked_sub_signed(d))
+        .ok_or_else(|| XinErrorOut::usage(format!("{label} is out of range")))?;
+    Ok(dt.with_nanosecond(0).unwrap_or(dt).to_rfc3339())
+}
+
 fn resolve_mailbox_id(s: &str, mailboxes: &[jmap_client::mailbox::Mailbox]) -> Option<String> {
+    resolve_mailbox(s, mailboxes).and_then(|(m, _)| m.id().map(|id| id.to_string()))
+}
+
+/// Resolve a mailbox reference; also reports how it matched
+/// (`id`, `role`, `name`, `nameCaseInsensitive`).
+fn resolve_mailbox<'a>(
+    s: &str,
+    mailboxes: &'a [jmap_client::mailbox::Mailbox],
+) -> Option<(&'a jmap_client::mailbox::Mailbox, &'static str)> {
     let needle = s.trim();
     if needle.is_empty() {
         return None;
     }
 
     // 0) direct id match
-    for m in mailboxes {
-        if let Some(id) = m.id() {
-            if id == needle {
-                return Some(id.to_string());
-            }
-        }
+    if let Some(m) = mailboxes.iter().find(|m| m.id() == Some(needle)) {
+        return Some((m, "id"));
     }
 
     // 1) role match
@@ -365,24 +751,19 @@ fn resolve_mailbox_id(s: &str, mailboxes: &[jmap_client::mailbox::Mailbox]) -> O
     });
 
     if let Some(m) = role_match {
-        return m.id().map(|id| id.to_string());
+        return Some((m, "role"));
     }
 
     // 2) exact name match
     if let Some(m) = mailboxes.iter().find(|m| m.name() == Some(needle)) {
-        return m.id().map(|id| id.to_string());
+        return Some((m, "name"));
     }
 
     // 3) case-insensitive name match
-    let lower = needle.to_lowercase();
-    if let Some(m) = mailboxes
+    mailboxes
         .iter()
-        .find(|m| m.name().map(|n| n.to_lowercase()) == Some(lower.clone()))
-    {
-        return m.id().map(|id| id.to_string());
-    }
-
-    None
+        .find(|m| m.name().map(|n| n.to_lowercase()) == Some(needle_lower.clone()))
+        .map(|m| (m, "nameCaseInsensitive"))
 }
 
 fn op(kind: &str, conditions: Vec<Value>) -> Value {
@@ -393,12 +774,304 @@ fn not(cond: Value) -> Value {
     op("NOT", vec![cond])
 }
 
-fn and_all(mut conditions: Vec<Value>) -> Value {
-    if conditions.is_empty() {
-        json!({})
-    } else if conditions.len() == 1 {
-        conditions.pop().unwrap()
-    } else {
-        op("AND", conditions)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 15, 12, 30, 0).unwrap()
+    }
+
+    fn compile_in(q: &str, tz: Tz) -> Result<Value, XinErrorOut> {
+        let ctx = Ctx {
+            mailboxes: None,
+            tz: Ok(tz),
+            now: now(),
+        };
+        compile_expr(&parse_query(q)?, &ctx)
+    }
+
+    fn compile(q: &str) -> Result<Value, XinErrorOut> {
+        compile_in(q, Tz::UTC)
+    }
+
+    fn err(q: &str) -> String {
+        compile(q).expect_err("expected parse error").message
+    }
+
+    #[test]
+    fn implicit_and_and_bare_terms() {
+        assert_eq!(compile("from:alice").unwrap(), json!({"from": "alice"}));
+        assert_eq!(
+            compile("from:alice subject:\"foo bar\" hello").unwrap(),
+            json!({"operator": "AND", "conditions": [
+                {"from": "alice"},
+                {"subject": "foo bar"},
+                {"text": "hello"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn groups_or_and_group_negation() {
+        assert_eq!(
+            compile("(from:a OR from:b) AND -(subject:c d)").unwrap(),
+            json!({"operator": "AND", "conditions": [
+                {"operator": "OR", "conditions": [{"from": "a"}, {"from": "b"}]},
+                {"operator": "NOT", "conditions": [
+                    {"operator": "AND", "conditions": [{"subject": "c"}, {"text": "d"}]}
+                ]}
+            ]})
+        );
+    }
+
+    #[test]
+    fn precedence_not_then_and_then_or() {
+        // a b | c  ==  (a AND b) OR c
+        assert_eq!(
+            compile("from:a from:b | from:c").unwrap(),
+            json!({"operator": "OR", "conditions": [
+                {"operator": "AND", "conditions": [{"from": "a"}, {"from": "b"}]},
+                {"from": "c"}
+            ]})
+        );
+
+        // NOT binds tighter than AND.
+        assert_eq!(
+            compile("NOT from:a from:b").unwrap(),
+            json!({"operator": "AND", "conditions": [
+                {"operator": "NOT", "conditions": [{"from": "a"}]},
+                {"from": "b"}
+            ]})
+        );
+
+        // Lower-case `or` is just a text term.
+        assert_eq!(
+            compile("cats or dogs").unwrap(),
+            json!({"operator": "AND", "conditions": [
+                {"text": "cats"}, {"text": "or"}, {"text": "dogs"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn legacy_or_groups_still_work_and_nest() {
+        assert_eq!(
+            compile("or:(from:alice|from:bob) seen:false").unwrap(),
+            json!({"operator": "AND", "conditions": [
+                {"operator": "OR", "conditions": [{"from": "alice"}, {"from": "bob"}]},
+                {"notKeyword": "$seen"}
+            ]})
+        );
+        assert_eq!(
+            compile("or:(or:(from:alice|from:bob)|from:carol)").unwrap(),
+            json!({"operator": "OR", "conditions": [
+                {"from": "alice"}, {"from": "bob"}, {"from": "carol"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn errors_report_positions() {
+        assert!(err("(from:a").contains("position 1: missing `)`"));
+        assert!(err("from:a )").contains("position 8: unmatched `)`"));
+        assert!(err("from:a OR").contains("position 10: unexpected end"));
+        assert!(err("from:a ()").contains("position 8: empty group"));
+        assert!(err("subject:\"x").contains("position 1: unterminated quote"));
+        assert!(err("from:a - b").contains("position 8"));
+        assert!(err("from:a nope:x").contains("unsupported term: nope (at position 8)"));
+    }
+
+    #[test]
+    fn size_keyword_header_and_thread_terms() {
+        assert_eq!(
+            compile("larger:5M smaller:100k").unwrap(),
+            op(
+                "AND",
+                vec![
+                    json!({"minSize": 5 * 1024 * 1024}),
+                    json!({"maxSize": 100 * 1024})
+                ]
+            )
+        );
+        assert_eq!(compile("larger:2048").unwrap(), json!({"minSize": 2048}));
+        assert_eq!(
+            compile("keyword:$Label").unwrap(),
+            json!({"hasKeyword": "$label"})
+        );
+        assert_eq!(
+            compile("header:List-Id:foo").unwrap(),
+            json!({"header": ["List-Id", "foo"]})
+        );
+        assert_eq!(
+            compile("header:X-Spam").unwrap(),
+            json!({"header": ["X-Spam"]})
+        );
+        assert_eq!(
+            compile("thread-has:$flagged -thread-all:$seen").unwrap(),
+            op(
+                "AND",
+                vec![
+                    json!({"someInThreadHaveKeyword": "$flagged"}),
+                    not(json!({"allInThreadHaveKeyword": "$seen"})),
+                ]
+            )
+        );
+        assert_eq!(
+            compile("is:answered OR is:draft").unwrap(),
+            op(
+                "OR",
+                vec![
+                    json!({"hasKeyword": "$answered"}),
+                    json!({"hasKeyword": "$draft"}),
+                ]
+            )
+        );
+        assert!(err("larger:lots").contains("larger must be a size"));
+        assert!(err("keyword:\"a b\"").contains("keyword requires a JMAP keyword"));
+        assert!(err("is:spam").contains("unsupported is:spam"));
+        assert!(err("header:").contains("requires a header name"));
+    }
+
+    #[test]
+    fn relative_dates() {
+        let now = now();
+        assert_eq!(
+            parse_relative("7d", "newer_than", now).unwrap(),
+            "2026-03-08T12:30:00+00:00"
+        );
+        assert_eq!(
+            parse_relative("2w", "older_than", now).unwrap(),
+            "2026-03-01T12:30:00+00:00"
+        );
+        assert_eq!(
+            parse_relative("12h", "newer_than", now).unwrap(),
+            "2026-03-15T00:30:00+00:00"
+        );
+        assert!(parse_relative("2026-01-01", "older_than", now).is_err());
+        assert!(compile("newer_than:7d").unwrap().get("after").is_some());
+        assert!(compile("older_than:1y").unwrap().get("before").is_some());
+        assert!(compile("after:3d").unwrap().get("after").is_some());
+        assert!(err("newer_than:soon").contains("relative age"));
+    }
+
+    #[test]
+    fn bare_dates_and_day_keywords_use_the_timezone() {
+        let tokyo: Tz = "Asia/Tokyo".parse().unwrap();
+        assert_eq!(
+            compile_in("after:2026-01-01", tokyo).unwrap(),
+            json!({"after": "2025-12-31T15:00:00+00:00"})
+        );
+        assert_eq!(
+            compile("after:2026-01-01").unwrap(),
+            json!({"after": "2026-01-01T00:00:00+00:00"})
+        );
+        // 2026-03-15T12:30Z is 21:30 in Tokyo.
+        assert_eq!(
+            compile_in("after:today", tokyo).unwrap(),
+            json!({"after": "2026-03-14T15:00:00+00:00"})
+        );
+        assert_eq!(
+            compile("after:today").unwrap(),
+            json!({"after": "2026-03-15T00:00:00+00:00"})
+        );
+        assert_eq!(
+            compile_in("after:yesterday before:today", tokyo).unwrap(),
+            op(
+                "AND",
+                vec![
+                    json!({"after": "2026-03-13T15:00:00+00:00"}),
+                    json!({"before": "2026-03-14T15:00:00+00:00"}),
+                ]
+            )
+        );
+        // New York: 2026-03-08 starts at 05:00Z (EST); RFC3339 input is kept as-is.
+        let ny: Tz = "America/New_York".parse().unwrap();
+        assert_eq!(
+            compile_in("before:2026-03-08", ny).unwrap(),
+            json!({"before": "2026-03-08T05:00:00+00:00"})
+        );
+        assert_eq!(
+            compile_in("before:2026-03-08T00:00:00+09:00", ny).unwrap(),
+            json!({"before": "2026-03-07T15:00:00+00:00"})
+        );
+    }
+
+    #[test]
+    fn invalid_timezone_only_fails_bare_dates() {
+        let compile_bad = |q: &str| {
+            let ctx = Ctx {
+                mailboxes: None,
+                tz: Err(XinErrorOut::config(
+                    "invalid timezone 'Mars/Olympus' (XIN_TZ)",
+                )),
+                now: now(),
+            };
+            compile_expr(&parse_query(q)?, &ctx)
+        };
+        assert!(compile_bad("from:a newer_than:7d after:2026-01-01T00:00:00Z").is_ok());
+        for q in ["after:2026-01-01", "before:today"] {
+            assert_eq!(compile_bad(q).unwrap_err().kind, "xinConfigError");
+        }
+    }
+
+    #[test]
+    fn saved_queries_expand_as_groups() {
+        let saved = BTreeMap::from([
+            (
+                "invoices".to_string(),
+                "from:billing OR subject:invoice".to_string(),
+            ),
+            (
+                "unfiled".to_string(),
+                "@invoices -keyword:$filed".to_string(),
+            ),
+            ("loop-a".to_string(), "@loop-b".to_string()),
+            ("loop-b".to_string(), "from:x @loop-a".to_string()),
+            ("broken".to_string(), "(from:x".to_string()),
+        ]);
+        let compile_saved = |q: &str| {
+            let (expr, used) = parse_query_with(q, &saved, Vec::new())?;
+            let ctx = Ctx {
+                mailboxes: None,
+                tz: Ok(Tz::UTC),
+                now: now(),
+            };
+            compile_expr(&expr, &ctx).map(|f| (f, used))
+        };
+
+        let invoices = op(
+            "OR",
+            vec![json!({"from": "billing"}), json!({"subject": "invoice"})],
+        );
+        let (f, used) = compile_saved("@unfiled seen:false").unwrap();
+        assert_eq!(
+            f,
+            op(
+                "AND",
+                vec![
+                    invoices.clone(),
+                    not(json!({"hasKeyword": "$filed"})),
+                    json!({"notKeyword": "$seen"}),
+                ]
+            )
+        );
+        let names: Vec<&str> = used.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["unfiled", "invoices"]);
+
+        assert_eq!(
+            compile_saved("-@invoices").unwrap().0,
+            not(invoices.clone())
+        );
+        // Not a saved query name: still a text term.
+        assert_eq!(
+            compile_saved("@example.com").unwrap().0,
+            json!({"text": "@example.com"})
+        );
+
+        let msg = |q: &str| compile_saved(q).unwrap_err().message;
+        assert!(msg("from:a @nope").contains("unknown saved query @nope (at position 8)"));
+        assert!(msg("@loop-a").contains("refers to itself (@loop-a -> @loop-b -> @loop-a)"));
+        assert!(msg("@broken").starts_with("in saved query @broken: query parse error"));
     }
 }
@@ -0,0 +1,191 @@
+//! Blob transfer helpers: session URL templates, `.part` files for resumable downloads and
+//! progress events on stderr.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+/// Expand a session URL template (`downloadUrl` / `uploadUrl`, RFC 6570 level 1 as in
+/// RFC 8620 §2) with percent-encoded values.
+pub fn expand_url_template(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let var = &rest[open + 1..open + close];
+        match vars.iter().find(|(k, _)| *k == var) {
+            Some((_, v)) => out.push_str(&percent_encode(v)),
+            None => out.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// Where a download of `blob_id` is written until it completes (`report.pdf.<blobId>.part`);
+/// a leftover one is resumed with an HTTP `Range` request. The blobId in the name keeps a
+/// partial file of another blob from being resumed.
+pub fn part_path(out: &Path, blob_id: &str) -> PathBuf {
+    let mut name = out.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{blob_id}.part"));
+    out.with_file_name(name)
+}
+
+/// The first byte position from a `Content-Range: bytes 100-199/200` header.
+pub fn content_range_start(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// The complete length from a `Content-Range: bytes 100-199/200` header (`None` for `*`).
+pub fn content_range_total(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .strip_prefix("bytes ")?
+        .rsplit_once('/')?
+        .1
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Hex SHA-256 of the file at `path`, read in chunks.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Emit a progress event at most this often...
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+/// ...or after this many bytes, whichever comes first.
+const PROGRESS_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Progress of one transfer, reported as JSON lines on stderr so stdout stays a single
+/// envelope:
+///
+/// `{"event":"progress","op":"download","blobId":"...","bytes":1048576,"total":4194304,"done":false}`
+pub struct Progress {
+    op: &'static str,
+    blob_id: String,
+    enabled: bool,
+    last_at: Option<Instant>,
+    last_bytes: u64,
+}
+
+impl Progress {
+    pub fn new(op: &'static str, blob_id: &str, enabled: bool) -> Self {
+        Self {
+            op,
+            blob_id: blob_id.to_string(),
+            enabled,
+            last_at: None,
+            last_bytes: 0,
+        }
+    }
+
+    /// Report `bytes` transferred so far; throttled.
+    pub fn update(&mut self, bytes: u64, total: Option<u64>) {
+        let due = match self.last_at {
+            None => true,
+            Some(at) => {
+                at.elapsed() >= PROGRESS_INTERVAL
+                    || bytes.saturating_sub(self.last_bytes) >= PROGRESS_BYTES
+            }
+        };
+        if due {
+            self.emit(bytes, total, false);
+        }
+    }
+
+    /// Report the final size; always emitted.
+    pub fn finish(&mut self, bytes: u64, total: Option<u64>) {
+        self.emit(bytes, total, true);
+    }
+
+    fn emit(&mut self, bytes: u64, total: Option<u64>, done: bool) {
+        self.last_at = Some(Instant::now());
+        self.last_bytes = bytes;
+        if !self.enabled {
+            return;
+        }
+        let event = json!({
+            "event": "progress",
+            "op": self.op,
+            "blobId": self.blob_id,
+            "bytes": bytes,
+            "total": total,
+            "done": done,
+        });
+        let mut err = std::io::stderr().lock();
+        let _ = writeln!(err, "{event}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_session_url_templates() {
+        assert_eq!(
+            expand_url_template(
+                "https://x.example/download/{accountId}/{blobId}/{name}?type={type}",
+                &[
+                    ("accountId", "A"),
+                    ("blobId", "B1"),
+                    ("name", "Q1 report.pdf"),
+                    ("type", "application/pdf"),
+                ],
+            ),
+            "https://x.example/download/A/B1/Q1%20report.pdf?type=application%2Fpdf"
+        );
+        assert_eq!(
+            expand_url_template("/upload/{accountId}/{other}", &[("accountId", "A")]),
+            "/upload/A/{other}"
+        );
+    }
+
+    #[test]
+    fn part_files_and_content_range() {
+        assert_eq!(
+            part_path(Path::new("out/report.pdf"), "B1"),
+            PathBuf::from("out/report.pdf.B1.part")
+        );
+        assert_eq!(content_range_total("bytes 100-199/200"), Some(200));
+        assert_eq!(content_range_total("bytes 100-199/*"), None);
+        assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(content_range_start("bytes */200"), None);
+    }
+}
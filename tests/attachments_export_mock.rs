@@ -0,0 +1,173 @@
+use assert_cmd::Command;
+use serde_json::json;
+use tempfile::tempdir;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn mock_session(server: &MockServer) -> serde_json::Value {
+    json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": {}
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": {}
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri()),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    })
+}
+
+#[tokio::test]
+async fn attachments_export_dedupes_and_writes_manifest() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/query\""))
+        .and(body_string_contains("\"hasAttachment\":true"))
+        .and(body_string_contains("billing@example.com"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 2,
+                    "ids": ["m1", "m2"]
+                }, "q0"],
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {
+                            "id": "m1",
+                            "threadId": "t1",
+                            "receivedAt": "2026-03-02T09:00:00Z",
+                            "subject": "Invoice March",
+                            "from": [{"name": "Billing", "email": "billing@example.com"}],
+                            "attachments": [
+                                {"blobId": "B1", "name": "invoice.pdf", "type": "application/pdf", "size": 5}
+                            ]
+                        },
+                        {
+                            "id": "m2",
+                            "threadId": "t2",
+                            "receivedAt": "2026-03-09T09:00:00Z",
+                            "subject": "Reminder",
+                            "from": [{"name": "Billing", "email": "billing@example.com"}],
+                            "attachments": [
+                                {"blobId": "B2", "name": "../invoice.pdf", "type": "application/pdf", "size": 5},
+                                {"blobId": "B3", "name": "invoice.pdf", "type": "application/pdf", "size": 5}
+                            ]
+                        }
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // B2 is the same content as B1 under another blobId; B3 differs but has the same name.
+    for (blob, name, body) in [
+        ("B1", "invoice.pdf", "one"),
+        ("B2", "..%2Finvoice.pdf", "one"),
+        ("B3", "invoice.pdf", "two"),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(format!("/download/A/{blob}/{name}")))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("invoices");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "attachments",
+            "export",
+            "from:billing@example.com",
+            "--no-progress",
+            "--dir",
+            out.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["emails"], 2);
+    assert_eq!(v["data"]["attachments"], 3);
+    assert_eq!(v["data"]["duplicates"], 1);
+    assert_eq!(
+        v["data"]["written"],
+        json!(["invoice.pdf", "invoice (2).pdf"])
+    );
+
+    assert_eq!(std::fs::read(out.join("invoice.pdf")).expect("one"), b"one");
+    assert_eq!(
+        std::fs::read(out.join("invoice (2).pdf")).expect("two"),
+        b"two"
+    );
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.join("manifest.json")).expect("manifest"))
+            .expect("manifest json");
+    let files = manifest["files"].as_array().expect("files");
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0]["file"], "invoice.pdf");
+    let sources = files[0]["sources"].as_array().expect("sources");
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0]["emailId"], "m1");
+    assert_eq!(sources[0]["threadId"], "t1");
+    assert_eq!(sources[0]["from"]["email"], "billing@example.com");
+    assert_eq!(sources[0]["receivedAt"], "2026-03-02T09:00:00Z");
+    assert_eq!(sources[1]["blobId"], "B2");
+}
@@ -979,6 +979,28 @@ async fn drafts_send_works_against_mock_jmap() {
     let server = MockServer::start().await;
     mount_session(&server).await;
 
+    let mailbox_response = json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Mailbox/get", {
+                "accountId": "A",
+                "state": "s",
+                "list": [
+                    {"id": "mb1", "name": "Drafts", "role": "drafts"},
+                    {"id": "mb2", "name": "Sent", "role": "sent"}
+                ],
+                "notFound": []
+            }, "m0"]
+        ]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mailbox_response))
+        .mount(&server)
+        .await;
+
     let identity_response = json!({
         "sessionState": "s",
         "methodResponses": [
@@ -1019,6 +1041,9 @@ async fn drafts_send_works_against_mock_jmap() {
     Mock::given(method("POST"))
         .and(path("/jmap"))
         .and(body_string_contains("EmailSubmission/set"))
+        .and(body_string_contains(
+            "\"onSuccessUpdateEmail\":{\"#c0\":{\"keywords/$draft\":null,\"keywords/$seen\":true,\"mailboxIds/mb1\":null,\"mailboxIds/mb2\":true}}",
+        ))
         .respond_with(ResponseTemplate::new(200).set_body_json(submission_response))
         .mount(&server)
         .await;
@@ -1062,6 +1087,141 @@ async fn drafts_send_works_against_mock_jmap() {
             .and_then(|x| x.as_str()),
         Some("s1")
     );
+    assert_eq!(v["data"]["afterSend"]["action"], "move");
+    assert_eq!(v["data"]["afterSend"]["sentMailboxId"], "mb2");
+    assert_eq!(v["data"]["afterSend"]["applied"], true);
+}
+
+#[tokio::test]
+async fn drafts_send_after_send_destroy_and_update_failures() {
+    let server = MockServer::start().await;
+    mount_session(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "m1", "threadId": "t1"}],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    // No Mailbox/get is needed for destroy/keep.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"onSuccessDestroyEmail\":[\"#c0\"]"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "created": {"c0": {"id": "s1", "sendAt": "2026-02-08T00:00:00Z"}}
+                }, "s0"],
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "destroyed": ["m1"]
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(
+            "\"onSuccessUpdateEmail\":{\"#c0\":{\"keywords/$draft\":null,\"keywords/$seen\":true}}",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "created": {"c0": {"id": "s2"}}
+                }, "s0"],
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "notUpdated": {"m1": {"type": "forbidden"}}
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .env("XIN_AFTER_SEND", "destroy")
+        .args(["drafts", "send", "m1"])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["draft"]["threadId"], "t1");
+    assert_eq!(v["data"]["submission"]["sendAt"], "2026-02-08T00:00:00Z");
+    assert_eq!(v["data"]["afterSend"]["action"], "destroy");
+    assert_eq!(v["data"]["afterSend"]["applied"], true);
+
+    // The submission succeeded but the email update did not: still ok, with a warning.
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .env("XIN_AFTER_SEND", "keep")
+        .args(["drafts", "send", "m1"])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["submission"]["id"], "s2");
+    assert_eq!(v["data"]["afterSend"]["action"], "keep");
+    assert_eq!(v["data"]["afterSend"]["applied"], false);
+    assert_eq!(v["data"]["afterSend"]["error"]["type"], "forbidden");
+    let warnings = v["meta"]["warnings"].as_array().expect("warnings");
+    assert!(warnings[0].as_str().unwrap_or("").contains("forbidden"));
 }
 
 #[tokio::test]
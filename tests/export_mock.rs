@@ -0,0 +1,562 @@
+use assert_cmd::Command;
+use serde_json::json;
+use tempfile::tempdir;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn mock_session(server: &MockServer) -> serde_json::Value {
+    json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": {}
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": {}
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri()),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    })
+}
+
+async fn mock_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// `Email/get` with no ids: the current Email state.
+async fn mount_email_state(server: &MockServer, state: &str) {
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/get\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {"accountId": "A", "state": state, "list": [], "notFound": []}, "g0"]
+            ]
+        })))
+        .with_priority(10)
+        .mount(server)
+        .await;
+}
+
+async fn mount_download(server: &MockServer, blob: &str, email_id: &str, body: &str) {
+    Mock::given(method("GET"))
+        .and(path(format!("/download/A/{blob}/{email_id}.eml")))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body.to_string()))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+fn run_xin(server: &MockServer, args: &[&str]) -> serde_json::Value {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(args)
+        .output()
+        .expect("run");
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("json")
+}
+
+fn file_names(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .expect("dir")
+        .map(|e| e.expect("entry").file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn export_maildir_keeps_flags_and_resumes_incrementally() {
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("acme");
+    let out_arg = out.to_string_lossy().into_owned();
+
+    // Full export: state first, then the query pages.
+    let server = mock_server().await;
+    mount_email_state(&server, "S1").await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/query\""))
+        .and(body_string_contains("acme.example"))
+        .and(body_string_contains("\"blobId\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 2,
+                    "ids": ["m1", "m2"]
+                }, "q0"],
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S1",
+                    "list": [
+                        {
+                            "id": "m1",
+                            "blobId": "B1",
+                            "receivedAt": "2026-03-02T09:00:00Z",
+                            "keywords": {"$seen": true, "$flagged": true, "project": true},
+                            "from": [{"email": "pm@acme.example"}]
+                        },
+                        {
+                            "id": "m2",
+                            "blobId": "B2",
+                            "receivedAt": "2026-03-03T09:00:00Z",
+                            "keywords": {},
+                            "from": [{"email": "pm@acme.example"}]
+                        }
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_download(&server, "B1", "m1", "Subject: one\r\n\r\nfirst\r\n").await;
+    mount_download(&server, "B2", "m2", "Subject: two\r\n\r\nsecond\r\n").await;
+
+    let v = run_xin(
+        &server,
+        &[
+            "export",
+            "from:acme.example",
+            "--format",
+            "maildir",
+            "--out",
+            &out_arg,
+        ],
+    );
+    assert_eq!(v["data"]["written"], 2);
+    assert_eq!(v["data"]["state"], "S1");
+    assert_eq!(
+        file_names(&out.join("cur")),
+        vec!["1772442000.m1.xin:2,FS", "1772528400.m2.xin:2,"]
+    );
+    assert_eq!(
+        std::fs::read_to_string(out.join("cur/1772442000.m1.xin:2,FS")).expect("m1"),
+        "Subject: one\r\n\r\nfirst\r\n"
+    );
+    assert!(file_names(&out.join("tmp")).is_empty());
+
+    // Incremental: only matching mail created since S1.
+    let server = mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/changes\""))
+        .and(body_string_contains("\"sinceState\":\"S1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/changes", {
+                    "accountId": "A",
+                    "oldState": "S1",
+                    "newState": "S2",
+                    "hasMoreChanges": false,
+                    "created": ["m3", "m4"],
+                    "updated": ["m1"],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/get\""))
+        .and(body_string_contains("\"properties\":[\"receivedAt\"]"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S2",
+                    "list": [
+                        {"id": "m3", "receivedAt": "2026-03-04T09:00:00Z"},
+                        {"id": "m4", "receivedAt": "2026-03-04T10:00:00Z"}
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/query\""))
+        .and(body_string_contains("acme.example"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 1,
+                    "ids": ["m3"]
+                }, "q0"],
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 0,
+                    "ids": []
+                }, "q1"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/get\""))
+        .and(body_string_contains("\"ids\":[\"m3\"]"))
+        .and(body_string_contains("\"blobId\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S2",
+                    "list": [{
+                        "id": "m3",
+                        "blobId": "B3",
+                        "receivedAt": "2026-03-04T09:00:00Z",
+                        "keywords": {"$answered": true, "$seen": true},
+                        "from": [{"email": "pm@acme.example"}]
+                    }],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_download(&server, "B3", "m3", "Subject: three\r\n\r\nthird\r\n").await;
+
+    let v = run_xin(
+        &server,
+        &[
+            "export",
+            "from:acme.example",
+            "--format",
+            "maildir",
+            "--out",
+            &out_arg,
+            "--incremental",
+        ],
+    );
+    assert_eq!(v["data"]["since"], "S1");
+    assert_eq!(v["data"]["state"], "S2");
+    assert_eq!(v["data"]["written"], 1);
+    assert_eq!(
+        file_names(&out.join("cur")),
+        vec![
+            "1772442000.m1.xin:2,FS",
+            "1772528400.m2.xin:2,",
+            "1772614800.m3.xin:2,RS"
+        ]
+    );
+    let saved: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.join(".xin-export.json")).expect("state file"))
+            .expect("state json");
+    assert_eq!(saved, json!({"format": "maildir", "state": "S2"}));
+}
+
+#[tokio::test]
+async fn export_mbox_quotes_from_lines() {
+    let server = mock_server().await;
+    mount_email_state(&server, "S1").await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/query\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 2,
+                    "ids": ["m1", "m2"]
+                }, "q0"],
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S1",
+                    "list": [
+                        {
+                            "id": "m1",
+                            "blobId": "B1",
+                            "receivedAt": "2026-03-02T09:00:00Z",
+                            "keywords": {"$seen": true},
+                            "from": [{"name": "PM", "email": "pm@acme.example"}]
+                        },
+                        {
+                            "id": "m2",
+                            "blobId": "B2",
+                            "receivedAt": "2026-03-03T09:00:00Z",
+                            "keywords": {},
+                            "from": []
+                        }
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_download(
+        &server,
+        "B1",
+        "m1",
+        "Subject: one\r\n\r\nFrom the desk of PM\r\n>From before\r\n",
+    )
+    .await;
+    mount_download(&server, "B2", "m2", "Subject: two\r\n\r\nsecond").await;
+
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("acme.mbox");
+    // A full export replaces whatever was there.
+    std::fs::write(&out, "stale\n").expect("write");
+
+    let v = run_xin(
+        &server,
+        &[
+            "export",
+            "--format",
+            "mbox",
+            "--out",
+            out.to_string_lossy().as_ref(),
+        ],
+    );
+    assert_eq!(v["data"]["written"], 2);
+    assert_eq!(
+        std::fs::read_to_string(&out).expect("mbox"),
+        "From pm@acme.example Mon Mar  2 09:00:00 2026\n\
+         Subject: one\n\n>From the desk of PM\n>>From before\n\n\
+         From MAILER-DAEMON Tue Mar  3 09:00:00 2026\n\
+         Subject: two\n\nsecond\n\n"
+    );
+    // The per-message temporary files are gone.
+    assert_eq!(
+        file_names(dir.path()),
+        vec!["acme.mbox", "acme.mbox.xin-export.json"]
+    );
+}
+
+/// `Email/changes` from S1 to S2 creating m1 and m2, both matching the query.
+async fn mount_mbox_changes(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/changes\""))
+        .and(body_string_contains("\"sinceState\":\"S1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/changes", {
+                    "accountId": "A",
+                    "oldState": "S1",
+                    "newState": "S2",
+                    "hasMoreChanges": false,
+                    "created": ["m1", "m2"],
+                    "updated": [],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/get\""))
+        .and(body_string_contains("\"properties\":[\"receivedAt\"]"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S2",
+                    "list": [
+                        {"id": "m1", "receivedAt": "2026-03-02T09:00:00Z"},
+                        {"id": "m2", "receivedAt": "2026-03-03T09:00:00Z"}
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/query\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 1,
+                    "ids": ["m1"]
+                }, "q0"],
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "total": 1,
+                    "ids": ["m2"]
+                }, "q1"]
+            ]
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/get\""))
+        .and(body_string_contains("\"blobId\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S2",
+                    "list": [
+                        {
+                            "id": "m1",
+                            "blobId": "B1",
+                            "receivedAt": "2026-03-02T09:00:00Z",
+                            "keywords": {},
+                            "from": [{"email": "pm@acme.example"}]
+                        },
+                        {
+                            "id": "m2",
+                            "blobId": "B2",
+                            "receivedAt": "2026-03-03T09:00:00Z",
+                            "keywords": {},
+                            "from": [{"email": "pm@acme.example"}]
+                        }
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn export_mbox_retry_does_not_duplicate_appended_messages() {
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("acme.mbox");
+    let out_arg = out.to_string_lossy().into_owned();
+    let state_file = dir.path().join("acme.mbox.xin-export.json");
+    std::fs::write(&state_file, r#"{"format": "mbox", "state": "S1"}"#).expect("write");
+    let args = [
+        "export",
+        "--format",
+        "mbox",
+        "--out",
+        &out_arg,
+        "--incremental",
+    ];
+
+    // m2 fails: the state stays at S1 and records m1 as appended.
+    let server = mock_server().await;
+    mount_mbox_changes(&server).await;
+    mount_download(&server, "B1", "m1", "Subject: one\r\n\r\nfirst\r\n").await;
+    Mock::given(method("GET"))
+        .and(path("/download/A/B2/m2.eml"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let v = run_xin(&server, &args);
+    assert_eq!(v["data"]["written"], 1);
+    assert_eq!(v["data"]["failed"][0]["emailId"], "m2");
+    let saved: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&state_file).expect("state file"))
+            .expect("state json");
+    assert_eq!(
+        saved,
+        json!({"format": "mbox", "state": "S1", "exported": ["m1"]})
+    );
+
+    // The retry appends only m2.
+    let server = mock_server().await;
+    mount_mbox_changes(&server).await;
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/m1.eml"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes("unused"))
+        .expect(0)
+        .mount(&server)
+        .await;
+    mount_download(&server, "B2", "m2", "Subject: two\r\n\r\nsecond\r\n").await;
+
+    let v = run_xin(&server, &args);
+    assert_eq!(v["data"]["written"], 1);
+    assert_eq!(v["data"]["alreadyExported"], 1);
+    assert_eq!(v["data"]["state"], "S2");
+    assert_eq!(
+        std::fs::read_to_string(&out).expect("mbox"),
+        "From pm@acme.example Mon Mar  2 09:00:00 2026\n\
+         Subject: one\n\nfirst\n\n\
+         From pm@acme.example Tue Mar  3 09:00:00 2026\n\
+         Subject: two\n\nsecond\n\n"
+    );
+    let saved: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&state_file).expect("state file"))
+            .expect("state json");
+    assert_eq!(saved, json!({"format": "mbox", "state": "S2"}));
+}
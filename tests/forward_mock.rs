@@ -0,0 +1,459 @@
+use assert_cmd::Command;
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn mock_session(server: &MockServer) -> serde_json::Value {
+    json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": {}
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": {}
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri()),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    })
+}
+
+/// Session, Drafts/Sent mailboxes, the identities and a successful EmailSubmission/set.
+async fn mount_common_with_identities(server: &MockServer, identities: serde_json::Value) {
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(server)))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {"id": "mb1", "name": "Drafts", "role": "drafts"},
+                        {"id": "mb2", "name": "Sent", "role": "sent"}
+                    ],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": identities,
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "undoStatus": "final"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    // Forwarding reuses blobIds; nothing may be uploaded.
+    Mock::given(method("POST"))
+        .and(path_regex("^/upload/.*"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(server)
+        .await;
+}
+
+async fn mount_common(server: &MockServer) {
+    mount_common_with_identities(
+        server,
+        json!([{"id": "i1", "name": "Me", "email": "me@example.com"}]),
+    )
+    .await;
+}
+
+async fn mount_original(server: &MockServer, email: serde_json::Value) {
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [email],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+fn email_set_created() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Email/set", {
+                "accountId": "A",
+                "oldState": "s",
+                "newState": "s",
+                "created": {"c0": {"id": "m2", "threadId": "t2"}}
+            }, "e0"]
+        ]
+    }))
+}
+
+fn original_email() -> serde_json::Value {
+    json!({
+        "id": "m1",
+        "threadId": "t1",
+        "blobId": "b-msg",
+        "size": 1234,
+        "receivedAt": "2026-03-03T09:05:00Z",
+        "sentAt": "2026-03-03T09:05:00Z",
+        "subject": "Hello",
+        "messageId": ["orig@example.com"],
+        "from": [{"name": "Alice", "email": "alice@example.com"}],
+        "to": [{"name": null, "email": "me@example.com"}],
+        "cc": [],
+        "bcc": [],
+        "preview": "Hello there",
+        "hasAttachment": true,
+        "mailboxIds": {"inbox": true},
+        "keywords": {"$seen": true},
+        "textBody": [{"partId": "1", "blobId": "b-text", "size": 24, "type": "text/plain"}],
+        "htmlBody": [{"partId": "1", "blobId": "b-text", "size": 24, "type": "text/plain"}],
+        "attachments": [{
+            "partId": "2",
+            "blobId": "b-att",
+            "size": 10,
+            "name": "a.pdf",
+            "type": "application/pdf",
+            "disposition": "attachment"
+        }],
+        "bodyValues": {
+            "1": {"value": "Hello there\nSecond line\n", "isTruncated": false, "isEncodingProblem": false}
+        }
+    })
+}
+
+#[tokio::test]
+async fn reply_quote_appends_attribution_and_quoted_text() {
+    let server = MockServer::start().await;
+    mount_common(&server).await;
+    mount_original(&server, original_email()).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains(
+            "Thanks!\\n\\nOn Tue, 3 Mar 2026 09:05 UTC, Alice <alice@example.com> wrote:\\n> Hello there\\n> Second line\\n",
+        ))
+        .respond_with(email_set_created())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["reply", "m1", "--quote", "--text", "Thanks!"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["command"], "reply");
+    assert_eq!(v["data"]["draft"]["emailId"], "m2");
+}
+
+#[tokio::test]
+async fn forward_inline_reuses_attachment_blobs() {
+    let server = MockServer::start().await;
+    mount_common(&server).await;
+    mount_original(&server, original_email()).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("\"subject\":\"Fwd: Hello\""))
+        .and(body_string_contains(
+            "FYI\\n\\n---------- Forwarded message ---------\\nFrom: Alice <alice@example.com>\\nDate: Tue, 3 Mar 2026 09:05 UTC\\nSubject: Hello\\nTo: me@example.com\\n\\nHello there\\nSecond line\\n",
+        ))
+        .and(body_string_contains("\"blobId\":\"b-att\""))
+        .and(body_string_contains("multipart/mixed"))
+        .respond_with(email_set_created())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["forward", "m1", "--to", "bob@example.com", "--text", "FYI"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["command"], "forward");
+    assert_eq!(v["data"]["forwarded"]["emailId"], "m1");
+    assert_eq!(v["data"]["forwarded"]["mode"], "inline");
+    assert_eq!(v["data"]["forwarded"]["blobIds"], json!(["b-att"]));
+    assert_eq!(v["data"]["uploaded"], json!([]));
+}
+
+#[tokio::test]
+async fn forward_inline_keeps_images_the_html_references() {
+    let server = MockServer::start().await;
+    mount_common(&server).await;
+    let mut original = original_email();
+    original["htmlBody"] =
+        json!([{"partId": "3", "blobId": "b-html", "size": 40, "type": "text/html"}]);
+    original["bodyValues"]["3"] = json!({
+        "value": "<p>Logo: <img src=\"cid:logo@example.com\"></p>",
+        "isTruncated": false,
+        "isEncodingProblem": false
+    });
+    original["attachments"] = json!([
+        {
+            "partId": "4",
+            "blobId": "b-logo",
+            "size": 10,
+            "name": "logo.png",
+            "type": "image/png",
+            "disposition": "inline",
+            "cid": "logo@example.com"
+        },
+        {
+            "partId": "2",
+            "blobId": "b-att",
+            "size": 10,
+            "name": "a.pdf",
+            "type": "application/pdf",
+            "disposition": "attachment",
+            "cid": "unused@example.com"
+        }
+    ]);
+    mount_original(&server, original).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("multipart/related"))
+        .and(body_string_contains("\"cid\":\"logo@example.com\""))
+        .respond_with(email_set_created())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["forward", "m1", "--to", "bob@example.com"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    // Only the referenced image is inline; the PDF stays an attachment.
+    let requests = server.received_requests().await.expect("requests");
+    let set: serde_json::Value = requests
+        .iter()
+        .filter_map(|r| serde_json::from_slice::<serde_json::Value>(&r.body).ok())
+        .find(|b| b.to_string().contains("\"Email/set\""))
+        .expect("Email/set request");
+    let body = set.to_string();
+    assert!(!body.contains("unused@example.com"));
+    assert!(body.contains("\"disposition\":\"attachment\""));
+}
+
+#[tokio::test]
+async fn forward_as_attachment_attaches_the_original_message() {
+    let server = MockServer::start().await;
+    mount_common(&server).await;
+    mount_original(&server, original_email()).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("\"blobId\":\"b-msg\""))
+        .and(body_string_contains("message/rfc822"))
+        .and(body_string_contains("\"name\":\"Hello.eml\""))
+        .respond_with(email_set_created())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "forward",
+            "m1",
+            "--to",
+            "bob@example.com",
+            "--as-attachment",
+            "--subject",
+            "See attached",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["forwarded"]["mode"], "attachment");
+    assert_eq!(v["data"]["forwarded"]["blobIds"], json!(["b-msg"]));
+}
+
+fn alias_identities() -> serde_json::Value {
+    json!([
+        {"id": "i1", "name": "Me", "email": "me@example.com"},
+        {"id": "i2", "name": "Sales", "email": "sales@example.com"},
+        {"id": "i3", "name": "Corp", "email": "*@corp.example"}
+    ])
+}
+
+#[tokio::test]
+async fn reply_picks_the_identity_the_original_was_sent_to() {
+    let server = MockServer::start().await;
+    mount_common_with_identities(&server, alias_identities()).await;
+
+    let mut original = original_email();
+    original["to"] = json!([{"name": null, "email": "sales@example.com"}]);
+    mount_original(&server, original).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("\"email\":\"sales@example.com\""))
+        .respond_with(email_set_created())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["reply", "m1", "--text", "On it"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["identity"]["id"], "i2");
+    assert_eq!(v["data"]["identity"]["rule"], "exact");
+    assert_eq!(v["data"]["identity"]["matched"]["header"], "to");
+}
+
+#[tokio::test]
+async fn forward_uses_a_wildcard_identity_for_the_delivered_to_alias() {
+    let server = MockServer::start().await;
+    mount_common_with_identities(&server, alias_identities()).await;
+
+    let mut original = original_email();
+    original["to"] = json!([{"name": null, "email": "team@lists.example"}]);
+    original["header:Delivered-To:asText:all"] = json!(["ops@corp.example"]);
+    mount_original(&server, original).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("\"email\":\"ops@corp.example\""))
+        .respond_with(email_set_created())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["forward", "m1", "--to", "bob@example.com"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["identity"]["id"], "i3");
+    assert_eq!(v["data"]["identity"]["email"], "ops@corp.example");
+    assert_eq!(v["data"]["identity"]["rule"], "wildcard");
+    assert_eq!(v["data"]["identity"]["matched"]["header"], "deliveredTo");
+}
@@ -691,3 +691,109 @@ async fn history_page_token_since_mismatch_is_usage_error_and_does_not_hit_chang
         .count();
     assert_eq!(changes_posts, 1);
 }
+
+#[tokio::test]
+async fn history_types_mailbox_returns_typed_changes() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    let changes = json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Mailbox/changes", {
+                "accountId": "A",
+                "oldState": "M0",
+                "newState": "M1",
+                "hasMoreChanges": true,
+                "created": [],
+                "updated": ["mb_inbox"],
+                "destroyed": ["mb_old"],
+                "updatedProperties": ["totalEmails", "unreadEmails"]
+            }, "c0"]
+        ]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/changes"))
+        .and(body_string_contains("\"sinceState\":\"M0\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(changes))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["history", "--types", "mailbox", "--since", "M0"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        v.pointer("/data/types/0").and_then(|v| v.as_str()),
+        Some("mailbox")
+    );
+    assert_eq!(
+        v.pointer("/data/sinceStates/mailbox")
+            .and_then(|v| v.as_str()),
+        Some("M0")
+    );
+    assert_eq!(
+        v.pointer("/data/newStates/mailbox")
+            .and_then(|v| v.as_str()),
+        Some("M1")
+    );
+    assert_eq!(
+        v.pointer("/data/changes/mailbox/updated/0")
+            .and_then(|v| v.as_str()),
+        Some("mb_inbox")
+    );
+    assert_eq!(
+        v.pointer("/data/changes/mailbox/updatedProperties/0")
+            .and_then(|v| v.as_str()),
+        Some("totalEmails")
+    );
+    assert!(
+        v.pointer("/meta/nextPage")
+            .and_then(|v| v.as_str())
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn history_since_with_multiple_types_is_usage_error() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["history", "--types", "email,mailbox", "--since", "S0"])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(
+        v.pointer("/error/kind").and_then(|v| v.as_str()),
+        Some("xinUsageError")
+    );
+}
@@ -0,0 +1,314 @@
+use assert_cmd::Command;
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn mock_session(server: &MockServer) -> serde_json::Value {
+    json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": {}
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": {}
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri()),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    })
+}
+
+/// Session plus an Identity/get returning `identities`.
+async fn mount_identities(server: &MockServer, identities: serde_json::Value) {
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(server)))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": identities,
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+fn identity_set_response(body: serde_json::Value) -> ResponseTemplate {
+    let mut args = json!({"accountId": "A", "oldState": "s", "newState": "s2"});
+    for (k, v) in body.as_object().expect("object") {
+        args[k] = v.clone();
+    }
+    ResponseTemplate::new(200).set_body_json(json!({
+        "sessionState": "s",
+        "methodResponses": [["Identity/set", args, "s0"]]
+    }))
+}
+
+fn run(server: &MockServer, args: &[&str]) -> (std::process::Output, serde_json::Value) {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(args)
+        .output()
+        .expect("run");
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    (output, v)
+}
+
+#[tokio::test]
+async fn identities_create_sends_signature_and_addresses() {
+    let server = MockServer::start().await;
+    mount_identities(&server, json!([])).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/set"))
+        .and(body_string_contains("\"textSignature\":\"Alice\\nACME\""))
+        .and(body_string_contains(
+            "\"replyTo\":[{\"email\":\"team@example.com\",\"name\":\"Team\"}]",
+        ))
+        .respond_with(identity_set_response(json!({
+            "created": {"c0": {"id": "i9", "mayDelete": true}}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (output, v) = run(
+        &server,
+        &[
+            "identities",
+            "create",
+            "--email",
+            "alice@example.com",
+            "--name",
+            "Alice",
+            "--reply-to",
+            "Team <team@example.com>",
+            "--text-signature",
+            "Alice\nACME",
+        ],
+    );
+
+    assert!(output.status.success(), "stdout:\n{v}");
+    assert_eq!(v["command"], "identities.create");
+    assert_eq!(v["data"]["identity"]["id"], "i9");
+    assert_eq!(v["data"]["identity"]["email"], "alice@example.com");
+    assert_eq!(v["data"]["identity"]["mayDelete"], true);
+}
+
+#[tokio::test]
+async fn identities_update_can_clear_reply_to() {
+    let server = MockServer::start().await;
+    mount_identities(
+        &server,
+        json!([{
+            "id": "i1",
+            "name": "Me",
+            "email": "me@example.com",
+            "replyTo": null,
+            "bcc": null,
+            "textSignature": "",
+            "htmlSignature": "",
+            "mayDelete": true
+        }]),
+    )
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/set"))
+        .and(body_string_contains("\"i1\":{\"replyTo\":null}"))
+        .respond_with(identity_set_response(json!({"updated": {"i1": null}})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (output, v) = run(
+        &server,
+        &["identities", "update", "me@example.com", "--clear-reply-to"],
+    );
+
+    assert!(output.status.success(), "stdout:\n{v}");
+    assert_eq!(v["data"]["identity"]["id"], "i1");
+    assert_eq!(v["data"]["updated"], json!(["replyTo"]));
+}
+
+#[tokio::test]
+async fn identities_update_reports_set_error() {
+    let server = MockServer::start().await;
+    mount_identities(
+        &server,
+        json!([{"id": "i1", "name": "Me", "email": "me@example.com"}]),
+    )
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/set"))
+        .respond_with(identity_set_response(json!({
+            "notUpdated": {"i1": {"type": "forbidden", "description": "not allowed"}}
+        })))
+        .mount(&server)
+        .await;
+
+    let (output, v) = run(&server, &["identities", "update", "i1", "--name", "New"]);
+
+    assert!(!output.status.success());
+    assert_eq!(v["ok"], false);
+    assert_eq!(v["error"]["kind"], "jmapRequestError");
+}
+
+#[tokio::test]
+async fn identities_delete_requires_force() {
+    let server = MockServer::start().await;
+    mount_identities(
+        &server,
+        json!([{"id": "i1", "name": "Me", "email": "me@example.com"}]),
+    )
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/set"))
+        .and(body_string_contains("\"destroy\":[\"i1\"]"))
+        .respond_with(identity_set_response(json!({"destroyed": ["i1"]})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (output, v) = run(&server, &["identities", "delete", "i1"]);
+    assert!(!output.status.success());
+    assert_eq!(v["error"]["kind"], "xinUsageError");
+
+    let (output, v) = run(&server, &["--force", "identities", "delete", "i1"]);
+    assert!(output.status.success(), "stdout:\n{v}");
+    assert_eq!(v["data"]["deleted"], "i1");
+}
+
+#[tokio::test]
+async fn send_signature_appends_identity_signature() {
+    let server = MockServer::start().await;
+    mount_identities(
+        &server,
+        json!([{
+            "id": "i1",
+            "name": "Me",
+            "email": "me@example.com",
+            "textSignature": "Me\nACME Inc.",
+            "htmlSignature": ""
+        }]),
+    )
+    .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {"id": "mb1", "name": "Drafts", "role": "drafts"},
+                        {"id": "mb2", "name": "Sent", "role": "sent"}
+                    ],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("Hello\\n\\n-- \\nMe\\nACME Inc.\\n"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m2", "threadId": "t2"}}
+                }, "e0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "undoStatus": "final"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let (output, v) = run(
+        &server,
+        &[
+            "send",
+            "--to",
+            "bob@example.com",
+            "--subject",
+            "Hi",
+            "--text",
+            "Hello",
+            "--signature",
+        ],
+    );
+
+    assert!(output.status.success(), "stdout:\n{v}");
+    assert_eq!(v["command"], "send");
+}
@@ -0,0 +1,375 @@
+use assert_cmd::Command;
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn mock_session(server: &MockServer) -> serde_json::Value {
+    json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": {}
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": {}
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri()),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    })
+}
+
+/// Session, Drafts/Sent mailboxes and one identity.
+async fn mount_common(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(server)))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {"id": "mb1", "name": "Drafts", "role": "drafts"},
+                        {"id": "mb2", "name": "Sent", "role": "sent"}
+                    ],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+fn email_created(id: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Email/set", {
+                "accountId": "A",
+                "oldState": "s",
+                "newState": "s",
+                "created": {"c0": {"id": id, "threadId": format!("t-{id}")}}
+            }, "e0"]
+        ]
+    }))
+}
+
+fn write_fixtures(dir: &std::path::Path) -> (String, String) {
+    let template = dir.join("notice.tmpl");
+    std::fs::write(
+        &template,
+        "To: {{name}} <{{email}}>\nSubject: Invoice {{invoice}}\n\nHi {{name}},\ninvoice {{invoice}} is due.\n",
+    )
+    .expect("write template");
+    let data = dir.join("rows.csv");
+    std::fs::write(
+        &data,
+        "name,email,invoice\nAlice,alice@example.com,100\n\"Bob, Jr.\",bob@example.com,101\n",
+    )
+    .expect("write data");
+    (
+        format!("@{}", template.display()),
+        format!("@{}", data.display()),
+    )
+}
+
+#[test]
+fn merge_dry_run_renders_every_row_without_connecting() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let (template, data) = write_fixtures(tmp.path());
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", "http://127.0.0.1:9")
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "--dry-run",
+            "send",
+            "--template",
+            &template,
+            "--data",
+            &data,
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["dryRun"], true);
+    assert_eq!(v["data"]["summary"]["total"], 2);
+    let rows = v["data"]["results"].as_array().expect("results");
+    assert_eq!(rows[0]["subject"], "Invoice 100");
+    assert_eq!(rows[0]["text"], "Hi Alice,\ninvoice 100 is due.\n");
+    assert_eq!(rows[1]["to"], json!(["Bob, Jr. <bob@example.com>"]));
+}
+
+#[test]
+fn merge_ndjson_prints_only_json_lines() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let (template, data) = write_fixtures(tmp.path());
+    let run = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+            .env("XIN_BASE_URL", "http://127.0.0.1:9")
+            .env("XIN_TOKEN", "test-token")
+            .args([
+                "--dry-run",
+                "send",
+                "--template",
+                &template,
+                "--data",
+                &data,
+                "--ndjson",
+            ])
+            .args(extra)
+            .output()
+            .expect("run")
+    };
+    let lines = |stdout: &[u8]| -> Vec<serde_json::Value> {
+        String::from_utf8_lossy(stdout)
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap_or_else(|e| panic!("{e}: {l}")))
+            .collect()
+    };
+
+    let output = run(&[]);
+    assert!(output.status.success());
+    let rows = lines(&output.stdout);
+    assert_eq!(rows.len(), 2, "one line per row and no envelope");
+    assert_eq!(rows[0]["row"], 1);
+    assert_eq!(rows[1]["subject"], "Invoice 101");
+
+    let output = run(&["--rows", "3"]);
+    assert!(!output.status.success());
+    let rows = lines(&output.stdout);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["type"], "error");
+    assert_eq!(rows[0]["error"]["kind"], "xinUsageError");
+}
+
+#[test]
+fn merge_ndjson_exits_nonzero_when_a_row_fails() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let (template, _) = write_fixtures(tmp.path());
+    let data = tmp.path().join("rows.jsonl");
+    std::fs::write(
+        &data,
+        "{\"name\":\"Alice\",\"email\":\"alice@example.com\",\"invoice\":100}\n{\"name\":\"Bob\",\"email\":\"bob@example.com\"}\n",
+    )
+    .expect("write data");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", "http://127.0.0.1:9")
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "--dry-run",
+            "send",
+            "--template",
+            &template,
+            "--data",
+            &format!("@{}", data.display()),
+            "--ndjson",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let lines: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("json line"))
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["ok"], true);
+    assert_eq!(lines[1]["ok"], false);
+    assert_eq!(lines[2]["type"], "error");
+    assert_eq!(lines[2]["error"]["kind"], "xinMergeFailed");
+}
+
+#[test]
+fn merge_missing_variable_fails_before_sending() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let (template, _) = write_fixtures(tmp.path());
+    let data = tmp.path().join("rows.jsonl");
+    std::fs::write(
+        &data,
+        "{\"name\":\"Alice\",\"email\":\"alice@example.com\",\"invoice\":100}\n{\"name\":\"Bob\",\"email\":\"bob@example.com\"}\n",
+    )
+    .expect("write data");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", "http://127.0.0.1:9")
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--template",
+            &template,
+            "--data",
+            &format!("@{}", data.display()),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinUsageError");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("row 2: missing variable: invoice")
+    );
+}
+
+#[tokio::test]
+async fn merge_sends_each_row_and_reports_failed_rows() {
+    let server = MockServer::start().await;
+    mount_common(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("alice@example.com"))
+        .and(body_string_contains("Hi Alice,\\ninvoice 100 is due.\\n"))
+        .respond_with(email_created("m1"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("bob@example.com"))
+        .respond_with(email_created("m2"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .and(body_string_contains("\"m1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "undoStatus": "final"}}
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .and(body_string_contains("\"m2\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "notCreated": {"c0": {"type": "forbiddenToSend", "description": "quota"}}
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempfile::tempdir().expect("tmp");
+    let (template, data) = write_fixtures(tmp.path());
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--template",
+            &template,
+            "--data",
+            &data,
+            "--delay-ms",
+            "0",
+        ])
+        .output()
+        .expect("run");
+
+    // A failed row fails the command, but every row is still reported.
+    assert!(
+        !output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["ok"], false);
+    assert_eq!(v["error"]["kind"], "xinMergeFailed");
+    assert_eq!(v["data"]["summary"]["succeeded"], 1);
+    assert_eq!(v["data"]["summary"]["failed"], 1);
+    assert_eq!(v["data"]["retryRows"], "2");
+
+    let rows = v["data"]["results"].as_array().expect("results");
+    assert_eq!(rows[0]["ok"], true);
+    assert_eq!(rows[0]["submission"]["id"], "s1");
+    assert_eq!(rows[1]["ok"], false);
+    assert_eq!(rows[1]["draft"]["emailId"], "m2");
+    assert_eq!(rows[1]["error"]["kind"], "jmapRequestError");
+}
@@ -0,0 +1,242 @@
+use assert_cmd::Command;
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn xin(cfg_path: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("xin"));
+    cmd.env("XIN_CONFIG_PATH", cfg_path);
+    cmd
+}
+
+fn json_from_stdout(out: &std::process::Output) -> serde_json::Value {
+    let s = String::from_utf8_lossy(&out.stdout);
+    serde_json::from_str(&s).expect("stdout json")
+}
+
+async fn mount_minimal_jmap_session(server: &MockServer) {
+    let session = json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": {}
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": {}
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!(
+            "{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}",
+            server.uri()
+        ),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    });
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(session))
+        .mount(server)
+        .await;
+}
+
+#[test]
+fn queries_set_list_delete_round_trip_through_config() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg_path = tmp.path().join("xin-config.json");
+
+    xin(&cfg_path).args(["config", "init"]).assert().success();
+
+    let out = xin(&cfg_path)
+        .args([
+            "queries",
+            "set",
+            "invoices",
+            "from:billing OR subject:invoice",
+        ])
+        .output()
+        .expect("set");
+    assert!(out.status.success());
+    let v = json_from_stdout(&out);
+    assert_eq!(v["command"], "queries.set");
+    assert_eq!(v["data"]["scope"], "global");
+    assert_eq!(v["data"]["replaced"], false);
+
+    // Per-account, referencing the global one.
+    let out = xin(&cfg_path)
+        .args([
+            "--account",
+            "fastmail",
+            "queries",
+            "set",
+            "unfiled",
+            "@invoices -keyword:$filed",
+        ])
+        .output()
+        .expect("set account");
+    assert!(out.status.success());
+    assert_eq!(json_from_stdout(&out)["data"]["account"], "fastmail");
+
+    let cfg: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&cfg_path).expect("read cfg"))
+            .expect("cfg json");
+    assert_eq!(
+        cfg["queries"]["invoices"],
+        "from:billing OR subject:invoice"
+    );
+    assert_eq!(
+        cfg["accounts"]["fastmail"]["queries"]["unfiled"],
+        "@invoices -keyword:$filed"
+    );
+
+    let out = xin(&cfg_path)
+        .args(["queries", "list"])
+        .output()
+        .expect("list");
+    let v = json_from_stdout(&out);
+    let names: Vec<&str> = v["data"]["queries"]
+        .as_array()
+        .expect("queries")
+        .iter()
+        .map(|q| q["name"].as_str().unwrap_or(""))
+        .collect();
+    assert_eq!(names, vec!["invoices", "unfiled"]);
+
+    // Invalid queries, unknown references and cycles are rejected before writing.
+    for (name, q) in [
+        ("bad", "(from:x"),
+        ("dangling", "@nope"),
+        ("invoices", "@unfiled"),
+        ("bad name", "from:x"),
+    ] {
+        let out = xin(&cfg_path)
+            .args(["--account", "fastmail", "queries", "set", name, q])
+            .output()
+            .expect("set invalid");
+        assert!(!out.status.success(), "{name}: {q}");
+        assert_eq!(json_from_stdout(&out)["error"]["kind"], "xinUsageError");
+    }
+
+    let out = xin(&cfg_path)
+        .args(["queries", "delete", "invoices"])
+        .output()
+        .expect("delete");
+    assert!(out.status.success());
+    let out = xin(&cfg_path)
+        .args(["queries", "delete", "invoices"])
+        .output()
+        .expect("delete again");
+    assert!(!out.status.success());
+}
+
+#[tokio::test]
+async fn search_expands_saved_queries_and_composes_with_terms() {
+    let server = MockServer::start().await;
+    mount_minimal_jmap_session(&server).await;
+
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg_path = tmp.path().join("xin-config.json");
+    std::fs::write(
+        &cfg_path,
+        serde_json::to_string(&json!({
+            "defaults": {"account": "main"},
+            "queries": {"invoices": "from:billing OR subject:invoice"},
+            "accounts": {
+                "main": {
+                    "baseUrl": server.uri(),
+                    "auth": {"type": "bearer", "token": "test-token"},
+                    "queries": {"invoices": "from:vendor"}
+                }
+            }
+        }))
+        .expect("cfg"),
+    )
+    .expect("write cfg");
+
+    // The account's `invoices` overrides the global one.
+    let expected_filter = json!({
+        "operator": "AND",
+        "conditions": [{"from": "vendor"}, {"notKeyword": "$seen"}]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(
+            serde_json::to_string(&expected_filter).expect("filter json"),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "s",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "ids": [],
+                    "total": 0
+                }, "s0"],
+                ["Email/get", {"accountId": "A", "state": "s", "list": [], "notFound": []}, "s1"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let out = xin(&cfg_path)
+        .args(["search", "@invoices seen:false"])
+        .output()
+        .expect("search");
+    assert!(
+        out.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+
+    let out = xin(&cfg_path)
+        .args(["search", "@invoices seen:false", "--explain"])
+        .output()
+        .expect("explain");
+    let v = json_from_stdout(&out);
+    assert_eq!(
+        v["data"]["explain"]["savedQueries"],
+        json!([{"name": "invoices", "query": "from:vendor"}])
+    );
+
+    let out = xin(&cfg_path)
+        .args(["search", "@missing"])
+        .output()
+        .expect("unknown");
+    assert!(!out.status.success());
+    let v = json_from_stdout(&out);
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("unknown saved query @missing")
+    );
+}
@@ -1,7 +1,7 @@
 use assert_cmd::Command;
 use serde_json::json;
 use tempfile::tempdir;
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 fn mock_session(server: &MockServer) -> serde_json::Value {
@@ -99,3 +99,211 @@ async fn attachment_download_writes_file() {
         Some("B1")
     );
 }
+
+#[tokio::test]
+async fn attachment_download_resumes_partial_file() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/a.bin"))
+        .and(header("range", "bytes=3-"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .insert_header("content-range", "bytes 3-4/5")
+                .set_body_bytes(b"lo"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("a.bin");
+    std::fs::write(dir.path().join("a.bin.B1.part"), b"hel").expect("write part");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "attachment",
+            "m1",
+            "B1",
+            "--name",
+            "a.bin",
+            "--out",
+            out.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(std::fs::read(&out).expect("read out"), b"hello");
+    assert!(!dir.path().join("a.bin.B1.part").exists());
+
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["bytes"], 5);
+    assert_eq!(v["data"]["resumedFrom"], 3);
+
+    // Progress events are JSON lines on stderr, ending with `done`.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let last: serde_json::Value =
+        serde_json::from_str(stderr.lines().last().expect("progress")).expect("event");
+    assert_eq!(last["event"], "progress");
+    assert_eq!(last["bytes"], 5);
+    assert_eq!(last["total"], 5);
+    assert_eq!(last["done"], true);
+}
+
+#[tokio::test]
+async fn attachment_download_restarts_when_range_is_ignored() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/none"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello"))
+        .mount(&server)
+        .await;
+
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("a.bin");
+    std::fs::write(dir.path().join("a.bin.B1.part"), b"stale data").expect("write part");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "attachment",
+            "m1",
+            "B1",
+            "--no-progress",
+            "--out",
+            out.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&out).expect("read out"), b"hello");
+    assert!(output.stderr.is_empty());
+
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["resumedFrom"], 0);
+}
+
+#[tokio::test]
+async fn attachment_download_restarts_when_range_starts_elsewhere() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    // The server answers the range request from byte 0: appending it would corrupt the file.
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/none"))
+        .and(header("range", "bytes=3-"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .insert_header("content-range", "bytes 0-4/5")
+                .set_body_bytes(b"hello"),
+        )
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/none"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("a.bin");
+    std::fs::write(dir.path().join("a.bin.B1.part"), b"hel").expect("write part");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "attachment",
+            "m1",
+            "B1",
+            "--no-progress",
+            "--out",
+            out.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&out).expect("read out"), b"hello");
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["resumedFrom"], 0);
+}
+
+#[tokio::test]
+async fn attachment_download_ignores_partial_file_of_another_blob() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/none"))
+        .and(header("range", "bytes=3-"))
+        .respond_with(ResponseTemplate::new(500))
+        .with_priority(1)
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/download/A/B1/none"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"hello"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempdir().expect("tmp");
+    let out = dir.path().join("a.bin");
+    std::fs::write(dir.path().join("a.bin.B0.part"), b"old").expect("write part");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "attachment",
+            "m1",
+            "B1",
+            "--no-progress",
+            "--out",
+            out.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    assert_eq!(std::fs::read(&out).expect("read out"), b"hello");
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["resumedFrom"], 0);
+}
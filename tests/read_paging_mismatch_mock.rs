@@ -170,3 +170,98 @@ async fn page_token_mismatch_is_a_usage_error_and_does_not_hit_query_endpoint()
         .count();
     assert_eq!(query_posts, 1);
 }
+
+#[tokio::test]
+async fn page_token_records_sort_and_rejects_a_different_sort() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/query\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "s",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "ids": ["m1"]
+                }, "q0"],
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{
+                        "id": "m1",
+                        "threadId": "t1",
+                        "receivedAt": "2026-02-08T00:00:00Z",
+                        "subject": "Hi1",
+                        "from": [{"name": "Alice", "email": "alice@example.com"}],
+                        "to": [],
+                        "preview": "preview",
+                        "hasAttachment": false,
+                        "mailboxIds": {"inbox": true},
+                        "keywords": {}
+                    }],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let out1 = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "--max",
+            "1",
+            "--sort",
+            "from",
+            "--filter-json",
+            "{}",
+        ])
+        .output()
+        .expect("run");
+    assert!(out1.status.success());
+
+    let v1: serde_json::Value = serde_json::from_slice(&out1.stdout).expect("json");
+    let tok = v1["meta"]["nextPage"]
+        .as_str()
+        .expect("nextPage should exist")
+        .to_string();
+
+    // Same args but default sort => mismatch, no second Email/query.
+    let out2 = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "--max",
+            "1",
+            "--page",
+            &tok,
+            "--filter-json",
+            "{}",
+        ])
+        .output()
+        .expect("run");
+    assert!(!out2.status.success());
+
+    let v2: serde_json::Value = serde_json::from_slice(&out2.stdout).expect("json");
+    assert_eq!(v2["error"]["kind"], "xinUsageError");
+    assert!(
+        v2["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("page token does not match args")
+    );
+}
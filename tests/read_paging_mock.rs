@@ -14,13 +14,13 @@ struct PageToken {
     is_ascending: bool,
     #[allow(dead_code)]
     filter: serde_json::Value,
+    anchor: Option<String>,
+    #[serde(rename = "queryState")]
+    query_state: Option<String>,
 }
 
-#[tokio::test]
-async fn search_emits_next_page_even_when_total_is_missing() {
-    let server = MockServer::start().await;
-
-    let session = json!({
+fn mock_session(server: &MockServer) -> serde_json::Value {
+    json!({
         "capabilities": {
             "urn:ietf:params:jmap:core": {
                 "maxSizeUpload": 1000000,
@@ -58,11 +58,16 @@ async fn search_emits_next_page_even_when_total_is_missing() {
         "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
         "eventSourceUrl": format!("{}/events", server.uri()),
         "state": "s"
-    });
+    })
+}
+
+#[tokio::test]
+async fn search_emits_next_page_even_when_total_is_missing() {
+    let server = MockServer::start().await;
 
     Mock::given(method("GET"))
         .and(path("/.well-known/jmap"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(session))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
         .mount(&server)
         .await;
 
@@ -139,4 +144,219 @@ async fn search_emits_next_page_even_when_total_is_missing() {
     assert_eq!(t.limit, 2);
     assert_eq!(t.collapse_threads, true);
     assert_eq!(t.is_ascending, false);
+    assert_eq!(t.anchor.as_deref(), Some("m2"));
+    assert_eq!(t.query_state.as_deref(), Some("s"));
+}
+
+fn page_token(position: i32, anchor: &str, query_state: &str) -> String {
+    let token = json!({
+        "position": position,
+        "limit": 2,
+        "collapseThreads": true,
+        "isAscending": false,
+        "filter": {},
+        "sort": [{"property": "receivedAt", "isAscending": false}],
+        "anchor": anchor,
+        "queryState": query_state
+    });
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&token).expect("token json"))
+}
+
+fn page_response(query_state: &str, position: i32, ids: &[&str]) -> serde_json::Value {
+    let list: Vec<serde_json::Value> = ids
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "threadId": format!("t_{id}"),
+                "receivedAt": "2026-02-08T00:00:00Z",
+                "subject": format!("Subject {id}"),
+                "from": [{"name": "Alice", "email": "alice@example.com"}],
+                "to": [],
+                "preview": "preview",
+                "hasAttachment": false,
+                "mailboxIds": {"inbox": true},
+                "keywords": {}
+            })
+        })
+        .collect();
+
+    json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Email/query", {
+                "accountId": "A",
+                "queryState": query_state,
+                "canCalculateChanges": false,
+                "position": position,
+                "ids": ids
+            }, "q0"],
+            ["Email/get", {
+                "accountId": "A",
+                "state": "s",
+                "list": list,
+                "notFound": []
+            }, "g0"]
+        ]
+    })
+}
+
+#[tokio::test]
+async fn search_next_page_is_anchored_and_warns_on_drift() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    // New mail arrived: the anchor moved from position 2 to 3.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"anchor\":\"m2\""))
+        .and(body_string_contains("\"anchorOffset\":1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_response(
+            "qs2",
+            3,
+            &["m3", "m4"],
+        )))
+        .mount(&server)
+        .await;
+
+    let tok = page_token(2, "m2", "qs1");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "--max",
+            "2",
+            "--page",
+            &tok,
+            "--filter-json",
+            "{}",
+        ])
+        .output()
+        .expect("run");
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["items"][0]["emailId"], "m3");
+    let warnings = v["meta"]["warnings"].as_array().expect("warnings");
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.as_str().unwrap_or("").contains("qs1 -> qs2"))
+    );
+
+    let next = v["meta"]["nextPage"].as_str().expect("nextPage");
+    let bytes = URL_SAFE_NO_PAD.decode(next).expect("b64");
+    let t: PageToken = serde_json::from_slice(&bytes).expect("token json");
+    assert_eq!(t.position, 5);
+    assert_eq!(t.anchor.as_deref(), Some("m4"));
+    assert_eq!(t.query_state.as_deref(), Some("qs2"));
+
+    // Opt-in: fail instead of warning.
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "--max",
+            "2",
+            "--page",
+            &tok,
+            "--fail-on-drift",
+            "--filter-json",
+            "{}",
+        ])
+        .output()
+        .expect("run");
+    assert!(!output.status.success());
+
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinQueryChanged");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("queryState qs1 -> qs2")
+    );
+    assert!(v["error"]["jmap"].is_null());
+}
+
+#[tokio::test]
+async fn search_falls_back_to_position_when_anchor_is_gone() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"anchor\":\"m2\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["error", {"type": "anchorNotFound"}, "q0"],
+                ["error", {"type": "resultReference"}, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"position\":2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page_response(
+            "qs1",
+            2,
+            &["m3", "m4"],
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tok = page_token(2, "m2", "qs1");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "--max",
+            "2",
+            "--page",
+            &tok,
+            "--filter-json",
+            "{}",
+        ])
+        .output()
+        .expect("run");
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["items"][0]["emailId"], "m3");
+    let warnings = v["meta"]["warnings"].as_array().expect("warnings");
+    assert_eq!(warnings.len(), 1);
+    assert!(
+        warnings[0]
+            .as_str()
+            .unwrap_or("")
+            .contains("resumed at position 2")
+    );
 }
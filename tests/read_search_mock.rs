@@ -797,50 +797,326 @@ async fn filter_json_is_passed_through_and_server_errors_are_returned() {
     );
 }
 
+fn empty_search_response() -> serde_json::Value {
+    json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Email/query", {
+                "accountId": "A",
+                "queryState": "s",
+                "canCalculateChanges": false,
+                "position": 0,
+                "ids": [],
+                "total": 0
+            }, "s0"],
+            ["Email/get", {
+                "accountId": "A",
+                "state": "s",
+                "list": [],
+                "notFound": []
+            }, "s1"]
+        ]
+    })
+}
+
 #[tokio::test]
-async fn search_sugar_parentheses_are_rejected_in_v0() {
+async fn search_sugar_parentheses_and_group_negation_compile_to_filter_operators() {
     let server = MockServer::start().await;
 
     mount_minimal_jmap_session(&server).await;
 
+    let expected_filter = json!({
+        "operator": "AND",
+        "conditions": [
+            {"operator": "OR", "conditions": [{"from": "alice"}, {"from": "bob"}]},
+            {"operator": "NOT", "conditions": [
+                {"operator": "AND", "conditions": [{"subject": "spam"}, {"text": "promo"}]}
+            ]}
+        ]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(
+            serde_json::to_string(&expected_filter).expect("filter json"),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
     let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
         .env("XIN_BASE_URL", server.uri())
         .env("XIN_TOKEN", "test-token")
-        .args(["search", "(from:alice)", "--max", "10"])
+        .args([
+            "search",
+            "(from:alice OR from:bob) AND -(subject:spam promo)",
+            "--max",
+            "10",
+        ])
         .output()
         .expect("run");
 
-    assert!(!output.status.success());
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[tokio::test]
+async fn search_sugar_nested_or_groups_are_flattened() {
+    let server = MockServer::start().await;
+
+    mount_minimal_jmap_session(&server).await;
+
+    let expected_filter = json!({
+        "operator": "OR",
+        "conditions": [{"from": "alice"}, {"from": "bob"}, {"from": "carol"}]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(
+            serde_json::to_string(&expected_filter).expect("filter json"),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "or:(or:(from:alice|from:bob)|from:carol)",
+            "--max",
+            "10",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[tokio::test]
+async fn search_sugar_size_header_keyword_and_thread_terms_are_sent_to_server() {
+    let server = MockServer::start().await;
+
+    mount_minimal_jmap_session(&server).await;
+
+    let expected_filter = json!({
+        "operator": "AND",
+        "conditions": [
+            {"minSize": 5242880},
+            {"header": ["List-Id", "github"]},
+            {"someInThreadHaveKeyword": "$flagged"},
+            {"hasKeyword": "$answered"},
+            {"operator": "NOT", "conditions": [{"hasKeyword": "$label"}]}
+        ]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(
+            serde_json::to_string(&expected_filter).expect("filter json"),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "larger:5M header:List-Id:github thread-has:$flagged is:answered -keyword:$label",
+            "--max",
+            "10",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[tokio::test]
+async fn search_sugar_bare_dates_resolve_in_the_selected_timezone() {
+    let server = MockServer::start().await;
+
+    mount_minimal_jmap_session(&server).await;
+
+    // Midnight in Tokyo (UTC+9) is 15:00Z the day before.
+    let expected_filter = json!({"after": "2025-12-31T15:00:00+00:00"});
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(
+            serde_json::to_string(&expected_filter).expect("filter json"),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    // XIN_TZ, and --tz taking precedence over it.
+    for (env_tz, args) in [
+        ("Asia/Tokyo", vec!["search", "after:2026-01-01"]),
+        (
+            "Europe/London",
+            vec!["--tz", "Asia/Tokyo", "search", "after:2026-01-01"],
+        ),
+    ] {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+            .env("XIN_BASE_URL", server.uri())
+            .env("XIN_TOKEN", "test-token")
+            .env("XIN_TZ", env_tz)
+            .args(&args)
+            .output()
+            .expect("run");
+
+        assert!(
+            output.status.success(),
+            "stdout:\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["--tz", "Mars/Olympus", "search", "after:2026-01-01"])
+        .output()
+        .expect("run");
 
+    assert!(!output.status.success());
     let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
-    assert_eq!(v.get("ok").and_then(|v| v.as_bool()), Some(false));
-    assert_eq!(
-        v.get("error")
-            .and_then(|e| e.get("kind"))
-            .and_then(|k| k.as_str()),
-        Some("xinUsageError")
+    assert_eq!(v["error"]["kind"], "xinUsageError");
+
+    // Queries without bare dates do not need the zone, so an invalid one is not fatal.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("{\"from\":\"x\"}"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .env("XIN_TZ", "Mars/Olympus")
+        .args(["search", "from:x"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
     );
+}
 
-    let msg = v
-        .get("error")
-        .and_then(|e| e.get("message"))
-        .and_then(|m| m.as_str())
-        .unwrap_or("");
-    assert!(msg.contains("parentheses"), "message={msg}");
-    assert!(msg.contains("--filter-json"), "message={msg}");
-    assert!(msg.contains("Example"), "message={msg}");
+#[tokio::test]
+async fn search_sort_keys_are_sent_as_comparators() {
+    let server = MockServer::start().await;
+
+    mount_minimal_jmap_session(&server).await;
+
+    let expected_sort = json!([
+        {"isAscending": false, "keyword": "$flagged", "property": "hasKeyword"},
+        {"isAscending": true, "property": "from"},
+        {"isAscending": true, "property": "size"}
+    ]);
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains(format!(
+            "\"sort\":{}",
+            serde_json::to_string(&expected_sort).expect("sort json")
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "search",
+            "from:x",
+            "--sort",
+            "hasKeyword:$flagged,from,size:asc",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
 }
 
 #[tokio::test]
-async fn search_sugar_group_negation_is_rejected_in_v0() {
+async fn search_unsupported_sort_is_reported_clearly() {
     let server = MockServer::start().await;
 
     mount_minimal_jmap_session(&server).await;
 
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["error", {"type": "unsupportedSort"}, "q0"],
+                ["error", {"type": "resultReference"}, "g0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
     let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
         .env("XIN_BASE_URL", server.uri())
         .env("XIN_TOKEN", "test-token")
-        .args(["search", "-(from:alice)", "--max", "10"])
+        .args(["search", "from:x", "--sort", "sentAt"])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "jmapRequestError");
+    assert_eq!(v["error"]["jmap"]["type"], "unsupportedSort");
+    assert_eq!(v["error"]["jmap"]["method"], "Email/query");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("cannot sort by sentAt")
+    );
+}
+
+#[tokio::test]
+async fn search_sugar_parse_error_reports_position() {
+    let server = MockServer::start().await;
+
+    mount_minimal_jmap_session(&server).await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["search", "from:alice (subject:foo", "--max", "10"])
         .output()
         .expect("run");
 
@@ -860,38 +1136,90 @@ async fn search_sugar_group_negation_is_rejected_in_v0() {
         .and_then(|e| e.get("message"))
         .and_then(|m| m.as_str())
         .unwrap_or("");
-    assert!(msg.contains("group negation"), "message={msg}");
+    assert!(msg.contains("position 12"), "message={msg}");
     assert!(msg.contains("--filter-json"), "message={msg}");
-    assert!(msg.contains("Example"), "message={msg}");
 }
 
 #[tokio::test]
-async fn search_sugar_nested_or_group_is_rejected_in_v0() {
+async fn search_explain_reports_filter_mailboxes_and_query_without_searching() {
     let server = MockServer::start().await;
 
     mount_minimal_jmap_session(&server).await;
 
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Mailbox/get\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {"id": "mbx_inbox", "name": "Inbox", "role": "inbox"},
+                        {"id": "mbx_receipts", "name": "Receipts", "role": null}
+                    ],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    // Email/query must not be called.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(empty_search_response()))
+        .expect(0)
+        .mount(&server)
+        .await;
+
     let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
         .env("XIN_BASE_URL", server.uri())
         .env("XIN_TOKEN", "test-token")
         .args([
             "search",
-            "or:(or:(from:alice|from:bob)|from:carol)",
+            "in:receipts OR in:inbox",
             "--max",
-            "10",
+            "5",
+            "--explain",
         ])
         .output()
         .expect("run");
 
-    assert!(!output.status.success());
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
 
     let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
-    assert_eq!(v.get("ok").and_then(|v| v.as_bool()), Some(false));
-
-    let msg = v
-        .get("error")
-        .and_then(|e| e.get("message"))
-        .and_then(|m| m.as_str())
-        .unwrap_or("");
-    assert!(msg.contains("nested or"), "message={msg}");
+    let explain = v.pointer("/data/explain").expect("data.explain");
+    assert_eq!(
+        explain.get("filterSource").and_then(|v| v.as_str()),
+        Some("sugar")
+    );
+    assert_eq!(
+        explain.get("filter"),
+        Some(&json!({
+            "operator": "OR",
+            "conditions": [{"inMailbox": "mbx_receipts"}, {"inMailbox": "mbx_inbox"}]
+        }))
+    );
+    assert_eq!(
+        explain.get("mailboxes"),
+        Some(&json!([
+            {"term": "in:receipts", "position": 1, "mailboxId": "mbx_receipts", "name": "Receipts", "matchedBy": "nameCaseInsensitive"},
+            {"term": "in:inbox", "position": 16, "mailboxId": "mbx_inbox", "name": "Inbox", "matchedBy": "role"}
+        ]))
+    );
+    assert_eq!(
+        explain
+            .pointer("/emailQuery/limit")
+            .and_then(|v| v.as_u64()),
+        Some(5)
+    );
+    assert_eq!(explain.pointer("/emailQuery/filter"), explain.get("filter"));
+    assert!(v.pointer("/data/items").is_none());
 }
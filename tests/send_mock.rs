@@ -681,3 +681,971 @@ async fn send_html_and_attachment_uploads_and_sets_body_structure() {
         Some(1)
     );
 }
+
+#[tokio::test]
+async fn send_markdown_sends_text_and_html_alternatives() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "mb1", "name": "Drafts", "role": "drafts"}],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    // Both parts come from the same source: HTML with <code>/<a>/<table>, text with the
+    // backticks, the URL and pipe-aligned table rows.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("multipart/alternative"))
+        .and(body_string_contains("<code>xin send</code>"))
+        .and(body_string_contains(
+            "<a href=\\\"https://example.com\\\">docs</a>",
+        ))
+        .and(body_string_contains("<td>apple</td>"))
+        .and(body_string_contains("&lt;b&gt;raw&lt;/b&gt;"))
+        .and(body_string_contains(
+            "Run `xin send`, see docs (https://example.com). <b>raw</b>\\n\\n| Name  | Qty |\\n|-------|-----|\\n| apple | 3   |\\n",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "threadId": "t1"}}
+                }, "e0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "emailId": "m1"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Hi",
+            "--markdown",
+            "Run `xin send`, see [docs](https://example.com). <b>raw</b>\n\n| Name | Qty |\n|---|---|\n| apple | 3 |\n",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn send_markdown_conflicts_with_text() {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", "http://127.0.0.1:9")
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Hi",
+            "--markdown",
+            "*hi*",
+            "--text",
+            "hi",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn send_embeds_local_images_as_inline_parts() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "mb1", "name": "Drafts", "role": "drafts"}],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    // The logo is referenced twice but uploaded once.
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .and(header("content-type", "image/png"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "accountId": "A",
+            "blobId": "b_logo",
+            "type": "image/png",
+            "size": 4
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // HTML and the image sit in a multipart/related; the src points at the part's cid.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("multipart/related"))
+        .and(body_string_contains("\"cid\":\"img1.logo.png@xin\""))
+        .and(body_string_contains("\"disposition\":\"inline\""))
+        .and(body_string_contains(
+            "<img src=\\\"cid:img1.logo.png@xin\\\"><img src=cid:img1.logo.png@xin>",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "threadId": "t1"}}
+                }, "e0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "emailId": "m1"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dir.path().join("logo.png"), b"\x89PNG\r\n\x1a\n").expect("write png");
+    let body = dir.path().join("body.html");
+    std::fs::write(
+        &body,
+        "<img src=\"logo.png\"><img src=./logo.png><img src=\"https://example.com/a.png\">",
+    )
+    .expect("write html");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Hi",
+            "--body-html",
+            &format!("@{}", body.display()),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["uploaded"][0]["cid"], "img1.logo.png@xin");
+}
+
+#[tokio::test]
+async fn send_unusable_inline_image_is_a_usage_error() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "mb1", "name": "Drafts", "role": "drafts"}],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    // Nothing is uploaded or created.
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    // Missing, outside the body's directory, and not an image.
+    let dir = tempfile::tempdir().expect("tempdir");
+    let mail = dir.path().join("mail");
+    std::fs::create_dir(&mail).expect("mkdir");
+    std::fs::write(dir.path().join("secret.png"), b"\x89PNG\r\n\x1a\n").expect("write png");
+    std::fs::write(mail.join("notes.png"), "not an image").expect("write notes");
+    let cases = [
+        ("missing.png", "missing.png"),
+        ("../secret.png", "is outside"),
+        ("notes.png", "is not an image"),
+    ];
+
+    for (src, needle) in cases {
+        let body = mail.join("body.html");
+        std::fs::write(&body, format!("<p>Hi</p><img src=\"{src}\">")).expect("write html");
+
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+            .env("XIN_BASE_URL", server.uri())
+            .env("XIN_TOKEN", "test-token")
+            .args([
+                "send",
+                "--to",
+                "to@example.com",
+                "--subject",
+                "Hi",
+                "--body-html",
+                &format!("@{}", body.display()),
+            ])
+            .output()
+            .expect("run");
+
+        assert!(!output.status.success(), "{src}");
+        let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+        assert_eq!(v["error"]["kind"], "xinUsageError");
+        let message = v["error"]["message"].as_str().unwrap_or("");
+        assert!(message.contains(needle), "{src}: {message}");
+    }
+}
+
+#[tokio::test]
+async fn send_eml_imports_and_submits_with_header_envelope() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "mb1", "name": "Drafts", "role": "drafts"}],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {"id": "i1", "name": "Me", "email": "me@example.com"},
+                        {"id": "i2", "name": "Sales", "email": "sales@example.com"}
+                    ],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .and(header("content-type", "message/rfc822"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "accountId": "A",
+            "blobId": "b_eml",
+            "type": "message/rfc822",
+            "size": 200
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/import\""))
+        .and(body_string_contains("\"blobId\":\"b_eml\""))
+        .and(body_string_contains("\"mailboxIds\":{\"mb1\":true}"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/import", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "blobId": "b_eml", "threadId": "t1", "size": 200}}
+                }, "i0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // The message is never rebuilt through Email/set.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    // Identity from the From header; envelope from To + Cc + Bcc.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .and(body_string_contains("\"identityId\":\"i2\""))
+        .and(body_string_contains(
+            "\"mailFrom\":{\"email\":\"sales@example.com\",\"parameters\":null}",
+        ))
+        .and(body_string_contains(
+            "\"rcptTo\":[{\"email\":\"a@example.com\",\"parameters\":null},{\"email\":\"jane@example.com\",\"parameters\":null},{\"email\":\"hidden@example.com\",\"parameters\":null},{\"email\":\"other@example.com\",\"parameters\":null}]",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "emailId": "m1"}}
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut eml = NamedTempFile::new().expect("tmp");
+    eml.write_all(
+        b"From: Sales <sales@example.com>\r\nTo: a@example.com\r\nCc: \"Doe, Jane\"\r\n <jane@example.com>\r\nBcc: hidden@example.com,\r\n other@example.com\r\nSubject: Report\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=x\r\n\r\n--x\r\nContent-Type: text/plain\r\n\r\nHi\r\n--x--\r\n",
+    )
+    .expect("write eml");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["send", "--eml", &format!("@{}", eml.path().display())])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["draft"]["emailId"], "m1");
+    assert_eq!(v["data"]["identity"]["rule"], "exact");
+    assert_eq!(v["data"]["imported"]["blobId"], "b_eml");
+
+    // The uploaded message has no Bcc header; the other fields are untouched.
+    let requests = server.received_requests().await.expect("requests");
+    let upload = requests
+        .iter()
+        .find(|r| r.url.path() == "/upload/A")
+        .expect("upload request");
+    let uploaded = String::from_utf8_lossy(&upload.body);
+    assert!(!uploaded.contains("Bcc"), "{uploaded}");
+    assert!(!uploaded.contains("other@example.com"), "{uploaded}");
+    assert!(uploaded.starts_with(
+        "From: Sales <sales@example.com>\r\nTo: a@example.com\r\nCc: \"Doe, Jane\"\r\n <jane@example.com>\r\nSubject: Report\r\n"
+    ));
+    assert!(v["meta"]["warnings"].is_null());
+}
+
+#[test]
+fn send_eml_without_recipients_is_a_usage_error() {
+    let mut eml = NamedTempFile::new().expect("tmp");
+    eml.write_all(b"From: me@example.com\r\nSubject: Hi\r\n\r\nHi\r\n")
+        .expect("write eml");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", "http://127.0.0.1:9")
+        .env("XIN_TOKEN", "test-token")
+        .args(["send", "--eml", &format!("@{}", eml.path().display())])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinUsageError");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("--rcpt-to")
+    );
+}
+
+async fn mount_attach_blob_source(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(server)))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "mb1", "name": "Drafts", "role": "drafts"}],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    // The source email is read once for its attachment metadata.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/get"))
+        .and(body_string_contains("\"m9\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{
+                        "id": "m9",
+                        "attachments": [{
+                            "partId": "2",
+                            "blobId": "b_pdf",
+                            "size": 1234,
+                            "name": "invoice.pdf",
+                            "type": "application/pdf",
+                            "disposition": "attachment"
+                        }]
+                    }],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+
+    // Nothing is downloaded or uploaded.
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn send_attach_blob_references_existing_blobs() {
+    let server = MockServer::start().await;
+    mount_attach_blob_source(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("\"blobId\":\"b_pdf\""))
+        .and(body_string_contains("\"name\":\"invoice.pdf\""))
+        .and(body_string_contains("\"type\":\"application/pdf\""))
+        .and(body_string_contains("\"blobId\":\"b_raw\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "threadId": "t1"}}
+                }, "e0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "emailId": "m1"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Invoice",
+            "--attach-blob",
+            "m9:b_pdf",
+            "--attach-blob",
+            "b_raw",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["uploaded"][0]["blobId"], "b_pdf");
+    assert_eq!(v["data"]["uploaded"][0]["size"], 1234);
+    assert_eq!(v["data"]["uploaded"][1]["type"], "application/octet-stream");
+    // A bare blobId has no known size.
+    assert!(v["data"]["uploaded"][1]["size"].is_null());
+}
+
+#[tokio::test]
+async fn send_attach_blob_unknown_blob_is_a_usage_error() {
+    let server = MockServer::start().await;
+    mount_attach_blob_source(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Invoice",
+            "--attach-blob",
+            "m9:b_other",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinUsageError");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("not an attachment of email m9")
+    );
+}
+
+async fn mount_session_drafts_identity(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(server)))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "mb1", "name": "Drafts", "role": "drafts"}],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn send_attach_sniffs_types_and_accepts_overrides() {
+    let server = MockServer::start().await;
+    mount_session_drafts_identity(&server).await;
+
+    // No extension: sniffed from the bytes.
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .and(header("content-type", "application/pdf"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "accountId": "A",
+            "blobId": "b_scan",
+            "type": "application/pdf",
+            "size": 9
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // `;type=` wins over the `.dat` extension.
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .and(header("content-type", "text/calendar"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "accountId": "A",
+            "blobId": "b_ics",
+            "type": "text/calendar",
+            "size": 15
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .and(body_string_contains("\"name\":\"scan\""))
+        .and(body_string_contains("\"name\":\"invite.ics\""))
+        .and(body_string_contains("\"type\":\"text/calendar\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "threadId": "t1"}}
+                }, "e0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "s1", "emailId": "m1"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let scan = dir.path().join("scan");
+    std::fs::write(&scan, b"%PDF-1.7\n").expect("write scan");
+    let data = dir.path().join("event.dat");
+    std::fs::write(&data, b"BEGIN:VCALENDAR").expect("write ics");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Files",
+            "--attach",
+            &scan.display().to_string(),
+            "--attach",
+            &format!("{};type=text/calendar;name=invite.ics", data.display()),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[tokio::test]
+async fn send_attach_over_max_size_upload_fails_before_uploading() {
+    let server = MockServer::start().await;
+    mount_session_drafts_identity(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    // The mock session allows 1000000-byte uploads; the small file comes first.
+    let dir = tempfile::tempdir().expect("tempdir");
+    let small = dir.path().join("small.txt");
+    std::fs::write(&small, b"ok").expect("write small");
+    let big = dir.path().join("big.bin");
+    std::fs::write(&big, vec![0u8; 1_000_001]).expect("write big");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Big",
+            "--attach",
+            &small.display().to_string(),
+            "--attach",
+            &big.display().to_string(),
+        ])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinTooLarge");
+    assert!(v["error"].get("jmap").is_none());
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .ends_with("up to 1000000 bytes (maxSizeUpload)")
+    );
+}
+
+#[tokio::test]
+async fn send_eml_over_max_size_upload_fails_before_uploading() {
+    let server = MockServer::start().await;
+    mount_session_drafts_identity(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/upload/A"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let mut eml = NamedTempFile::new().expect("tmp");
+    eml.write_all(b"From: me@example.com\r\nTo: to@example.com\r\nSubject: Big\r\n\r\n")
+        .expect("write eml");
+    eml.write_all(&vec![b'a'; 1_000_001]).expect("write eml");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["send", "--eml", &format!("@{}", eml.path().display())])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinTooLarge");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .ends_with("up to 1000000 bytes (maxSizeUpload)")
+    );
+}
@@ -0,0 +1,577 @@
+use assert_cmd::Command;
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn mock_session(server: &MockServer, submission: serde_json::Value) -> serde_json::Value {
+    json!({
+        "capabilities": {
+            "urn:ietf:params:jmap:core": {
+                "maxSizeUpload": 1000000,
+                "maxConcurrentUpload": 4,
+                "maxSizeRequest": 1000000,
+                "maxConcurrentRequests": 4,
+                "maxCallsInRequest": 16,
+                "maxObjectsInGet": 256,
+                "maxObjectsInSet": 256,
+                "collationAlgorithms": ["i;unicode-casemap"]
+            },
+            "urn:ietf:params:jmap:mail": {},
+            "urn:ietf:params:jmap:submission": submission
+        },
+        "accounts": {
+            "A": {
+                "name": "mock",
+                "isPersonal": true,
+                "isReadOnly": false,
+                "accountCapabilities": {
+                    "urn:ietf:params:jmap:mail": {},
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:submission": submission
+                }
+            }
+        },
+        "primaryAccounts": {
+            "urn:ietf:params:jmap:mail": "A",
+            "urn:ietf:params:jmap:core": "A",
+            "urn:ietf:params:jmap:submission": "A"
+        },
+        "username": "me",
+        "apiUrl": format!("{}/jmap", server.uri()),
+        "downloadUrl": format!("{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", server.uri()),
+        "uploadUrl": format!("{}/upload/{{accountId}}", server.uri()),
+        "eventSourceUrl": format!("{}/events", server.uri()),
+        "state": "s"
+    })
+}
+
+fn future_release() -> serde_json::Value {
+    json!({"maxDelayedSend": 86400, "submissionExtensions": {"FUTURERELEASE": []}})
+}
+
+async fn mount_session(server: &MockServer, submission: serde_json::Value) {
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(server, submission)))
+        .mount(server)
+        .await;
+}
+
+async fn mount_mailboxes_and_identity(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Mailbox/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Mailbox/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [
+                        {"id": "mb1", "name": "Drafts", "role": "drafts"},
+                        {"id": "mb2", "name": "Sent", "role": "sent"}
+                    ],
+                    "notFound": []
+                }, "m0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Identity/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Identity/get", {
+                    "accountId": "A",
+                    "state": "s",
+                    "list": [{"id": "i1", "name": "Me", "email": "me@example.com"}],
+                    "notFound": []
+                }, "i0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+fn submission_get_response(undo_status: &str) -> serde_json::Value {
+    json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["EmailSubmission/get", {
+                "accountId": "A",
+                "state": "s",
+                "list": [{
+                    "id": "s1",
+                    "identityId": "i1",
+                    "emailId": "m1",
+                    "threadId": "t1",
+                    "sendAt": "2026-03-01T00:10:00Z",
+                    "undoStatus": undo_status
+                }],
+                "notFound": []
+            }, "g0"]
+        ]
+    })
+}
+
+#[tokio::test]
+async fn send_at_holds_the_submission_with_futurerelease() {
+    let server = MockServer::start().await;
+    mount_session(&server, future_release()).await;
+    mount_mailboxes_and_identity(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "threadId": "t1"}}
+                }, "e0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .and(body_string_contains(
+            "\"mailFrom\":{\"email\":\"me@example.com\",\"parameters\":{\"HOLDFOR\":\"600\"}}",
+        ))
+        .and(body_string_contains(
+            "\"rcptTo\":[{\"email\":\"to@example.com\",\"parameters\":null},{\"email\":\"cc@example.com\",\"parameters\":null}]",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "created": {"c0": {
+                        "id": "s1",
+                        "sendAt": "2026-03-01T00:10:00Z",
+                        "undoStatus": "pending"
+                    }}
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--cc",
+            "cc@example.com",
+            "--subject",
+            "Hi",
+            "--text",
+            "Hello",
+            "--send-at",
+            "+10m",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["submission"]["id"], "s1");
+    assert_eq!(v["data"]["submission"]["sendAt"], "2026-03-01T00:10:00Z");
+    assert_eq!(v["data"]["submission"]["undoStatus"], "pending");
+}
+
+#[tokio::test]
+async fn send_at_without_futurerelease_fails_before_creating_a_draft() {
+    let server = MockServer::start().await;
+    mount_session(&server, json!({})).await;
+    mount_mailboxes_and_identity(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/set"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Hi",
+            "--text",
+            "Hello",
+            "--send-at",
+            "+10m",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinNotSupported");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("FUTURERELEASE")
+    );
+}
+
+#[tokio::test]
+async fn submissions_list_pending_queries_and_gets() {
+    let server = MockServer::start().await;
+    mount_session(&server, future_release()).await;
+
+    let mut response = submission_get_response("pending");
+    response["methodResponses"].as_array_mut().unwrap().insert(
+        0,
+        json!(["EmailSubmission/query", {
+                "accountId": "A",
+                "queryState": "q",
+                "canCalculateChanges": false,
+                "position": 0,
+                "ids": ["s1"]
+            }, "q0"]),
+    );
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/query"))
+        .and(body_string_contains(
+            "\"filter\":{\"undoStatus\":\"pending\"}",
+        ))
+        .and(body_string_contains("\"resultOf\":\"q0\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["submissions", "list", "--pending"])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["command"], "submissions.list");
+    assert_eq!(v["data"]["items"][0]["id"], "s1");
+    assert_eq!(v["data"]["items"][0]["undoStatus"], "pending");
+}
+
+#[tokio::test]
+async fn submissions_cancel_sets_canceled_and_restores_the_draft() {
+    let server = MockServer::start().await;
+    mount_session(&server, future_release()).await;
+    mount_mailboxes_and_identity(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(submission_get_response("pending")))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .and(body_string_contains("\"update\":{\"s1\":{\"undoStatus\":\"canceled\"}}"))
+        .and(body_string_contains(
+            "\"onSuccessUpdateEmail\":{\"s1\":{\"keywords/$draft\":true,\"mailboxIds/mb1\":true,\"mailboxIds/mb2\":null}}",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "updated": {"s1": null}
+                }, "s0"],
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "updated": {"m1": null}
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["submissions", "cancel", "s1"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["submission"]["undoStatus"], "canceled");
+    assert_eq!(v["data"]["draft"]["emailId"], "m1");
+    assert_eq!(v["data"]["draft"]["restored"], true);
+}
+
+#[tokio::test]
+async fn submissions_cancel_rejects_a_final_submission() {
+    let server = MockServer::start().await;
+    mount_session(&server, future_release()).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(submission_get_response("final")))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["submissions", "cancel", "s1"])
+        .output()
+        .expect("run");
+
+    assert!(!output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["error"]["kind"], "xinUsageError");
+    assert!(
+        v["error"]["message"]
+            .as_str()
+            .unwrap_or("")
+            .contains("is final")
+    );
+}
+
+async fn mount_send_until_submission(server: &MockServer) {
+    mount_mailboxes_and_identity(server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("\"Email/set\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s",
+                    "created": {"c0": {"id": "m1", "threadId": "t1"}}
+                }, "e0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/set"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/set", {
+                    "accountId": "A",
+                    "oldState": "s",
+                    "newState": "s1",
+                    "created": {"c0": {"id": "s1", "undoStatus": "final"}}
+                }, "s0"]
+            ]
+        })))
+        .mount(server)
+        .await;
+}
+
+fn delivery_get_response(delivered: &str) -> serde_json::Value {
+    let mut response = submission_get_response("final");
+    response["methodResponses"][0][1]["list"][0]["deliveryStatus"] = json!({
+        "to@example.com": {
+            "smtpReply": "250 2.0.0 OK",
+            "delivered": delivered,
+            "displayed": "unknown"
+        }
+    });
+    response["methodResponses"][0][1]["list"][0]["dsnBlobIds"] = json!(["b-dsn"]);
+    response["methodResponses"][0][1]["list"][0]["mdnBlobIds"] = json!([]);
+    response
+}
+
+#[tokio::test]
+async fn send_wait_delivery_reports_final_delivery_status() {
+    let server = MockServer::start().await;
+    mount_session(&server, json!({})).await;
+    mount_send_until_submission(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/get"))
+        .and(body_string_contains("deliveryStatus"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(delivery_get_response("yes")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Hi",
+            "--text",
+            "Hello",
+            "--wait-delivery",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    let delivery = &v["data"]["delivery"];
+    assert_eq!(delivery["state"], "delivered");
+    assert_eq!(delivery["final"], true);
+    assert_eq!(delivery["timedOut"], false);
+    assert_eq!(delivery["recipients"][0]["email"], "to@example.com");
+    assert_eq!(delivery["recipients"][0]["smtpReply"], "250 2.0.0 OK");
+    assert_eq!(delivery["dsnBlobIds"], json!(["b-dsn"]));
+}
+
+#[tokio::test]
+async fn send_wait_delivery_times_out_with_a_warning() {
+    let server = MockServer::start().await;
+    mount_session(&server, json!({})).await;
+    mount_send_until_submission(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(delivery_get_response("queued")))
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "send",
+            "--to",
+            "to@example.com",
+            "--subject",
+            "Hi",
+            "--text",
+            "Hello",
+            "--wait-delivery",
+            "--wait-timeout",
+            "0",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["data"]["delivery"]["state"], "queued");
+    assert_eq!(v["data"]["delivery"]["timedOut"], true);
+    let warnings = v["meta"]["warnings"].as_array().expect("warnings");
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.as_str().unwrap_or("").contains("still queued"))
+    );
+}
+
+#[tokio::test]
+async fn submissions_changes_hydrates_created_and_updated() {
+    let server = MockServer::start().await;
+    mount_session(&server, json!({})).await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/changes"))
+        .and(body_string_contains("\"sinceState\":\"s0\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/changes", {
+                    "accountId": "A",
+                    "oldState": "s0",
+                    "newState": "s1",
+                    "hasMoreChanges": false,
+                    "created": [],
+                    "updated": ["s1"],
+                    "destroyed": ["s9"]
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/get"))
+        .and(body_string_contains("\"ids\":[\"s1\"]"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(delivery_get_response("no")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["submissions", "changes", "--since", "s0"])
+        .output()
+        .expect("run");
+
+    assert!(output.status.success());
+    let v: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(v["command"], "submissions.changes");
+    assert_eq!(v["data"]["newState"], "s1");
+    assert_eq!(v["data"]["changes"]["updated"], json!(["s1"]));
+    assert_eq!(v["data"]["changes"]["destroyed"], json!(["s9"]));
+    assert_eq!(
+        v["data"]["submissions"][0]["deliveryStatus"]["to@example.com"]["delivered"],
+        "no"
+    );
+}
@@ -1,6 +1,6 @@
 use assert_cmd::Command;
 use serde_json::json;
-use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::matchers::{body_string_contains, header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 fn mock_session(server: &MockServer) -> serde_json::Value {
@@ -45,6 +45,79 @@ fn mock_session(server: &MockServer) -> serde_json::Value {
     })
 }
 
+/// Mount an `Email/changes` response for `since` -> `new` reporting one created email.
+async fn mount_email_changes(server: &MockServer, since: &str, new: &str, created: &str) {
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/changes"))
+        .and(body_string_contains(format!("\"sinceState\":\"{since}\"")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/changes", {
+                    "accountId": "A",
+                    "oldState": since,
+                    "newState": new,
+                    "hasMoreChanges": false,
+                    "created": [created],
+                    "updated": [],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+/// Run a long-lived `xin watch` until a stdout line contains `needle` (or 20s pass), then
+/// kill it. Returns the stdout lines read.
+async fn watch_until(
+    server_uri: String,
+    args: Vec<&'static str>,
+    needle: &'static str,
+) -> Vec<String> {
+    tokio::task::spawn_blocking(move || {
+        use std::io::BufRead;
+
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+            .env("XIN_BASE_URL", server_uri)
+            .env("XIN_TOKEN", "test-token")
+            .args(args)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("spawn");
+        let stdout = child.stdout.take().expect("stdout");
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(20);
+        let mut lines = Vec::new();
+        while let Ok(line) =
+            rx.recv_timeout(deadline.saturating_duration_since(std::time::Instant::now()))
+        {
+            let done = line.contains(needle);
+            lines.push(line);
+            if done {
+                break;
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        lines
+    })
+    .await
+    .expect("watch task")
+}
+
 #[tokio::test]
 async fn watch_once_emits_ready_and_change_events_and_writes_checkpoint() {
     let server = MockServer::start().await;
@@ -306,3 +379,718 @@ async fn watch_checkpoint_resumes_and_ignores_since_when_not_provided() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("\"id\":\"m2\""), "stdout: {stdout}");
 }
+
+#[tokio::test]
+async fn watch_websocket_transport_falls_back_to_polling_without_capability() {
+    let server = MockServer::start().await;
+
+    // The mock session does not advertise urn:ietf:params:jmap:websocket.
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    let changes_response = json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Email/changes", {
+                "accountId": "A",
+                "oldState": "S0",
+                "newState": "S1",
+                "hasMoreChanges": false,
+                "created": ["m_new"],
+                "updated": [],
+                "destroyed": []
+            }, "c0"]
+        ]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/changes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(changes_response))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "watch",
+            "--no-envelope",
+            "--transport",
+            "websocket",
+            "--since",
+            "S0",
+            "--once",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    let ready: serde_json::Value = serde_json::from_str(lines[0]).expect("ready json");
+    assert_eq!(ready.get("type").and_then(|v| v.as_str()), Some("ready"));
+    assert_eq!(
+        ready.get("transport").and_then(|v| v.as_str()),
+        Some("poll")
+    );
+    assert!(stdout.contains("\"id\":\"m_new\""), "stdout: {stdout}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn watch_websocket_push_state_change_triggers_email_changes() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+    // RFC 8887 endpoint: records the upgrade's Authorization header and the first frame,
+    // then pushes one StateChange.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let ws_url = format!("ws://{}/ws", listener.local_addr().expect("addr"));
+    let (seen_tx, seen_rx) = std::sync::mpsc::channel::<(String, String)>();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        let mut auth = String::new();
+        // The handshake callback's error type is tungstenite's, not ours.
+        #[allow(clippy::result_large_err)]
+        let callback = |req: &Request, mut resp: Response| -> Result<Response, ErrorResponse> {
+            auth = req
+                .headers()
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            resp.headers_mut()
+                .insert("Sec-WebSocket-Protocol", HeaderValue::from_static("jmap"));
+            Ok(resp)
+        };
+        let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+            .await
+            .expect("handshake");
+
+        let enable = match ws.next().await {
+            Some(Ok(Message::Text(t))) => t.to_string(),
+            other => panic!("expected WebSocketPushEnable, got {other:?}"),
+        };
+        let _ = seen_tx.send((auth, enable));
+
+        let change = json!({"@type": "StateChange", "changed": {"A": {"Email": "S2"}}});
+        ws.send(Message::text(change.to_string()))
+            .await
+            .expect("push");
+        // Keep the socket open until xin is stopped.
+        while let Some(Ok(_)) = ws.next().await {}
+    });
+
+    let server = MockServer::start().await;
+    let mut session = mock_session(&server);
+    session["capabilities"]["urn:ietf:params:jmap:websocket"] =
+        json!({"url": ws_url, "supportsPush": true});
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(session))
+        .mount(&server)
+        .await;
+
+    // The first fetch runs right away; the second only happens because of the push (the
+    // poll interval is far longer than the test).
+    mount_email_changes(&server, "S0", "S1", "m1").await;
+    mount_email_changes(&server, "S1", "S2", "m2").await;
+
+    let lines = watch_until(
+        server.uri(),
+        vec![
+            "watch",
+            "--no-envelope",
+            "--transport",
+            "websocket",
+            "--since",
+            "S0",
+            "--interval-ms",
+            "600000",
+        ],
+        "\"id\":\"m2\"",
+    )
+    .await;
+
+    let ready: serde_json::Value = serde_json::from_str(&lines[0]).expect("ready json");
+    assert_eq!(ready["transport"], "websocket");
+    assert!(
+        lines.iter().any(|l| l.contains("\"id\":\"m2\"")),
+        "stdout: {lines:?}"
+    );
+
+    let (auth, enable) = seen_rx
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .expect("websocket frames");
+    assert_eq!(auth, "Bearer test-token");
+    let enable: serde_json::Value = serde_json::from_str(&enable).expect("enable json");
+    assert_eq!(
+        enable,
+        json!({"@type": "WebSocketPushEnable", "dataTypes": ["Email"]})
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn watch_websocket_keeps_polling_when_the_socket_stops_responding() {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+    // Accepts the push subscription, then goes silent: no pushes, no Pongs, no close.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let ws_url = format!("ws://{}/ws", listener.local_addr().expect("addr"));
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        // The handshake callback's error type is tungstenite's, not ours.
+        #[allow(clippy::result_large_err)]
+        let callback = |_: &Request, mut resp: Response| -> Result<Response, ErrorResponse> {
+            resp.headers_mut()
+                .insert("Sec-WebSocket-Protocol", HeaderValue::from_static("jmap"));
+            Ok(resp)
+        };
+        let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+            .await
+            .expect("handshake");
+        let _enable = ws.next().await;
+        std::future::pending::<()>().await;
+        drop(ws);
+    });
+
+    let server = MockServer::start().await;
+    let mut session = mock_session(&server);
+    session["capabilities"]["urn:ietf:params:jmap:websocket"] =
+        json!({"url": ws_url, "supportsPush": true});
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(session))
+        .mount(&server)
+        .await;
+
+    // No push ever arrives: the second fetch only happens on the poll timer.
+    mount_email_changes(&server, "S0", "S1", "m1").await;
+    mount_email_changes(&server, "S1", "S2", "m2").await;
+
+    let lines = watch_until(
+        server.uri(),
+        vec![
+            "watch",
+            "--no-envelope",
+            "--transport",
+            "websocket",
+            "--since",
+            "S0",
+            "--interval-ms",
+            "300",
+            "--jitter-ms",
+            "0",
+        ],
+        "\"id\":\"m2\"",
+    )
+    .await;
+
+    let ready: serde_json::Value = serde_json::from_str(&lines[0]).expect("ready json");
+    assert_eq!(ready["transport"], "websocket");
+    assert!(
+        lines.iter().any(|l| l.contains("\"id\":\"m2\"")),
+        "stdout: {lines:?}"
+    );
+}
+
+#[tokio::test]
+async fn watch_eventsource_transport_connects_to_event_source_url() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    // SSE stub: a ping followed by a StateChange.
+    let sse_body = concat!(
+        "event: ping\n",
+        "data: {\"interval\":30}\n",
+        "\n",
+        "event: state\n",
+        "data: {\"@type\":\"StateChange\",\"changed\":{\"A\":{\"Email\":\"S2\"}}}\n",
+        "\n",
+    );
+
+    Mock::given(method("GET"))
+        .and(path("/events"))
+        .and(header("accept", "text/event-stream"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse_body),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let changes_response = json!({
+        "sessionState": "s",
+        "methodResponses": [
+            ["Email/changes", {
+                "accountId": "A",
+                "oldState": "S0",
+                "newState": "S1",
+                "hasMoreChanges": false,
+                "created": ["m_new"],
+                "updated": [],
+                "destroyed": []
+            }, "c0"]
+        ]
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/changes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(changes_response))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "watch",
+            "--no-envelope",
+            "--transport",
+            "eventsource",
+            "--since",
+            "S0",
+            "--once",
+        ])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    let ready: serde_json::Value = serde_json::from_str(lines[0]).expect("ready json");
+    assert_eq!(ready.get("type").and_then(|v| v.as_str()), Some("ready"));
+    assert_eq!(
+        ready.get("transport").and_then(|v| v.as_str()),
+        Some("eventsource")
+    );
+    assert!(stdout.contains("\"id\":\"m_new\""), "stdout: {stdout}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn watch_eventsource_state_change_triggers_email_changes() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    let sse_body = concat!(
+        "event: state\n",
+        "data: {\"@type\":\"StateChange\",\"changed\":{\"A\":{\"Email\":\"S2\"}}}\n",
+        "\n",
+    );
+    Mock::given(method("GET"))
+        .and(path("/events"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse_body),
+        )
+        .mount(&server)
+        .await;
+
+    // The first fetch runs right away; the second only happens because of the pushed
+    // StateChange (the poll interval is far longer than the test).
+    mount_email_changes(&server, "S0", "S1", "m1").await;
+    mount_email_changes(&server, "S1", "S2", "m2").await;
+
+    let lines = watch_until(
+        server.uri(),
+        vec![
+            "watch",
+            "--no-envelope",
+            "--transport",
+            "eventsource",
+            "--since",
+            "S0",
+            "--interval-ms",
+            "600000",
+        ],
+        "\"id\":\"m2\"",
+    )
+    .await;
+
+    let ready: serde_json::Value = serde_json::from_str(&lines[0]).expect("ready json");
+    assert_eq!(ready["transport"], "eventsource");
+    let ids: Vec<String> = lines
+        .iter()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .filter(|e| e["type"] == "email.change")
+        .map(|e| e["id"].as_str().unwrap_or("").to_string())
+        .collect();
+    assert_eq!(ids, vec!["m1", "m2"]);
+}
+
+#[tokio::test]
+async fn watch_types_tracks_each_type_state_in_checkpoint() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    // Bootstrap: current Submission state.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/get", {
+                    "accountId": "A",
+                    "state": "U0",
+                    "list": [],
+                    "notFound": []
+                }, "s0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/changes"))
+        .and(body_string_contains("\"sinceState\":\"U0\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/changes", {
+                    "accountId": "A",
+                    "oldState": "U0",
+                    "newState": "U1",
+                    "hasMoreChanges": false,
+                    "created": [],
+                    "updated": ["sub1"],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("EmailSubmission/changes"))
+        .and(body_string_contains("\"sinceState\":\"U1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["EmailSubmission/changes", {
+                    "accountId": "A",
+                    "oldState": "U1",
+                    "newState": "U1",
+                    "hasMoreChanges": false,
+                    "created": [],
+                    "updated": [],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // Email: S0 -> S1, then S1 -> S1 on resume.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/changes"))
+        .and(body_string_contains("\"sinceState\":\"S0\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/changes", {
+                    "accountId": "A",
+                    "oldState": "S0",
+                    "newState": "S1",
+                    "hasMoreChanges": false,
+                    "created": ["m_new"],
+                    "updated": [],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/changes"))
+        .and(body_string_contains("\"sinceState\":\"S1\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/changes", {
+                    "accountId": "A",
+                    "oldState": "S1",
+                    "newState": "S1",
+                    "hasMoreChanges": false,
+                    "created": [],
+                    "updated": [],
+                    "destroyed": []
+                }, "c0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tmp = tempfile::tempdir().expect("tmp");
+    let checkpoint = tmp.path().join("watch.token");
+
+    // Run 1: email resumes from an email-only checkpoint; submission bootstraps.
+    let legacy_token = {
+        use base64::Engine;
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(json!({"sinceState": "S0", "maxChanges": 100}).to_string())
+    };
+    std::fs::write(&checkpoint, legacy_token).expect("write checkpoint");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args([
+            "watch",
+            "--no-envelope",
+            "--types",
+            "email,submission",
+            "--once",
+            "--checkpoint",
+        ])
+        .arg(&checkpoint)
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("ndjson"))
+        .collect();
+    assert_eq!(
+        lines[0]
+            .pointer("/sinceStates/submission")
+            .and_then(|v| v.as_str()),
+        Some("U0")
+    );
+    assert!(stdout.contains("\"id\":\"m_new\""), "stdout: {stdout}");
+
+    let sub = lines
+        .iter()
+        .find(|v| v.get("type").and_then(|t| t.as_str()) == Some("submission.changes"))
+        .expect("submission.changes event");
+    assert_eq!(
+        sub.pointer("/updated/0").and_then(|v| v.as_str()),
+        Some("sub1")
+    );
+    assert_eq!(sub.get("newState").and_then(|v| v.as_str()), Some("U1"));
+
+    // Run 2: both types resume from the checkpoint (S1 / U1); no --types needed.
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["watch", "--no-envelope", "--once", "--checkpoint"])
+        .arg(&checkpoint)
+        .output()
+        .expect("run2");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ready: serde_json::Value =
+        serde_json::from_str(stdout.lines().next().expect("ready")).expect("ready json");
+    assert_eq!(ready.get("types"), Some(&json!(["email", "submission"])));
+}
+
+#[tokio::test]
+async fn watch_query_only_reports_matching_created_and_updated_emails() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/.well-known/jmap"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(mock_session(&server)))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/changes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/changes", {
+                    "accountId": "A",
+                    "oldState": "S0",
+                    "newState": "S1",
+                    "hasMoreChanges": false,
+                    "created": ["m1", "m2"],
+                    "updated": ["m3"],
+                    "destroyed": ["m4"]
+                }, "c0"]
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    // Each receivedAt of the changed emails gets its own Email/query below.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/get"))
+        .and(body_string_contains("\"receivedAt\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/get", {
+                    "accountId": "A",
+                    "state": "S1",
+                    "list": [
+                        {"id": "m1", "receivedAt": "2026-03-01T10:00:00Z"},
+                        {"id": "m2", "receivedAt": "2026-03-01T11:00:00Z"},
+                        {"id": "m3", "receivedAt": "2026-02-01T09:00:00Z"}
+                    ],
+                    "notFound": []
+                }, "g0"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // One request, one second-wide query per email, oldest first; nothing in between.
+    Mock::given(method("POST"))
+        .and(path("/jmap"))
+        .and(body_string_contains("Email/query"))
+        .and(body_string_contains("{\"from\":\"vendor\"}"))
+        .and(body_string_contains(
+            "{\"after\":\"2026-02-01T09:00:00+00:00\"},{\"before\":\"2026-02-01T09:00:01+00:00\"}",
+        ))
+        .and(body_string_contains(
+            "{\"after\":\"2026-03-01T10:00:00+00:00\"},{\"before\":\"2026-03-01T10:00:01+00:00\"}",
+        ))
+        .and(body_string_contains(
+            "{\"after\":\"2026-03-01T11:00:00+00:00\"},{\"before\":\"2026-03-01T11:00:01+00:00\"}",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "sessionState": "s",
+            "methodResponses": [
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "ids": [],
+                    "total": 0
+                }, "q0"],
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "ids": ["other"],
+                    "total": 1
+                }, "q1"],
+                ["Email/query", {
+                    "accountId": "A",
+                    "queryState": "q",
+                    "canCalculateChanges": false,
+                    "position": 0,
+                    "ids": ["m2"],
+                    "total": 1
+                }, "q2"]
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("xin"))
+        .env("XIN_BASE_URL", server.uri())
+        .env("XIN_TOKEN", "test-token")
+        .args(["watch", "--since", "S0", "--once", "--query", "from:vendor"])
+        .output()
+        .expect("run");
+
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect();
+
+    assert_eq!(events[0]["type"], "ready");
+    assert_eq!(events[0]["query"], "from:vendor");
+
+    let changes: Vec<(String, String)> = events
+        .iter()
+        .filter(|e| e["type"] == "email.change")
+        .map(|e| {
+            (
+                e["changeType"].as_str().unwrap_or("").to_string(),
+                e["id"].as_str().unwrap_or("").to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("created".to_string(), "m2".to_string()),
+            ("destroyed".to_string(), "m4".to_string()),
+        ]
+    );
+}